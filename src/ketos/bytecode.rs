@@ -17,17 +17,19 @@
 //! with a single opcode, e.g. the `CONST_0` opcode replaces the two-byte
 //! sequence `CONST`, followed by operand `0`.
 
+use std::fmt::Write;
+
 use crate::compile::CompileError;
 use crate::exec::ExecError;
-use crate::function::Arity;
-use crate::name::Name;
+use crate::function::{plural, Arity};
+use crate::name::{debug_names, get_standard_name, Name, NameStore};
 use crate::value::Value;
 
 /// Bytecode version number, indicating the version of the most recent breaking
 /// change to the bytecode format. The version represents a `ketos` version
 /// number, e.g. `0x01_02_03_00` corresponds to version `1.2.3`.
 /// (The least significant 8 bits don't mean anything yet.)
-pub const BYTECODE_VERSION: u32 = 0x00_0b_00_00;
+pub const BYTECODE_VERSION: u32 = 0x00_0f_00_00;
 
 /// Maximum value of a short-encoded operand.
 pub const MAX_SHORT_OPERAND: u32 = 0x7f;
@@ -176,6 +178,10 @@ pub enum Instruction {
     CallSelf(u32),
     /// Perform tail-recursive call with *n* arguments from the top of the stack
     TailCallSelf(u32),
+    /// Call the function in value with no arguments, while *n* dynamic
+    /// variables are bound to the `(name, value)` pairs on top of the stack;
+    /// bindings are removed when the call returns.
+    DynamicLet(u32),
     /// Remove *n* values from the top of the stack
     Skip(u32),
     /// Return value from function
@@ -320,6 +326,7 @@ opcodes!{
     SKIP_3 = 121,
     SKIP_4 = 122,
     RETURN = 123,
+    DYNAMIC_LET = 124,
 }
 
 impl Instruction {
@@ -459,6 +466,7 @@ impl Instruction {
             SKIP_3 => Skip(3),
             SKIP_4 => Skip(4),
             RETURN => Return,
+            DYNAMIC_LET => DynamicLet(operand!()),
             _ => return Err(ExecError::UnrecognizedOpCode(op))
         };
 
@@ -613,6 +621,7 @@ impl Instruction {
             TailApplySelf(n) => op!(TAIL_APPLY_SELF, n),
             CallSelf(n) => op!(CALL_SELF, n),
             TailCallSelf(n) => op!(TAIL_CALL_SELF, n),
+            DynamicLet(n) => op!(DYNAMIC_LET, n),
             Skip(1) => op!(SKIP_1),
             Skip(2) => op!(SKIP_2),
             Skip(3) => op!(SKIP_3),
@@ -783,6 +792,134 @@ impl Code {
     }
 }
 
+/// Returns a human-readable listing of a code object.
+///
+/// The listing describes parameters, const values, and enclosed values,
+/// followed by each bytecode instruction with its offset.
+/// Jump targets are marked with `>>`; const values, enclosed values,
+/// and system function names referenced by an instruction are resolved
+/// and shown in a trailing comment.
+///
+/// `values` contains the enclosed values of a lambda, if any.
+pub fn disassemble(names: &NameStore, code: &Code, values: Option<&[Value]>)
+        -> Result<String, ExecError> {
+    let instrs = get_instructions(&code.code)?;
+    let mut res = String::new();
+
+    let _ = writeln!(res, "{} positional argument{} total",
+        code.n_params, plural(code.n_params));
+    let _ = writeln!(res, "{} positional argument{} required",
+        code.req_params, plural(code.req_params));
+
+    if code.kw_params.is_empty() {
+        let _ = writeln!(res, "0 keyword arguments");
+    } else {
+        let _ = writeln!(res, "{} keyword argument{}: {}",
+            code.kw_params.len(), plural(code.kw_params.len() as u32),
+            code.kw_params.iter()
+                .map(|&n| names.get(n)).collect::<Vec<_>>().join(" "));
+    }
+
+    if code.has_rest_params() {
+        let _ = writeln!(res, "Has rest parameter");
+    } else {
+        let _ = writeln!(res, "No rest parameter");
+    }
+
+    if code.consts.is_empty() {
+        let _ = writeln!(res, "0 const values");
+    } else {
+        let _ = writeln!(res, "{} const value{}:",
+            code.consts.len(), plural(code.consts.len() as u32));
+
+        for (i, v) in code.consts.iter().enumerate() {
+            let _ = writeln!(res, "  {} = {}", i, debug_names(names, v));
+        }
+    }
+
+    if let Some(v) = values {
+        let _ = writeln!(res, "{} enclosed value{}:", v.len(), plural(v.len() as u32));
+
+        for (i, v) in v.iter().enumerate() {
+            let _ = writeln!(res, "  {} = {}", i, debug_names(names, v));
+        }
+    } else {
+        let _ = writeln!(res, "0 enclosed values");
+    }
+
+    let mut jumps = Vec::with_capacity(16);
+
+    // Collect all jump labels
+    for (_, instr) in &instrs {
+        if let Some(off) = instr.jump_label() {
+            if let Err(pos) = jumps.binary_search(&off) {
+                jumps.insert(pos, off);
+            }
+        }
+    }
+
+    let _ = writeln!(res, "{} bytecode instruction{}:",
+        instrs.len(), plural(instrs.len() as u32));
+
+    for (off, instr) in instrs {
+        let label = if jumps.binary_search(&off).is_ok() { ">>" } else { "  " };
+
+        match instruction_comment(names, code, values, instr) {
+            Some(s) => {
+                // fmt::Debug does not honor "<n" formatting,
+                // so we make this string first and format again.
+                let instr = format!("{:?}", instr);
+                let _ = writeln!(res, "  {} {:>4}  {:<30} ; {}", label, off, instr, s);
+            }
+            None => {
+                let _ = writeln!(res, "  {} {:>4}  {:?}", label, off, instr);
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+/// Decodes all instructions in a series of bytes, paired with their offsets.
+fn get_instructions(code: &[u8]) -> Result<Vec<(u32, Instruction)>, ExecError> {
+    let mut res = Vec::new();
+    let mut r = CodeReader::new(code, 0);
+
+    while r.offset() != code.len() {
+        let off = r.offset() as u32;
+        res.push((off, r.read_instruction()?));
+    }
+
+    Ok(res)
+}
+
+/// Returns a description of the values referenced by an instruction's operands.
+fn instruction_comment(names: &NameStore, code: &Code, values: Option<&[Value]>,
+        instr: Instruction) -> Option<String> {
+    use self::Instruction::*;
+
+    match instr {
+        LoadC(n) | LoadCPush(n) => values
+            .and_then(|v| v.get(n as usize))
+            .map(|v| debug_names(names, v).to_string()),
+        GetDef(n) | Const(n) | GetDefPush(n) | ConstPush(n) |
+        EqConst(n) | NotEqConst(n) | SetDef(n) |
+        BuildClosure(n, _) | CallConst(n, _) => code.consts.get(n as usize)
+            .map(|c| debug_names(names, c).to_string()),
+        Jump(l) | JumpIf(l) | JumpIfNull(l) | JumpIfNotNull(l) |
+        JumpIfNot(l) | JumpIfEq(l) | JumpIfNotEq(l) |
+        JumpIfBound(l, _) => Some(format!("L{}", l)),
+        JumpIfEqConst(l, n) | JumpIfNotEqConst(l, n) =>
+            Some(match code.consts.get(n as usize) {
+                None => format!("L{}", l),
+                Some(c) => format!("L{} {}", l, debug_names(names, c)),
+            }),
+        CallSys(n) | CallSysArgs(n, _) =>
+            get_standard_name(n).map(|n| names.get(n).to_owned()),
+        _ => None
+    }
+}
+
 /// Bit flag values for `Code::flags`
 pub mod code_flags {
     /// Whether the code object has an associated name
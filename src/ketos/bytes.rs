@@ -27,6 +27,12 @@ impl Bytes {
         self.0.len()
     }
 
+    /// Returns whether two byte strings are views of the same range
+    /// within the same shared allocation.
+    pub fn ptr_eq(&self, other: &Bytes) -> bool {
+        self.0.ptr_eq(&other.0)
+    }
+
     /// Returns a subslice of the byte string.
     pub fn slice<R: RangeArgument<usize>>(&self, range: R) -> Bytes {
         Bytes(self.0.slice(range))
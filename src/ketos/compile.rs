@@ -19,11 +19,11 @@ use crate::exec::{Context, ExecError, execute_lambda};
 use crate::function::{Arity, Lambda};
 use crate::function::Arity::*;
 use crate::name::{
-    get_system_fn, is_system_operator, standard_names,
+    get_system_fn, is_dynamic_var, is_system_operator, standard_names,
     Name, NameDisplay, NameMap, NameSet, NameStore,
     NUM_SYSTEM_OPERATORS, SYSTEM_OPERATORS_BEGIN,
 };
-use crate::scope::{GlobalScope, ImportSet, MasterScope, Scope};
+use crate::scope::{GlobalScope, ImportSet, MasterScope, NameOrigin, Scope};
 use crate::structs::{StructDef, StructValueDef};
 use crate::trace::{Trace, TraceItem, set_traceback, take_traceback};
 use crate::value::{Value, FromValueRef};
@@ -46,6 +46,8 @@ pub enum CompileError {
     CannotDefine(Name),
     /// Attempt to define name held by `const` value
     ConstantExists(Name),
+    /// Attempt to add a module whose name is already in use
+    DuplicateModule(Name),
     /// Duplicate `exports` declaration
     DuplicateExports,
     /// Duplicate `module-doc` declaration
@@ -111,6 +113,7 @@ impl fmt::Display for CompileError {
             ConstantExists(_) =>
                 f.write_str("cannot define name occupied by a constant value"),
             DuplicateExports => f.write_str("duplicate `export` declaration"),
+            DuplicateModule(_) => f.write_str("module already exists"),
             DuplicateModuleDoc => f.write_str("duplicate module doc comment"),
             DuplicateParameter(_) => f.write_str("duplicate parameter"),
             ExportError{..} => f.write_str("export name not found in module"),
@@ -143,6 +146,7 @@ impl NameDisplay for CompileError {
             ArityError{name, ..} => write!(f, "`{}` {}", names.get(name), self),
             CannotDefine(name) |
             ConstantExists(name) |
+            DuplicateModule(name) |
             DuplicateParameter(name) |
             InvalidModuleName(name) |
             ModuleError(name) |
@@ -166,6 +170,41 @@ impl NameDisplay for CompileError {
     }
 }
 
+/// Represents a non-fatal diagnostic produced during compilation.
+///
+/// Warnings are collected in the global scope in which the offending
+/// code is compiled; see `GlobalScope::take_diagnostics`.
+#[derive(Clone, Debug)]
+pub enum CompileWarning {
+    /// Global definition of a name which is exported by a loaded module,
+    /// but not imported into the defining scope
+    ShadowBuiltin{
+        /// Module name
+        module: Name,
+        /// Defined name
+        name: Name,
+    },
+}
+
+impl fmt::Display for CompileWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompileWarning::ShadowBuiltin{..} =>
+                f.write_str("definition shadows a builtin name"),
+        }
+    }
+}
+
+impl NameDisplay for CompileWarning {
+    fn fmt(&self, names: &NameStore, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CompileWarning::ShadowBuiltin{module, name} =>
+                write!(f, "definition of `{}` shadows builtin from module `{}`",
+                    names.get(name), names.get(module)),
+        }
+    }
+}
+
 /// Compiles an expression into a code object.
 pub fn compile(ctx: &Context, value: &Value) -> Result<Code, Error> {
     let mut compiler = Compiler::new(ctx);
@@ -461,6 +500,10 @@ impl<'a> Compiler<'a> {
     }
 
     fn compile_value(&mut self, value: &Value) -> Result<(), Error> {
+        if let Value::Name(name) = *value {
+            self.observe_name(name);
+        }
+
         let mut value = Borrowed(value);
 
         match self.eval_constant(&value)? {
@@ -489,6 +532,15 @@ impl<'a> Compiler<'a> {
 
                 match *fn_v {
                     Value::Name(name) => {
+                        // Macros and operators are not names resolved at runtime,
+                        // unless shadowed by a local value
+                        if let Some(f) = self.scope().name_observer() {
+                            if self.is_local_name(name) ||
+                                    !(self.is_macro(name) || is_system_operator(name)) {
+                                f(name, self.name_origin(name));
+                            }
+                        }
+
                         if self.load_local_name(name)? {
                             self.push_instruction(Instruction::Push)?;
                             pushed_fn = true;
@@ -1293,6 +1345,35 @@ impl<'a> Compiler<'a> {
         }
     }
 
+    /// Reports the resolution of a name to the scope's name observer, if any.
+    fn observe_name(&self, name: Name) {
+        if let Some(f) = self.scope().name_observer() {
+            f(name, self.name_origin(name));
+        }
+    }
+
+    /// Returns where a name referenced by compiled code is resolved.
+    fn name_origin(&self, name: Name) -> NameOrigin {
+        if self.is_local_name(name) {
+            NameOrigin::Local
+        } else if MasterScope::contains(name) {
+            NameOrigin::Builtin
+        } else if self.scope().is_imported(name) {
+            NameOrigin::Module
+        } else {
+            NameOrigin::Global
+        }
+    }
+
+    /// Returns whether a name refers to a value on the stack or
+    /// in an enclosing scope.
+    fn is_local_name(&self, name: Name) -> bool {
+        self.stack.iter().any(|&(n, _)| n == name) ||
+            (self.self_name != Some(name) &&
+                (self.captures.contains(&name) ||
+                    self.outer.iter().any(|o| o.stack.iter().any(|&(n, _)| n == name))))
+    }
+
     /// Emits code to load a local value from the stack or closure values.
     /// Returns `Ok(true)` if a named value was found and loaded.
     fn load_local_name(&mut self, name: Name) -> Result<bool, CompileError> {
@@ -1425,6 +1506,9 @@ impl<'a> Compiler<'a> {
             Instruction::Skip(n) => {
                 self.stack_offset -= n;
             }
+            Instruction::DynamicLet(n) => {
+                self.stack_offset -= n * 2;
+            }
             // CallSys is handled at the push site
             // to avoid duplicate get_system_fn call
             Instruction::CallSysArgs(_, n) |
@@ -1730,6 +1814,9 @@ static SYSTEM_OPERATORS: [Operator; NUM_SYSTEM_OPERATORS] = [
     sys_op!(op_const, Range(2, 3)),
     sys_op!(op_set_module_doc, Exact(1)),
     sys_op!(op_call_self, Min(0)),
+    sys_op!(op_let_syntax, Exact(2)),
+    sys_op!(op_dynamic_let, Exact(2)),
+    sys_op!(op_match, Min(2)),
 ];
 
 /// `apply` calls a function or lambda with a series of arguments.
@@ -1796,21 +1883,36 @@ fn op_do(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
 ///       (b (bar)))
 ///   (baz a b))
 /// ```
+///
+/// A binding may instead give a list of names, which are bound to
+/// the elements of the list produced by its expression.
+///
+/// ```lisp
+/// (let (((a (b c)) (foo)))
+///   (baz a b c))
+/// ```
 fn op_let(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
     let mut n_vars = 0;
+    let mut pattern_body = None;
 
     match args[0] {
         Value::Unit => (),
         Value::List(ref li) => {
-            n_vars = li.len() as u32;
-            for v in li {
+            for (i, v) in li.iter().enumerate() {
                 match *v {
-                    Value::List(ref li) if li.len() == 2 => {
-                        let name = get_name(compiler, &li[0])?;
+                    Value::List(ref b) if b.len() == 2 => {
+                        if let Value::List(ref pat) = b[0] {
+                            pattern_body = Some(let_pattern(compiler,
+                                pat, &b[1], &li[i + 1..], &args[1])?);
+                            break;
+                        }
 
-                        compiler.compile_value(&li[1])?;
+                        let name = get_name(compiler, &b[0])?;
+
+                        compiler.compile_value(&b[1])?;
                         compiler.push_var(name);
                         compiler.push_instruction(Instruction::Push)?;
+                        n_vars += 1;
                     }
                     _ => {
                         compiler.set_trace_expr(v);
@@ -1826,7 +1928,7 @@ fn op_let(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
         }
     }
 
-    compiler.compile_value(&args[1])?;
+    compiler.compile_value(pattern_body.as_ref().unwrap_or(&args[1]))?;
 
     // Create a new block containing the Skip.
     // This helps to optimize out unnecessary instructions in the assembly phase.
@@ -1839,6 +1941,189 @@ fn op_let(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
     Ok(())
 }
 
+/// Expands a `let` binding of a list pattern, along with any bindings
+/// following it, into the application of a lambda to the bound value.
+/// A length mismatch is then reported as an arity error at runtime.
+fn let_pattern(compiler: &mut Compiler, pattern: &[Value], value: &Value,
+        rest: &[Value], body: &Value) -> Result<Value, Error> {
+    let body = if rest.is_empty() {
+        body.clone()
+    } else {
+        Value::from(vec![Value::Name(standard_names::LET),
+            Value::from(rest.to_vec()), body.clone()])
+    };
+
+    let mut n_temps = 0;
+    destructure(compiler, pattern, value.clone(), body, &mut n_temps)
+}
+
+fn destructure(compiler: &mut Compiler, pattern: &[Value], value: Value,
+        body: Value, n_temps: &mut u32) -> Result<Value, Error> {
+    let mut params = Vec::with_capacity(pattern.len());
+    let mut nested = Vec::new();
+
+    for v in pattern {
+        match *v {
+            Value::Name(name) => params.push(Value::Name(name)),
+            Value::List(ref li) => {
+                // Temporary names cannot be produced by the parser
+                let temp = compiler.scope().add_name(&format!("#pattern-{}", n_temps));
+                *n_temps += 1;
+                params.push(Value::Name(temp));
+                nested.push((li, temp));
+            }
+            ref v => {
+                compiler.set_trace_expr(v);
+                return Err(From::from(CompileError::SyntaxError(
+                    "expected name or list")));
+            }
+        }
+    }
+
+    let mut body = body;
+
+    for (li, temp) in nested.into_iter().rev() {
+        body = destructure(compiler, li, Value::Name(temp), body, n_temps)?;
+    }
+
+    Ok(Value::from(vec![
+        Value::Name(standard_names::APPLY),
+        Value::from(vec![
+            Value::Name(standard_names::LAMBDA),
+            Value::from(params),
+            body,
+        ]),
+        value,
+    ]))
+}
+
+/// `let-syntax` defines a series of macros which are visible only while
+/// compiling its body expression.
+///
+/// ```lisp
+/// (let-syntax (((twice e) `(* 2 ,e)))
+///   (twice 3))
+/// ```
+fn op_let_syntax(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
+    let mut shadowed = Vec::new();
+
+    let r = add_local_macros(compiler, &args[0], &mut shadowed)
+        .and_then(|()| compiler.compile_value(&args[1]));
+
+    // Restore the macro table, even if compilation failed
+    for (name, prev) in shadowed.into_iter().rev() {
+        match prev {
+            Some(lambda) => compiler.scope().add_macro(name, lambda),
+            None => { compiler.scope().remove_macro(name); }
+        }
+    }
+
+    r
+}
+
+/// Adds macros defined by `let-syntax` bindings to the global scope.
+/// Any macro shadowed by a binding is pushed onto `shadowed`,
+/// so that it may be restored afterward.
+fn add_local_macros(compiler: &mut Compiler, bindings: &Value,
+        shadowed: &mut Vec<(Name, Option<Lambda>)>) -> Result<(), Error> {
+    match *bindings {
+        Value::Unit => Ok(()),
+        Value::List(ref li) => {
+            for v in li {
+                let (name, params, body) = match *v {
+                    Value::List(ref b) if b.len() == 2 => match b[0] {
+                        Value::List(ref sig) =>
+                            (get_name(compiler, &sig[0])?, &sig[1..], &b[1]),
+                        ref v => {
+                            compiler.set_trace_expr(v);
+                            return Err(From::from(CompileError::SyntaxError(
+                                "expected list")));
+                        }
+                    },
+                    _ => {
+                        compiler.set_trace_expr(v);
+                        return Err(From::from(CompileError::SyntaxError(
+                            "expected list of 2 elements")));
+                    }
+                };
+
+                let (lambda, captures) = make_lambda(compiler,
+                    Some(name), params, body, None)?;
+
+                if !captures.is_empty() {
+                    return Err(From::from(CompileError::SyntaxError(
+                        "macro lambda cannot enclose values")));
+                }
+
+                shadowed.push((name, compiler.scope().get_macro(name)));
+                compiler.scope().add_macro(name, lambda);
+            }
+
+            Ok(())
+        }
+        ref v => {
+            compiler.set_trace_expr(v);
+            Err(From::from(CompileError::SyntaxError("expected list")))
+        }
+    }
+}
+
+/// `dynamic-let` binds a series of dynamic variables for the dynamic extent
+/// of its body expression; that is, functions called from within the body
+/// observe the new values. Previous values are restored when the body returns,
+/// even if it returns an error.
+///
+/// ```lisp
+/// (dynamic-let ((*print-readably* true))
+///   (println "~a" "foo"))
+/// ```
+fn op_dynamic_let(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
+    let mut n_vars = 0;
+
+    match args[0] {
+        Value::Unit => (),
+        Value::List(ref li) => {
+            for v in li {
+                match *v {
+                    Value::List(ref b) if b.len() == 2 => {
+                        let name = get_name(compiler, &b[0])?;
+
+                        if !is_dynamic_var(name) {
+                            compiler.set_trace_expr(&b[0]);
+                            return Err(From::from(CompileError::SyntaxError(
+                                "expected dynamic variable")));
+                        }
+
+                        let c = compiler.add_const(Owned(Value::Name(name)));
+                        compiler.push_instruction(Instruction::Const(c))?;
+                        compiler.push_instruction(Instruction::Push)?;
+                        compiler.compile_value(&b[1])?;
+                        compiler.push_instruction(Instruction::Push)?;
+                        n_vars += 1;
+                    }
+                    _ => {
+                        compiler.set_trace_expr(v);
+                        return Err(From::from(CompileError::SyntaxError(
+                            "expected list of 2 elements")))
+                    }
+                }
+            }
+        }
+        ref v => {
+            compiler.set_trace_expr(v);
+            return Err(From::from(CompileError::SyntaxError("expected list")))
+        }
+    }
+
+    let (lambda, captures) = make_lambda(compiler, None, &[], &args[1], None)?;
+
+    let c = compiler.add_const(Owned(Value::Lambda(lambda)));
+    compiler.load_lambda(c, &captures)?;
+    compiler.push_instruction(Instruction::DynamicLet(n_vars))?;
+
+    Ok(())
+}
+
 /// `define` declares a value binding or function binding in global scope.
 ///
 /// ```lisp
@@ -1855,6 +2140,7 @@ fn op_define(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
 
             let (doc, body) = extract_doc_string(args)?;
             test_define_name(compiler.scope(), name)?;
+            warn_shadow_builtin(compiler.scope(), name);
             compiler.compile_value(body)?;
 
             if let Some(doc) = doc {
@@ -1873,6 +2159,7 @@ fn op_define(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
             compiler.trace.push(TraceItem::Define(compiler.ctx.scope().name(), name));
 
             test_define_name(compiler.scope(), name)?;
+            warn_shadow_builtin(compiler.scope(), name);
             let (doc, body) = extract_doc_string(args)?;
 
             let c = compiler.add_const(Owned(Value::Name(name)));
@@ -2251,6 +2538,77 @@ fn op_cond(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
     Ok(())
 }
 
+/// `match` evaluates an expression and compares the result to a series of
+/// struct patterns, evaluating the body of the first matching pattern.
+/// Each name in a struct pattern is bound to the field of the same name.
+/// The pattern `_` matches any value.
+///
+/// ```lisp
+/// (match p
+///   ((Point x y) (+ x y))
+///   (_ 0))
+/// ```
+fn op_match(compiler: &mut Compiler, args: &[Value]) -> Result<(), Error> {
+    // Temporary names cannot be produced by the parser
+    let temp = Value::Name(compiler.scope().add_name("#match"));
+    let mut cond = Vec::with_capacity(args.len());
+
+    cond.push(Value::Name(standard_names::COND));
+
+    for arg in &args[1..] {
+        let (pat, body) = match *arg {
+            Value::List(ref li) if li.len() == 2 => (&li[0], &li[1]),
+            _ => {
+                compiler.set_trace_expr(arg);
+                return Err(From::from(CompileError::SyntaxError(
+                    "expected list of 2 elements")));
+            }
+        };
+
+        let clause = match *pat {
+            Value::Name(name) if compiler.scope().with_name(name, |s| s == "_") =>
+                vec![Value::Name(standard_names::ELSE), body.clone()],
+            Value::List(ref li) => {
+                let def = get_name(compiler, &li[0])?;
+                let mut bindings = Vec::with_capacity(li.len() - 1);
+
+                for v in &li[1..] {
+                    let field = get_name(compiler, v)?;
+
+                    bindings.push(Value::from(vec![v.clone(), Value::from(vec![
+                        Value::Name(standard_names::DOT),
+                        temp.clone(),
+                        Value::Keyword(field),
+                    ])]));
+                }
+
+                let body = if bindings.is_empty() {
+                    body.clone()
+                } else {
+                    Value::from(vec![Value::Name(standard_names::LET),
+                        Value::from(bindings), body.clone()])
+                };
+
+                vec![Value::from(vec![Value::Name(standard_names::IS_INSTANCE),
+                    Value::Name(def), temp.clone()]), body]
+            }
+            ref v => {
+                compiler.set_trace_expr(v);
+                return Err(From::from(CompileError::SyntaxError(
+                    "expected struct pattern or `_`")));
+            }
+        };
+
+        cond.push(Value::from(clause));
+    }
+
+    compiler.compile_value(&Value::from(vec![
+        Value::Name(standard_names::LET),
+        Value::from(vec![Value::from(vec![temp, args[0].clone()])]),
+        Value::from(cond),
+    ]))
+}
+
 /// `lambda` defines an anonymous lambda function which may enclose named values
 /// from the enclosing scope.
 ///
@@ -2539,6 +2897,22 @@ fn test_define_name(scope: &GlobalScope, name: Name) -> Result<(), CompileError>
     }
 }
 
+/// Adds a diagnostic to the scope if a global definition of `name` shadows
+/// a name exported by any loaded module.
+fn warn_shadow_builtin(scope: &GlobalScope, name: Name) {
+    let modules = scope.modules();
+
+    for module in modules.module_names() {
+        let exported = modules.get_module(module)
+            .is_some_and(|m| m.scope.is_exported(name));
+
+        if exported {
+            scope.add_diagnostic(CompileWarning::ShadowBuiltin{module, name});
+            break;
+        }
+    }
+}
+
 /// Creates a `Lambda` object using scope and local values from the given compiler.
 /// Returns the `Lambda` object and the set of names captured by the lambda.
 fn make_lambda(compiler: &mut Compiler, name: Option<Name>,
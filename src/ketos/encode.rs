@@ -428,7 +428,8 @@ impl<'a, 'data> ValueDecoder<'a, 'data> {
                     .map(Value::Char)
                     .ok_or(DecodeError::InvalidChar(c))
             }
-            STRING => self.read_string().map(|s| s.into()),
+            STRING => self.read_string()
+                .map(|s| Value::String(self.ctx.scope().intern_string(s))),
             BYTES => self.read_byte_string().map(|s| s.into()),
             PATH => self.read_string().map(|s| PathBuf::from(s).into()),
             // XXX: Decoding struct values is not implemented
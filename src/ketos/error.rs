@@ -7,9 +7,11 @@ use crate::compile::CompileError;
 use crate::encode::{DecodeError, EncodeError};
 use crate::exec::ExecError;
 use crate::io::IoError;
-use crate::name::{NameDisplay, NameStore};
+use crate::name::{display_names, NameDisplay, NameStore};
 use crate::parser::ParseError;
 use crate::restrict::RestrictError;
+use crate::trace::{Trace, TraceItem};
+use crate::value::ForeignValue;
 
 macro_rules! error_type {
     ( $( #[$meta:meta] )* pub enum $name:ident
@@ -95,6 +97,134 @@ impl StdError for Error {
     }
 }
 
+/// An `Error` paired with the traceback recorded when it occurred.
+///
+/// Values of this type are produced by `Interpreter::traced`.
+#[derive(Debug)]
+pub struct TracedError {
+    error: Error,
+    trace: Option<Box<Trace>>,
+}
+
+impl TracedError {
+    /// Creates a `TracedError` from an `Error` and its traceback, if any.
+    pub fn new(error: Error, trace: Option<Trace>) -> TracedError {
+        TracedError{ error, trace: trace.map(Box::new) }
+    }
+
+    /// Returns a borrowed reference to the contained `Error`.
+    pub fn error(&self) -> &Error {
+        &self.error
+    }
+
+    /// Consumes the `TracedError` and returns the contained `Error`.
+    pub fn into_error(self) -> Error {
+        self.error
+    }
+
+    /// Returns the traced items, beginning with the outermost context.
+    ///
+    /// `None` is returned if the error did not produce a traceback,
+    /// as is the case for parse errors.
+    pub fn traceback(&self) -> Option<&[TraceItem]> {
+        self.trace.as_ref().map(|t| t.items())
+    }
+
+    /// Returns a borrowed reference to the traceback, if any.
+    pub fn trace(&self) -> Option<&Trace> {
+        self.trace.as_deref()
+    }
+
+    /// Consumes the `TracedError` and returns the `Error` and its traceback.
+    pub fn into_parts(self) -> (Error, Option<Trace>) {
+        (self.error, self.trace.map(|t| *t))
+    }
+}
+
+impl From<TracedError> for Error {
+    fn from(e: TracedError) -> Error {
+        e.error
+    }
+}
+
+impl fmt::Display for TracedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+impl NameDisplay for TracedError {
+    fn fmt(&self, names: &NameStore, f: &mut fmt::Formatter) -> fmt::Result {
+        NameDisplay::fmt(&self.error, names, f)
+    }
+}
+
+impl StdError for TracedError {
+    fn description(&self) -> &str {
+        self.error.description()
+    }
+}
+
+/// Represents an `Error` as a value, retaining its description and
+/// formatted message.
+///
+/// Values of this type are produced by `Interpreter::eval_catching`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ErrorValue {
+    description: &'static str,
+    message: String,
+}
+
+impl ErrorValue {
+    /// Creates an `ErrorValue` from an `Error`, using the given `NameStore`
+    /// to format any names contained within.
+    pub fn new(e: &Error, names: &NameStore) -> ErrorValue {
+        ErrorValue{
+            description: e.description(),
+            message: display_names(names, e).to_string(),
+        }
+    }
+
+    /// Returns a string describing the nature of the error,
+    /// as returned by `Error::description`.
+    pub fn description(&self) -> &'static str {
+        self.description
+    }
+
+    /// Returns the formatted error message.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ErrorValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.description, self.message)
+    }
+}
+
+impl ForeignValue for ErrorValue {
+    fn is_equal_to(&self, rhs: &dyn ForeignValue) -> Result<bool, ExecError> {
+        match rhs.downcast_ref::<ErrorValue>() {
+            Some(rhs) => Ok(self == rhs),
+            None => Err(ExecError::TypeMismatch{
+                lhs: self.type_name(),
+                rhs: rhs.type_name(),
+            })
+        }
+    }
+
+    fn fmt_debug(&self, _names: &NameStore, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<error {:?}: {:?}>", self.description, self.message)
+    }
+
+    fn fmt_display(&self, _names: &NameStore, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+
+    fn type_name(&self) -> &'static str { "error" }
+}
+
 #[cfg(test)]
 mod test {
     use std::error::Error as StdError;
@@ -23,7 +23,7 @@
 //! returns a value, which is available to the calling function through the
 //! value register.
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::error::Error as StdError;
 use std::fmt;
 use std::mem::replace;
@@ -63,6 +63,12 @@ pub struct Context {
     run_start: Cell<Option<Instant>>,
     run_level: Cell<u32>,
     memory_held: Cell<usize>,
+    // The step count is shared, rather than copied, so that instructions
+    // executed by each Machine of a single run count toward one limit.
+    steps: Rc<Cell<u64>>,
+    // Dynamic variable bindings are shared, rather than copied, so that
+    // functions called within a `dynamic-let` body observe its bindings.
+    dynamic: Rc<RefCell<Vec<(Name, Value)>>>,
 }
 
 impl Context {
@@ -74,6 +80,8 @@ impl Context {
             run_start: Cell::new(None),
             run_level: Cell::new(0),
             memory_held: Cell::new(0),
+            steps: Rc::new(Cell::new(0)),
+            dynamic: Rc::new(RefCell::new(Vec::new())),
         }
     }
 
@@ -81,14 +89,45 @@ impl Context {
     pub fn scope(&self) -> &Scope { &self.scope }
 
     /// Creates a new execution context with the given scope.
+    ///
+    /// Code executed in the new context during a run of this context
+    /// counts toward the same execution time and step limits.
     pub fn with_scope(&self, scope: Scope) -> Context {
-        Context::new(scope, self.restrict.clone())
+        Context{
+            scope,
+            ..self.clone()
+        }
+    }
+
+    /// Creates a new execution context with the given restrictions,
+    /// sharing the scope of this context.
+    ///
+    /// Execution time and steps continue to be counted from the start
+    /// of the outermost run of this context.
+    pub fn with_restrict(&self, restrict: RestrictConfig) -> Context {
+        Context{
+            restrict,
+            ..self.clone()
+        }
     }
 
     /// Returns a reference to the contained restriction configuration.
     pub fn restrict(&self) -> &RestrictConfig { &self.restrict }
 
-    fn dec_run_level(&self) {
+    /// Returns the innermost value bound to the given dynamic variable
+    /// by `dynamic-let`, if any.
+    pub fn dynamic_value(&self, name: Name) -> Option<Value> {
+        self.dynamic.borrow().iter().rev()
+            .find(|&&(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    /// Returns whether the given dynamic variable is bound to `true`.
+    pub fn dynamic_flag(&self, name: Name) -> bool {
+        matches!(self.dynamic_value(name), Some(Value::Bool(true)))
+    }
+
+    pub(crate) fn dec_run_level(&self) {
         let n = self.run_level.get() - 1;
         self.run_level.set(n);
         if n == 0 {
@@ -96,11 +135,12 @@ impl Context {
         }
     }
 
-    fn inc_run_level(&self) {
+    pub(crate) fn inc_run_level(&self) {
         let n = self.run_level.get();
         self.run_level.set(n + 1);
         if n == 0 {
             self.run_start.set(Some(Instant::now()));
+            self.steps.set(0);
         }
     }
 
@@ -129,6 +169,15 @@ pub enum ExecError {
         /// Number of arguments present
         found: u32,
     },
+    /// Attempt to access a range of bytes beyond the end of a byte string
+    BytesOutOfBounds{
+        /// Offset of the first byte accessed
+        offset: usize,
+        /// Number of bytes accessed
+        width: usize,
+        /// Length of the byte string
+        len: usize,
+    },
     /// Attempt to compare with a `NaN` `Float` value.
     CompareNaN,
     /// Type does not support ordered comparison
@@ -143,6 +192,8 @@ pub enum ExecError {
     DuplicateKeyword(Name),
     /// Duplicate struct definition
     DuplicateStructDef(Name),
+    /// Call to a function requiring a Cargo feature which is not enabled
+    FeatureUnavailable(&'static str),
     /// No such field name in struct
     FieldError{
         /// Name of struct type
@@ -178,9 +229,24 @@ pub enum ExecError {
     InvalidConst(u32),
     /// Invalid (zero) depth value to `Quote`, `Quasiquote`, or `Comma` instruction
     InvalidDepth,
+    /// Malformed input to a decoding function
+    InvalidEncoding{
+        /// Name of the expected encoding
+        encoding: &'static str,
+        /// Description of the error
+        reason: &'static str,
+    },
+    /// Malformed JSON input
+    InvalidJson(String),
     /// Invalid jump label
     InvalidJump(u32),
+    /// Range whose lower bound is greater than its upper bound;
+    /// contains the lower and upper bounds
+    InvalidRange(Box<(Value, Value)>),
+    /// Invalid regular expression pattern
+    InvalidRegex(String),
     /// Slice indices out of order
+    #[deprecated(since = "0.14.0", note = "`slice` now returns `InvalidRange`")]
     InvalidSlice(usize, usize),
     /// Invalid stack index
     InvalidStack(u32),
@@ -197,6 +263,9 @@ pub enum ExecError {
     },
     /// Attempt to lookup a name that did not exist in scope.
     NameError(Name),
+    /// Attempt to index backward from the end of a sequence,
+    /// beyond its beginning; contains the negative index.
+    NegativeOutOfBounds(isize),
     /// Attempt to slice a string not along UTF-8 code point boundaries.
     NotCharBoundary(usize),
     /// Odd number of parameters when keyword-value pairs expected
@@ -207,6 +276,11 @@ pub enum ExecError {
     Overflow,
     /// Code called `panic`
     Panic(Option<Value>),
+    /// Attempt to copy a foreign value through a reference cycle
+    /// which its `deep_copy` implementation does not support
+    ReferenceCycle,
+    /// Call to a function whose global scope has been dropped
+    ScopeDropped,
     /// Struct definition not found
     StructDefError(Name),
     /// Operation performed on unexpected type
@@ -292,6 +366,9 @@ impl fmt::Display for ExecError {
         match *self {
             ArityError{expected, found, ..} =>
                 write!(f, "expected {}; found {}", expected, found),
+            BytesOutOfBounds{offset, width, len} =>
+                write!(f, "byte range {}..{} out of bounds for length {}",
+                    offset, offset.saturating_add(width), len),
             CannotCompare(ty) => write!(f, "cannot compare values of type {}", ty),
             CannotDefine(_) =>
                 f.write_str("cannot define name of standard value or operator"),
@@ -300,6 +377,8 @@ impl fmt::Display for ExecError {
             DuplicateField(_) => f.write_str("duplicate field"),
             DuplicateKeyword(_) => f.write_str("duplicate keyword"),
             DuplicateStructDef(_) => f.write_str("duplicate struct definition"),
+            FeatureUnavailable(feature) =>
+                write!(f, "feature `{}` is not enabled", feature),
             FieldError{..} => f.write_str("no such field in struct"),
             FieldTypeError{..} => f.write_str("incorrect field type"),
             FormatError{ref err, ..} =>
@@ -307,7 +386,14 @@ impl fmt::Display for ExecError {
             InvalidClosureValue(n) => write!(f, "invalid closure value: {}", n),
             InvalidConst(n) => write!(f, "invalid const: {}", n),
             InvalidDepth => f.write_str("invalid depth operand"),
+            InvalidEncoding{encoding, reason} =>
+                write!(f, "invalid {} input: {}", encoding, reason),
+            InvalidJson(ref err) => write!(f, "invalid json: {}", err),
             InvalidJump(label) => write!(f, "invalid jump label: {}", label),
+            InvalidRange(_) =>
+                f.write_str("invalid range: lower bound exceeds upper bound"),
+            InvalidRegex(ref err) => write!(f, "invalid regex: {}", err),
+            #[allow(deprecated)]
             InvalidSlice(begin, end) => write!(f, "invalid slice {}..{}", begin, end),
             InvalidStack(n) => write!(f, "invalid stack index: {}", n),
             InvalidSystemFn(n) => write!(f, "invalid system function: {}", n),
@@ -315,12 +401,16 @@ impl fmt::Display for ExecError {
                 write!(f, "system function requires argument count"),
             MissingField{..} => f.write_str("missing field in struct"),
             NameError(_) => f.write_str("name not found in global scope"),
+            NegativeOutOfBounds(n) => write!(f, "index out of bounds: {}", n),
             StructDefError(_) => f.write_str("struct definition not found"),
             NotCharBoundary(n) => write!(f, "index not on char boundary: {}", n),
             OddKeywordParams => f.write_str("expected keyword-value pairs"),
             OutOfBounds(n) => write!(f, "index out of bounds: {}", n),
             Overflow => f.write_str("integer overflow"),
             Panic(_) => f.write_str("panic"),
+            ReferenceCycle =>
+                f.write_str("cannot copy value containing a reference cycle"),
+            ScopeDropped => f.write_str("function scope has been dropped"),
             TypeError{expected, found, ..} =>
                 write!(f, "type error: expected {}; found {}", expected, found),
             StructMismatch{..} => f.write_str("incorrect struct type"),
@@ -379,6 +469,9 @@ impl NameDisplay for ExecError {
                 writeln!(f, "    {}", hi.highlight)?;
                 Ok(())
             }
+            InvalidRange(ref bounds) =>
+                write!(f, "invalid range: lower bound {} exceeds upper bound {}",
+                    debug_names(names, &bounds.0), debug_names(names, &bounds.1)),
             MissingArgCount(name) =>
                 write!(f, "system function `{}` requires argument count",
                     names.get(name)),
@@ -416,6 +509,115 @@ pub fn execute(ctx: &Context, code: Rc<Code>) -> Result<Value, Error> {
         .map_err(|e| { set_traceback(mach.build_trace()); e })
 }
 
+/// Outcome of running code with a step budget
+#[derive(Debug)]
+pub enum ExecState {
+    /// Execution completed, producing the value of the final expression
+    Done(Value),
+    /// The step budget was exhausted before execution completed
+    Suspended(Box<Suspended>),
+}
+
+/// Execution suspended after exhausting a step budget,
+/// which may be continued by calling `resume`.
+///
+/// The state of the virtual machine, including its value stack and call stack,
+/// is retained between steps. Each bytecode instruction executed counts as
+/// one step. Instructions run by a nested execution, such as a lambda called
+/// by a module function like `update-in`, are not counted; the nested call
+/// always runs to completion within a single step.
+pub struct Suspended {
+    machine: Machine,
+    scope: Scope,
+    frame: Option<StackFrame>,
+    code: std::vec::IntoIter<Rc<Code>>,
+    last_value: Value,
+    /// Bindings of unfinished `dynamic-let` forms, held while suspended
+    dynamic: Vec<(Name, Value)>,
+}
+
+impl fmt::Debug for Suspended {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Suspended")
+    }
+}
+
+impl Suspended {
+    /// Creates a suspended execution of a series of code objects,
+    /// which have not yet begun execution.
+    pub fn new(ctx: &Context, code: Vec<Rc<Code>>) -> Box<Suspended> {
+        Box::new(Suspended{
+            machine: Machine::new(ctx),
+            scope: ctx.scope().clone(),
+            frame: None,
+            code: code.into_iter(),
+            last_value: Value::Unit,
+            dynamic: Vec::new(),
+        })
+    }
+
+    /// Continues execution for at most `steps` instructions.
+    ///
+    /// Returns `ExecState::Done` with the value of the final code object
+    /// if execution completes; otherwise, returns `ExecState::Suspended`.
+    /// A budget of `0` steps makes no progress.
+    pub fn resume(mut self: Box<Self>, steps: u64) -> Result<ExecState, Error> {
+        self.machine.budget = Some(steps);
+
+        let depth = self.machine.context.dynamic.borrow().len();
+        self.machine.context.dynamic.borrow_mut().append(&mut self.dynamic);
+        self.machine.context.inc_run_level();
+
+        let res = self.run_budget();
+
+        self.machine.context.dec_run_level();
+
+        // Bindings are set aside so they are not visible to other code
+        // executed in the same context while this execution is suspended.
+        let held = self.machine.context.dynamic.borrow_mut().split_off(depth);
+
+        if let Ok(false) = res {
+            self.dynamic = held;
+        }
+
+        match res {
+            Ok(true) => Ok(ExecState::Done(self.last_value)),
+            Ok(false) => Ok(ExecState::Suspended(self)),
+            Err(e) => {
+                set_traceback(self.machine.build_trace());
+                Err(e)
+            }
+        }
+    }
+
+    /// Runs until all code completes or the budget is exhausted.
+    /// Returns whether execution has completed.
+    fn run_budget(&mut self) -> Result<bool, Error> {
+        loop {
+            let mut frame = match self.frame.take() {
+                Some(frame) => frame,
+                None => match self.code.next() {
+                    Some(code) => Machine::code_frame(&self.scope, code)?,
+                    None => return Ok(true)
+                }
+            };
+
+            match self.machine.run(&mut frame) {
+                Ok(true) => self.last_value = self.machine.value.take(),
+                Ok(false) => {
+                    self.frame = Some(frame);
+                    return Ok(false);
+                }
+                Err(e) => {
+                    // Save the frame for stack traces
+                    self.machine.call_stack.push(frame);
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
 /// Calls a function or lambda in the given scope with the given arguments.
 pub fn call_function(ctx: &Context, fun: Value, args: Vec<Value>) -> Result<Value, Error> {
     match fun {
@@ -464,6 +666,9 @@ struct StackFrame {
     sptr: u32,
     /// Whether the function value was on the stack
     fn_on_stack: bool,
+    /// For the body of a `dynamic-let` form, the number of dynamic
+    /// bindings to retain when the frame returns
+    dynamic_depth: Option<usize>,
 }
 
 struct Machine {
@@ -472,6 +677,8 @@ struct Machine {
     call_stack: Vec<StackFrame>,
     value: Value,
     sys_fn_call: Option<Name>,
+    /// Instructions which may be run before suspending, if limited
+    budget: Option<u64>,
 }
 
 impl Machine {
@@ -482,6 +689,7 @@ impl Machine {
             call_stack: Vec::with_capacity(ctx.restrict().call_stack_size),
             value: Value::Unit,
             sys_fn_call: None,
+            budget: None,
         }
     }
 
@@ -507,6 +715,13 @@ impl Machine {
     }
 
     fn execute(&mut self, scope: &Scope, code: Rc<Code>) -> Result<Value, Error> {
+        let frame = Machine::code_frame(scope, code)?;
+        self.start(frame)
+    }
+
+    /// Returns the initial frame for executing a code object
+    /// which accepts no arguments.
+    fn code_frame(scope: &Scope, code: Rc<Code>) -> Result<StackFrame, Error> {
         let arity = code.arity();
 
         if arity != Arity::Exact(0) {
@@ -517,20 +732,21 @@ impl Machine {
             }));
         }
 
-        self.start(StackFrame{
+        Ok(StackFrame{
             code,
             scope: scope.clone(),
             values: None,
             iptr: 0,
             sptr: 0,
             fn_on_stack: false,
+            dynamic_depth: None,
         })
     }
 
     fn execute_lambda(&mut self, lambda: Lambda, args: Vec<Value>)
             -> Result<Value, Error> {
         let scope = lambda.scope.upgrade()
-            .expect("Lambda scope has been destroyed");
+            .ok_or(ExecError::ScopeDropped)?;
 
         self.push_iter(args)?;
 
@@ -569,10 +785,12 @@ impl Machine {
             iptr: 0,
             sptr: 0,
             fn_on_stack: false,
+            dynamic_depth: None,
         })
     }
 
     fn start(&mut self, mut frame: StackFrame) -> Result<Value, Error> {
+        let depth = self.context.dynamic.borrow().len();
         self.context.inc_run_level();
 
         let res = self.run(&mut frame);
@@ -580,6 +798,8 @@ impl Machine {
         self.context.dec_run_level();
 
         if let Err(e) = res {
+            // Remove bindings of any `dynamic-let` body left unfinished
+            self.context.dynamic.borrow_mut().truncate(depth);
             // Save the frame for stack traces
             self.call_stack.push(frame);
             return Err(e);
@@ -588,12 +808,22 @@ impl Machine {
         Ok(self.value.take())
     }
 
-    fn run(&mut self, frame: &mut StackFrame) -> Result<(), Error> {
+    /// Runs instructions until the outermost frame returns,
+    /// returning `Ok(true)`, or until the budget, if any, is exhausted,
+    /// returning `Ok(false)`.
+    fn run(&mut self, frame: &mut StackFrame) -> Result<bool, Error> {
         use crate::bytecode::Instruction::*;
 
         let mut n_instructions = 0;
 
         loop {
+            if let Some(budget) = self.budget {
+                if budget == 0 {
+                    return Ok(false);
+                }
+                self.budget = Some(budget - 1);
+            }
+
             let instr = {
                 let mut r = CodeReader::new(&frame.code.code, frame.iptr as usize);
                 let instr = r.read_instruction()?;
@@ -607,6 +837,8 @@ impl Machine {
                 self.check_time()?;
             }
 
+            self.check_steps()?;
+
             match instr {
                 Load(n) => self.load(frame.sptr + n)?,
                 LoadC(n) => self.load_c(frame, n)?,
@@ -678,11 +910,15 @@ impl Machine {
                 TailApplySelf(n) => self.tail_apply_self(frame, n)?,
                 CallSelf(n) => self.call_self(frame, n)?,
                 TailCallSelf(n) => self.tail_call(frame, n)?,
+                DynamicLet(n) => self.dynamic_let(frame, n)?,
                 Skip(n) => self.skip_stack(n as usize)?,
                 Return => {
                     match self.call_stack.pop() {
                         None => break,
                         Some(call) => {
+                            if let Some(depth) = frame.dynamic_depth {
+                                self.context.dynamic.borrow_mut().truncate(depth);
+                            }
                             self.clean_stack(frame.sptr as usize);
                             if frame.fn_on_stack {
                                 // Pop one more value for the function
@@ -695,7 +931,7 @@ impl Machine {
             }
         }
 
-        Ok(())
+        Ok(true)
     }
 
     fn build_closure(&mut self, code: &Code, n_const: u32, n_values: u32)
@@ -711,6 +947,34 @@ impl Machine {
         Ok(())
     }
 
+    /// Binds dynamic variables and calls the body of a `dynamic-let` form
+    /// as a new frame, so that it counts toward the call stack limit.
+    /// The bindings are removed when the frame returns.
+    fn dynamic_let(&mut self, frame: &mut StackFrame, n: u32) -> Result<(), Error> {
+        let fun = match self.value.take() {
+            Value::Lambda(l) => l,
+            ref v => return Err(From::from(ExecError::expected("lambda", v)))
+        };
+        let bindings = self.drain_stack_top(n * 2)?.collect::<Vec<_>>();
+
+        let mut new = Vec::with_capacity(n as usize);
+
+        for pair in bindings.chunks(2) {
+            match pair[0] {
+                Value::Name(name) => new.push((name, pair[1].clone())),
+                ref v => return Err(From::from(ExecError::expected("name", v)))
+            }
+        }
+
+        let depth = self.context.dynamic.borrow().len();
+
+        self.call_lambda(frame, fun, 0, false)?;
+        frame.dynamic_depth = Some(depth);
+
+        self.context.dynamic.borrow_mut().extend(new);
+        Ok(())
+    }
+
     fn build_list(&mut self, n: u32) -> Result<(), ExecError> {
         let v = self.drain_stack_top(n)?.collect::<Vec<_>>().into();
         self.value = v;
@@ -736,6 +1000,19 @@ impl Machine {
         Ok(())
     }
 
+    fn check_steps(&self) -> Result<(), RestrictError> {
+        if let Some(max_steps) = self.context.restrict().max_steps {
+            let n = self.context.steps.get() + 1;
+            self.context.steps.set(n);
+
+            if n > max_steps {
+                return Err(RestrictError::StepLimitExceeded);
+            }
+        }
+
+        Ok(())
+    }
+
     fn get_sys_fn(&self, n: u32) -> Result<(Name, &'static SystemFn), ExecError> {
         get_standard_name(n).and_then(|n| get_system_fn(n).map(|f| (n, f)))
             .ok_or(ExecError::InvalidSystemFn(n))
@@ -834,7 +1111,7 @@ impl Machine {
     fn call_lambda(&mut self, frame: &mut StackFrame, lambda: Lambda,
             n_args: u32, fn_on_stack: bool) -> Result<(), Error> {
         let scope = lambda.scope.upgrade()
-            .expect("Lambda scope has been destroyed");
+            .ok_or(ExecError::ScopeDropped)?;
 
         if self.stack.len() < n_args as usize {
             return Err(From::from(RestrictError::ValueStackExceeded));
@@ -849,6 +1126,7 @@ impl Machine {
             iptr: 0,
             sptr: self.stack.len() as u32 - n_args,
             fn_on_stack,
+            dynamic_depth: None,
         });
 
         self.save_frame(old_frame)?;
@@ -1397,7 +1675,13 @@ impl Machine {
     fn increment(&mut self) -> Result<(), ExecError> {
         match self.value {
             Value::Float(ref mut f) => *f += 1.0,
-            Value::Integer(ref mut i) => *i = i.clone() + Integer::one(),
+            Value::Integer(ref mut i) => {
+                *i = if self.context.scope().fixed_integers() {
+                    fixed_step(i, i64::checked_add)?
+                } else {
+                    i.clone() + Integer::one()
+                };
+            }
             Value::Ratio(ref mut r) => *r = r.clone() + Ratio::one(),
             ref v => return Err(ExecError::expected("number", v))
         }
@@ -1407,7 +1691,13 @@ impl Machine {
     fn decrement(&mut self) -> Result<(), ExecError> {
         match self.value {
             Value::Float(ref mut f) => *f -= 1.0,
-            Value::Integer(ref mut i) => *i = i.clone() - Integer::one(),
+            Value::Integer(ref mut i) => {
+                *i = if self.context.scope().fixed_integers() {
+                    fixed_step(i, i64::checked_sub)?
+                } else {
+                    i.clone() - Integer::one()
+                };
+            }
             Value::Ratio(ref mut r) => *r = r.clone() - Ratio::one(),
             ref v => return Err(ExecError::expected("number", v))
         }
@@ -1477,6 +1767,14 @@ impl Machine {
     }
 }
 
+/// Increments or decrements an integer using checked `i64` arithmetic,
+/// as when fixed-width integers are enabled.
+fn fixed_step(i: &Integer, op: fn(i64, i64) -> Option<i64>) -> Result<Integer, ExecError> {
+    i.to_i64().and_then(|i| op(i, 1))
+        .map(Integer::from_i64)
+        .ok_or(ExecError::Overflow)
+}
+
 fn get_bool(v: &Value) -> Result<bool, ExecError> {
     FromValueRef::from_value_ref(v)
 }
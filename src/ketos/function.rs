@@ -14,12 +14,12 @@ use crate::bytes::Bytes;
 use crate::error::Error;
 use crate::exec::{Context, ExecError};
 use crate::integer::{Integer, Ratio};
-use crate::name::{Name, NUM_SYSTEM_FNS};
-use crate::restrict::RestrictError;
+use crate::name::{standard_names, Name, NUM_SYSTEM_FNS};
+use crate::restrict::{Capabilities, RestrictError};
 use crate::scope::{Scope, WeakScope};
-use crate::string_fmt::format_string;
+use crate::string_fmt::format_string_with;
 use crate::structs::StructDef;
-use crate::value::{FromValueRef, Value};
+use crate::value::{FromValueRef, ForeignValue, Value};
 
 use self::Arity::*;
 
@@ -76,7 +76,10 @@ Given no arguments, returns the multiplicative identity, `1`."),
 "Returns the cumulative quotient of successive arguments,
 rounded toward negative infinity."),
     sys_fn!(fn_rem,         Exact(2),
-"Returns the remainder of two arguments."),
+"Returns the remainder of two arguments.
+
+The result has the sign of the dividend, e.g. `(rem -7 3)` is `-1`.
+An error is returned if the divisor is zero, including a float zero."),
     sys_fn!(fn_shl,         Exact(2),
 "Returns an integer, bit shifted left by a given number."),
     sys_fn!(fn_shr,         Exact(2),
@@ -145,8 +148,13 @@ result in an error."),
 "Append a series of elements to a given list."),
     sys_fn!(fn_elt,         Exact(2),
 "Returns an element from a sequence, starting at zero index."),
-    sys_fn!(fn_concat,      Min(1),
-"Concatenates a series of sequences."),
+    sys_fn!(fn_concat,      Min(0),
+"Concatenates a series of sequences.
+
+The kind of the first argument determines the result; when it is a list,
+any argument which is not a list is an error.
+Given no arguments, returns the empty list.
+Given a single argument, returns it unchanged, sharing its storage."),
     sys_fn!(fn_join,        Min(1),
 "Joins a series of lists or strings and chars using a separator value."),
     sys_fn!(fn_len,         Exact(1),
@@ -154,7 +162,11 @@ result in an error."),
 
 String length is in bytes rather than characters."),
     sys_fn!(fn_slice,       Range(2, 3),
-"Returns a subsequence of a list or string."),
+"Returns a subsequence of a list or string.
+
+Negative indices count backward from the end of the sequence.
+An index beyond either end of the sequence is an error, as is
+a slice whose beginning is after its end."),
     sys_fn!(fn_first,       Exact(1),
 "Returns the first element of the given list or string."),
     sys_fn!(fn_second,      Exact(1),
@@ -202,7 +214,11 @@ Given no arguments, returns the value of `NaN`."),
     sys_fn!(fn_numer,       Exact(1),
 "Returns the numerator of a ratio."),
     sys_fn!(fn_rat,         Range(1, 2),
-"Returns the given numerical value as a ratio."),
+"Returns the given numerical value as a ratio.
+
+Given two integers, returns a ratio of numerator and denominator,
+reduced to lowest terms with the sign carried by the numerator.
+An error is returned if the denominator is zero."),
     sys_fn!(fn_recip,       Exact(1),
 "Returns the reciprocal of the given numeric value.
 
@@ -263,6 +279,15 @@ It accepts an optional parameter describing the reason for the panic."),
 "Returns the exclusive-or of the given boolean values."),
     sys_fn!(fn_not,         Exact(1),
 "Returns the inverse of the given boolean value."),
+    sys_fn!(fn_make_ratio,  Exact(2),
+"Returns a ratio of the given numerator and denominator.
+
+The result is reduced to lowest terms with the sign carried by the numerator.
+An error is returned if the denominator is zero."),
+    sys_fn!(fn_numer,       Exact(1),
+"Returns the numerator of a ratio."),
+    sys_fn!(fn_denom,       Exact(1),
+"Returns the denominator of a ratio."),
 ];
 
 /// Describes the number of arguments a function may accept.
@@ -439,14 +464,42 @@ fn expect_integer_owned(v: Value) -> Result<Integer, ExecError> {
     }
 }
 
-fn expect_number(v: &Value) -> Result<(), ExecError> {
+pub(crate) fn expect_number(v: &Value) -> Result<(), ExecError> {
     match *v {
         Value::Float(_) | Value::Integer(_) | Value::Ratio(_) => Ok(()),
         _ => Err(ExecError::expected("number", v))
     }
 }
 
-fn test_zero<T: Zero>(t: &T) -> Result<(), ExecError> {
+/// Checks the first operand of an arithmetic function,
+/// which may be a number or a foreign value.
+fn expect_number_or_foreign(v: &Value) -> Result<(), ExecError> {
+    match *v {
+        Value::Foreign(_) => Ok(()),
+        _ => expect_number(v)
+    }
+}
+
+/// Checks an arithmetic operand. Any value is accepted if the left operand
+/// is a foreign value, which will handle the operation itself.
+fn expect_operand(lhs: &Value, rhs: &Value) -> Result<(), ExecError> {
+    match *lhs {
+        Value::Foreign(_) => Ok(()),
+        _ => expect_number(rhs)
+    }
+}
+
+/// Calls an arithmetic hook of a foreign value, if `lhs` is a foreign value
+/// which supports the operation.
+fn foreign_arith<F>(lhs: &Value, rhs: &Value, f: F) -> Option<Result<Value, Error>>
+        where F: FnOnce(&dyn ForeignValue, &Value) -> Option<Result<Value, ExecError>> {
+    match *lhs {
+        Value::Foreign(ref a) => f(&**a, rhs).map(|r| r.map_err(From::from)),
+        _ => None
+    }
+}
+
+pub(crate) fn test_zero<T: Zero>(t: &T) -> Result<(), ExecError> {
     if t.is_zero() {
         Err(ExecError::DivideByZero)
     } else {
@@ -470,7 +523,7 @@ pub fn value_is(scope: &Scope, a: &Value, ty: Name) -> bool {
     }
 }
 
-fn coerce_numbers(lhs: Value, rhs: &Value) -> Result<(Value, Cow<Value>), ExecError> {
+pub(crate) fn coerce_numbers(lhs: Value, rhs: &Value) -> Result<(Value, Cow<Value>), ExecError> {
     let (lhs, rhs) = match (lhs, rhs) {
         (lhs @ Value::Float(_), rhs @ &Value::Float(_))
         | (lhs @ Value::Integer(_), rhs @ &Value::Integer(_))
@@ -510,10 +563,10 @@ fn fn_add(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
     let mut v = args[0].take();
 
-    expect_number(&v)?;
+    expect_number_or_foreign(&v)?;
 
     for arg in &args[1..] {
-        expect_number(arg)?;
+        expect_operand(&v, arg)?;
         v = add_number(ctx, v, arg)?;
     }
 
@@ -522,11 +575,18 @@ fn fn_add(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
 /// Returns the result of adding two values together.
 pub fn add_number(ctx: &Context, lhs: Value, rhs: &Value) -> Result<Value, Error> {
+    if let Some(r) = foreign_arith(&lhs, rhs, |a, b| a.add_value(b)) {
+        return r;
+    }
+
     let (lhs, rhs) = coerce_numbers(lhs, rhs)?;
 
     match (lhs, &*rhs) {
         (Value::Float(a), &Value::Float(b)) => Ok((a + b).into()),
         (Value::Integer(ref a), &Value::Integer(ref b)) => {
+            if ctx.scope().fixed_integers() {
+                return fixed_arith(ctx, a, b, i64::checked_add);
+            }
             check_bits(ctx, max(a.bits(), b.bits()) + 1)?;
             Ok((a + b).into())
         }
@@ -552,12 +612,15 @@ fn fn_sub(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let mut v = args[0].take();
 
     if args.len() == 1 {
-        neg_number(v)
+        match neg_number(v)? {
+            Value::Integer(i) => fixed_integer(ctx, i),
+            v => Ok(v)
+        }
     } else {
-        expect_number(&v)?;
+        expect_number_or_foreign(&v)?;
 
         for arg in &args[1..] {
-            expect_number(arg)?;
+            expect_operand(&v, arg)?;
             v = sub_number(ctx, v, arg)?;
         }
 
@@ -577,11 +640,18 @@ pub fn neg_number(v: Value) -> Result<Value, Error> {
 
 /// Returns the resulting of subtracting a value from another.
 pub fn sub_number(ctx: &Context, lhs: Value, rhs: &Value) -> Result<Value, Error> {
+    if let Some(r) = foreign_arith(&lhs, rhs, |a, b| a.sub_value(b)) {
+        return r;
+    }
+
     let (lhs, rhs) = coerce_numbers(lhs, rhs)?;
 
     match (lhs, &*rhs) {
         (Value::Float(a), &Value::Float(b)) => Ok((a - b).into()),
         (Value::Integer(ref a), &Value::Integer(ref b)) => {
+            if ctx.scope().fixed_integers() {
+                return fixed_arith(ctx, a, b, i64::checked_sub);
+            }
             check_bits(ctx, max(a.bits(), b.bits()) + 1)?;
             Ok((a - b).into())
         }
@@ -612,10 +682,10 @@ fn fn_mul(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
     let mut v = args[0].take();
 
-    expect_number(&v)?;
+    expect_number_or_foreign(&v)?;
 
     for arg in &args[1..] {
-        expect_number(arg)?;
+        expect_operand(&v, arg)?;
         v = mul_number(ctx, v, arg)?;
     }
 
@@ -624,11 +694,18 @@ fn fn_mul(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
 /// Returns the result of multiplying two values together.
 pub fn mul_number(ctx: &Context, lhs: Value, rhs: &Value) -> Result<Value, Error> {
+    if let Some(r) = foreign_arith(&lhs, rhs, |a, b| a.mul_value(b)) {
+        return r;
+    }
+
     let (lhs, rhs) = coerce_numbers(lhs, rhs)?;
 
     match (lhs, &*rhs) {
         (Value::Float(a), &Value::Float(b)) => Ok((a * b).into()),
         (Value::Integer(ref a), &Value::Integer(ref b)) => {
+            if ctx.scope().fixed_integers() {
+                return fixed_arith(ctx, a, b, i64::checked_mul);
+            }
             check_bits(ctx, a.bits() + b.bits())?;
             Ok((a * b).into())
         }
@@ -702,13 +779,46 @@ fn try_mul(ctx: &Context, lhs: &Integer, rhs: &Integer) -> Result<Integer, Error
     Ok(lhs * rhs)
 }
 
-fn try_mul_assign(ctx: &Context, lhs: &mut Integer, rhs: &Integer) -> Result<(), Error> {
+pub(crate) fn try_mul_assign(ctx: &Context, lhs: &mut Integer, rhs: &Integer)
+        -> Result<(), Error> {
     check_bits(ctx, lhs.bits() + rhs.bits())?;
 
     *lhs *= rhs;
     Ok(())
 }
 
+/// Returns an `Overflow` error if fixed-width integers are enabled
+/// and the given integer does not fit in an `i64`.
+pub(crate) fn check_fixed(ctx: &Context, i: &Integer) -> Result<(), ExecError> {
+    if ctx.scope().fixed_integers() && i.to_i64().is_none() {
+        Err(ExecError::Overflow)
+    } else {
+        Ok(())
+    }
+}
+
+/// Performs an integer operation using checked `i64` arithmetic,
+/// as when fixed-width integers are enabled.
+/// Operands or a result outside the range of `i64` produce an `Overflow` error.
+pub(crate) fn fixed_arith(ctx: &Context, a: &Integer, b: &Integer,
+        op: fn(i64, i64) -> Option<i64>) -> Result<Value, Error> {
+    let r = match (a.to_i64(), b.to_i64()) {
+        (Some(a), Some(b)) => op(a, b).ok_or(ExecError::Overflow)?,
+        _ => return Err(From::from(ExecError::Overflow))
+    };
+
+    let r = Integer::from_i64(r);
+    check_bits(ctx, r.bits())?;
+    Ok(r.into())
+}
+
+/// Returns an integer result as a value or, if fixed-width integers are
+/// enabled and the result does not fit in an `i64`, an `Overflow` error.
+pub(crate) fn fixed_integer(ctx: &Context, i: Integer) -> Result<Value, Error> {
+    check_fixed(ctx, &i)?;
+    Ok(i.into())
+}
+
 fn check_bits(ctx: &Context, bits: usize) -> Result<(), RestrictError> {
     if bits > ctx.restrict().max_integer_size {
         Err(RestrictError::IntegerLimitExceeded)
@@ -739,7 +849,7 @@ fn pow_number(ctx: &Context, lhs: Value, rhs: Value) -> Result<Value, Error> {
                 Ok(a.powf(b).into())
             } else {
                 let exp = b.to_u32().ok_or(ExecError::Overflow)?;
-                try_pow(ctx, a, exp).map(|i| i.into())
+                fixed_integer(ctx, try_pow(ctx, a, exp)?)
             }
         }
         (Value::Ratio(ref a), &Value::Ratio(ref b)) => {
@@ -755,6 +865,37 @@ fn pow_number(ctx: &Context, lhs: Value, rhs: Value) -> Result<Value, Error> {
     }
 }
 
+/// Returns a value raised to an exponent.
+///
+/// Unlike `^`, an integer or ratio raised to a negative integer exponent
+/// produces an exact ratio rather than a float.
+pub fn expt_number(ctx: &Context, lhs: Value, rhs: Value) -> Result<Value, Error> {
+    expect_number(&lhs)?;
+    expect_number(&rhs)?;
+
+    let exp = match rhs {
+        Value::Integer(ref i) if i.is_negative() => i.abs(),
+        Value::Ratio(ref r) if r.is_integer() && r.is_negative() => r.numer().abs(),
+        _ => return pow_number(ctx, lhs, rhs)
+    };
+
+    let base = match lhs {
+        Value::Integer(i) => Ratio::from_integer(i),
+        Value::Ratio(r) => r,
+        _ => return pow_number(ctx, lhs, rhs)
+    };
+
+    if base.is_zero() {
+        return Err(From::from(ExecError::DivideByZero));
+    }
+
+    let exp = exp.to_u32().ok_or(ExecError::Overflow)?;
+    let a = try_pow(ctx, base.numer(), exp)?;
+    let b = try_pow(ctx, base.denom(), exp)?;
+
+    Ok(Ratio::new(b, a).into())
+}
+
 fn pow_ratio_integer(ctx: &Context, lhs: &Ratio, rhs: &Integer) -> Result<Value, Error> {
     if rhs.is_negative() {
         let lhs = lhs.to_f64().ok_or(ExecError::Overflow)?;
@@ -774,14 +915,14 @@ fn pow_ratio_integer(ctx: &Context, lhs: &Ratio, rhs: &Integer) -> Result<Value,
 fn fn_div(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let mut v = args[0].take();
 
-    expect_number(&v)?;
+    expect_number_or_foreign(&v)?;
 
     if args.len() == 1 {
         // Call div instead of recip so that (/ 1) = 1
         div_number(ctx, 1.into(), &v)
     } else {
         for arg in &args[1..] {
-            expect_number(arg)?;
+            expect_operand(&v, arg)?;
             v = div_number(ctx, v, arg)?;
         }
 
@@ -827,6 +968,10 @@ fn floor_recip_number(v: Value) -> Result<Value, Error> {
 
 /// Returns the result of dividing two values.
 pub fn div_number(ctx: &Context, lhs: Value, rhs: &Value) -> Result<Value, Error> {
+    if let Some(r) = foreign_arith(&lhs, rhs, |a, b| a.div_value(b)) {
+        return r;
+    }
+
     let (lhs, rhs) = coerce_numbers(lhs, rhs)?;
 
     match (lhs, &*rhs) {
@@ -836,7 +981,7 @@ pub fn div_number(ctx: &Context, lhs: Value, rhs: &Value) -> Result<Value, Error
         (Value::Integer(ref a), &Value::Integer(ref b)) => {
             test_zero(b)?;
             if a.is_multiple_of(b) {
-                Ok((a / b).into())
+                fixed_integer(ctx, a / b)
             } else {
                 Ok(Ratio::new(a.clone(), b.clone()).into())
             }
@@ -867,6 +1012,9 @@ pub fn floor_div_number_step(ctx: &Context, lhs: Value, rhs: &Value) -> Result<V
     match (lhs, &*rhs) {
         (Value::Integer(ref a), &Value::Integer(ref b)) => {
             test_zero(b)?;
+            if ctx.scope().fixed_integers() {
+                return fixed_arith(ctx, a, b, i64::checked_div);
+            }
             Ok((a / b).into())
         }
         (lhs, rhs) => div_number(ctx, lhs, rhs)
@@ -889,6 +1037,7 @@ fn rem_number(lhs: Value, rhs: &Value) -> Result<Value, Error> {
 
     match (lhs, &*rhs) {
         (Value::Float(a), &Value::Float(b)) => {
+            test_zero(&b)?;
             Ok((a % b).into())
         }
         (Value::Integer(ref a), &Value::Integer(ref b)) => {
@@ -921,7 +1070,7 @@ fn shl_integer(ctx: &Context, lhs: &Value, rhs: &Value) -> Result<Value, Error>
     match rhs.to_u32() {
         Some(n) => {
             check_bits(ctx, lhs.bits() + n as usize)?;
-            Ok((lhs << (n as usize)).into())
+            fixed_integer(ctx, lhs << (n as usize))
         }
         None => Err(From::from(ExecError::Overflow)),
     }
@@ -1355,20 +1504,28 @@ fn fn_new(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     def.def().from_fields(ctx.scope(), &def, &mut fields)
 }
 
+/// Formats values according to the `*print-readably*` dynamic variable.
+fn format_args(ctx: &Context, fmt: &str, values: &[Value]) -> Result<String, ExecError> {
+    let readably = ctx.dynamic_flag(standard_names::PRINT_READABLY);
+    format_string_with(&ctx.scope().borrow_names(), fmt, values, readably)
+}
+
 /// `format` returns a formatted string.
 fn fn_format(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let fmt = get_string(&args[0])?;
 
-    let s = format_string(&ctx.scope().borrow_names(), fmt, &args[1..])?;
+    let s = format_args(ctx, fmt, &args[1..])?;
     Ok(s.into())
 }
 
 /// `print` prints a formatted string to `stdout`.
 fn fn_print(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    ctx.restrict().require(Capabilities::IO)?;
+
     let fmt = get_string(&args[0])?;
     let scope = ctx.scope();
 
-    let s = format_string(&scope.borrow_names(), fmt, &args[1..])?;
+    let s = format_args(ctx, fmt, &args[1..])?;
 
     scope.io().stdout.write_all(s.as_bytes())?;
     scope.io().stdout.flush()?;
@@ -1378,10 +1535,12 @@ fn fn_print(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
 /// `println` prints a formatted string to `stdout`, followed by a newline.
 fn fn_println(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    ctx.restrict().require(Capabilities::IO)?;
+
     let fmt = get_string(&args[0])?;
     let scope = ctx.scope();
 
-    let mut s = format_string(&scope.borrow_names(), fmt, &args[1..])?;
+    let mut s = format_args(ctx, fmt, &args[1..])?;
     if !s.ends_with('\n') {
         s.push('\n');
     }
@@ -1394,10 +1553,12 @@ fn fn_println(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
 /// `eprint` prints a formatted string to `stderr`.
 fn fn_eprint(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    ctx.restrict().require(Capabilities::IO)?;
+
     let fmt = get_string(&args[0])?;
     let scope = ctx.scope();
 
-    let s = format_string(&scope.borrow_names(), fmt, &args[1..])?;
+    let s = format_args(ctx, fmt, &args[1..])?;
 
     scope.io().stderr.write_all(s.as_bytes())?;
     scope.io().stderr.flush()?;
@@ -1407,10 +1568,12 @@ fn fn_eprint(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
 /// `println` prints a formatted string to `stdout`, followed by a newline.
 fn fn_eprintln(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    ctx.restrict().require(Capabilities::IO)?;
+
     let fmt = get_string(&args[0])?;
     let scope = ctx.scope();
 
-    let mut s = format_string(&scope.borrow_names(), fmt, &args[1..])?;
+    let mut s = format_args(ctx, fmt, &args[1..])?;
     if !s.ends_with('\n') {
         s.push('\n');
     }
@@ -1467,6 +1630,10 @@ fn fn_elt(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 /// (concat "foo" #'/' "bar")
 /// ```
 fn fn_concat(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    if args.is_empty() {
+        return Ok(Value::Unit);
+    }
+
     match args[0] {
         Value::Unit | Value::List(_) => concat_list(args),
         Value::Char(_) | Value::String(_) => concat_string(args),
@@ -1648,98 +1815,53 @@ fn fn_len(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 
 /// `slice` returns a subsequence of a list or string.
 fn fn_slice(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
-    let begin = usize::from_value_ref(&args[1])?;
-
-    if args.len() == 2 {
-        match args[0] {
-            Value::Unit => {
-                if begin > 0 {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else {
-                    Ok(Value::Unit)
-                }
-            }
-            Value::List(ref li) => {
-                let n = li.len();
-                if begin > n {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else {
-                    Ok(li.slice(begin..).into())
-                }
-            }
-            Value::String(ref s) => {
-                let n = s.len();
-                if begin > n {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else if !s.is_char_boundary(begin) {
-                    Err(From::from(ExecError::NotCharBoundary(begin)))
-                } else {
-                    Ok(s.slice(begin..).into())
-                }
-            }
-            Value::Bytes(ref b) => {
-                let n = b.len();
-                if begin > n {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else {
-                    Ok(b.slice(begin..).into())
-                }
-            }
-            ref v => Err(From::from(ExecError::expected("list or string", v)))
-        }
-    } else {
-        let end = usize::from_value_ref(&args[2])?;
+    let n = match args[0] {
+        Value::Unit => 0,
+        Value::List(ref li) => li.len(),
+        Value::String(ref s) => s.len(),
+        Value::Bytes(ref b) => b.len(),
+        ref v => return Err(From::from(ExecError::expected("list or string", v)))
+    };
 
-        if end < begin {
-            return Err(From::from(ExecError::InvalidSlice(begin, end)));
-        }
+    let begin = slice_index(&args[1], n)?;
+    let end = match args.get(2) {
+        Some(v) => slice_index(v, n)?,
+        None => n
+    };
 
-        match args[0] {
-            Value::Unit => {
-                if begin > 0 {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else if end > 0 {
-                    Err(From::from(ExecError::OutOfBounds(end)))
-                } else {
-                    Ok(Value::Unit)
-                }
-            }
-            Value::List(ref li) => {
-                let n = li.len();
-                if begin > n {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else if end > n {
-                    Err(From::from(ExecError::OutOfBounds(end)))
-                } else {
-                    Ok(li.slice(begin..end).into())
-                }
-            }
-            Value::String(ref s) => {
-                let n = s.len();
-                if begin > n {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else if end > n {
-                    Err(From::from(ExecError::OutOfBounds(end)))
-                } else if !s.is_char_boundary(begin) {
-                    Err(From::from(ExecError::NotCharBoundary(begin)))
-                } else if !s.is_char_boundary(end) {
-                    Err(From::from(ExecError::NotCharBoundary(end)))
-                } else {
-                    Ok(s.slice(begin..end).into())
-                }
-            }
-            Value::Bytes(ref b) => {
-                let n = b.len();
-                if begin > n {
-                    Err(From::from(ExecError::OutOfBounds(begin)))
-                } else if end > n {
-                    Err(From::from(ExecError::OutOfBounds(end)))
-                } else {
-                    Ok(b.slice(begin..end).into())
-                }
+    if end < begin {
+        return Err(From::from(ExecError::InvalidRange(
+            Box::new((args[1].clone(), args[2].clone())))));
+    }
+
+    match args[0] {
+        Value::List(ref li) => Ok(li.slice(begin..end).into()),
+        Value::String(ref s) => {
+            if !s.is_char_boundary(begin) {
+                Err(From::from(ExecError::NotCharBoundary(begin)))
+            } else if !s.is_char_boundary(end) {
+                Err(From::from(ExecError::NotCharBoundary(end)))
+            } else {
+                Ok(s.slice(begin..end).into())
             }
-            ref v => Err(From::from(ExecError::expected("list or string", v)))
         }
+        Value::Bytes(ref b) => Ok(b.slice(begin..end).into()),
+        _ => Ok(Value::Unit)
+    }
+}
+
+/// Resolves a slice index into a sequence of length `len`.
+/// Negative indices count backward from the end of the sequence.
+fn slice_index(v: &Value, len: usize) -> Result<usize, ExecError> {
+    let i = isize::from_value_ref(v)?;
+
+    if i < 0 {
+        let back = i.unsigned_abs();
+        len.checked_sub(back).ok_or(ExecError::NegativeOutOfBounds(i))
+    } else if i as usize > len {
+        Err(ExecError::OutOfBounds(i as usize))
+    } else {
+        Ok(i as usize)
     }
 }
 
@@ -1779,6 +1901,7 @@ pub fn first(v: &Value) -> Result<Value, Error> {
     match *v {
         // There can't be an empty list, so this should never panic.
         Value::List(ref li) => Ok(li[0].clone()),
+        Value::Unit => Err(From::from(ExecError::OutOfBounds(0))),
         Value::String(ref s) => match s.chars().next() {
             Some(ch) => Ok(ch.into()),
             None => Err(From::from(ExecError::OutOfBounds(0)))
@@ -1797,6 +1920,7 @@ pub fn first(v: &Value) -> Result<Value, Error> {
 pub fn last(v: &Value) -> Result<Value, Error> {
     match *v {
         Value::List(ref li) => Ok(li.last().cloned().unwrap()),
+        Value::Unit => Err(From::from(ExecError::OutOfBounds(0))),
         Value::String(ref s) => match s.chars().next_back() {
             Some(ch) => Ok(ch.into()),
             None => Err(From::from(ExecError::OutOfBounds(0)))
@@ -1818,6 +1942,7 @@ pub fn init(v: &Value) -> Result<Value, Error> {
             let len = li.len();
             Ok(li.slice(..len - 1).into())
         }
+        Value::Unit => Err(From::from(ExecError::OutOfBounds(0))),
         Value::String(ref s) => {
             let mut chars = s.char_indices();
 
@@ -1846,6 +1971,7 @@ pub fn tail(v: &Value) -> Result<Value, Error> {
         Value::List(ref li) => {
             Ok(li.slice(1..).into())
         }
+        Value::Unit => Err(From::from(ExecError::OutOfBounds(0))),
         Value::String(ref s) => {
             let mut chars = s.chars();
 
@@ -1891,10 +2017,10 @@ fn fn_reverse(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 }
 
 /// `abs` returns the absolute value of the given numerical value.
-fn fn_abs(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+fn fn_abs(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     match args[0] {
         Value::Float(f) => Ok(f.abs().into()),
-        Value::Integer(ref i) => Ok(i.abs().into()),
+        Value::Integer(ref i) => fixed_integer(ctx, i.abs()),
         Value::Ratio(ref r) => Ok(r.abs().into()),
         ref v => Err(From::from(ExecError::expected("number", v)))
     }
@@ -1949,15 +2075,15 @@ fn fn_trunc(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 /// `int` truncates a float or ratio value and returns its whole portion as an integer.
 ///
 /// If the given value is infinite or `NaN`, an error will result.
-fn fn_int(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+fn fn_int(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     match args[0].take() {
         Value::Float(f) => match f {
             f if f.is_infinite() || f.is_nan() => Err(From::from(ExecError::Overflow)),
-            f => Integer::from_f64(f)
-                .map(Value::Integer).ok_or_else(|| From::from(ExecError::Overflow)),
+            f => Integer::from_f64(f).ok_or_else(|| From::from(ExecError::Overflow))
+                .and_then(|i| fixed_integer(ctx, i)),
         },
         Value::Integer(i) => Ok(i.into()),
-        Value::Ratio(ref r) => Ok(r.to_integer().into()),
+        Value::Ratio(ref r) => fixed_integer(ctx, r.to_integer()),
         ref v => Err(From::from(ExecError::expected("number", v)))
     }
 }
@@ -2011,10 +2137,10 @@ fn fn_nan(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 }
 
 /// `denom` returns the denominator of a ratio.
-fn fn_denom(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+fn fn_denom(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     match args[0] {
         Value::Integer(_) => Ok(Integer::one().into()),
-        Value::Ratio(ref r) => Ok(r.denom().clone().into()),
+        Value::Ratio(ref r) => fixed_integer(ctx, r.denom().clone()),
         ref v => Err(From::from(ExecError::expected("integer or ratio", v)))
     }
 }
@@ -2029,10 +2155,10 @@ fn fn_fract(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
 }
 
 /// `numer` returns the numerator of a ratio.
-fn fn_numer(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+fn fn_numer(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     match args[0].take() {
         i @ Value::Integer(_) => Ok(i),
-        Value::Ratio(r) => Ok(r.numer().clone().into()),
+        Value::Ratio(r) => fixed_integer(ctx, r.numer().clone()),
         ref v => Err(From::from(ExecError::expected("integer or ratio", v)))
     }
 }
@@ -2063,6 +2189,11 @@ fn fn_rat(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     }
 }
 
+/// `make-ratio` returns a ratio of the given numerator and denominator.
+fn fn_make_ratio(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    fn_rat(ctx, args)
+}
+
 /// `recip` returns the reciprocal of the given numeric value.
 /// If the value is of type integer, the value returned will be a ratio.
 fn fn_recip(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
@@ -246,6 +246,30 @@ impl Integer {
         Integer(self.0.abs())
     }
 
+    /// Returns the integer square root of an `Integer`,
+    /// rounded toward zero.
+    ///
+    /// # Panics
+    ///
+    /// If the value is negative.
+    #[inline]
+    pub fn sqrt(&self) -> Integer {
+        Integer(self.0.sqrt())
+    }
+
+    /// Returns the number of one bits in the absolute value of an `Integer`.
+    #[inline]
+    pub fn count_ones(&self) -> u64 {
+        self.0.magnitude().count_ones()
+    }
+
+    /// Returns the number of trailing zero bits in the absolute value of
+    /// an `Integer`, or `None` if the value is zero.
+    #[inline]
+    pub fn trailing_zeros(&self) -> Option<u64> {
+        self.0.trailing_zeros()
+    }
+
     /// Returns whether `self` is a multiple of `rhs`.
     #[inline]
     pub fn is_multiple_of(&self, rhs: &Integer) -> bool {
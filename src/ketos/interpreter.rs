@@ -1,24 +1,31 @@
 //! Provides a context in which to compile and execute code.
 
 use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::DefaultHasher;
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{stderr, Read, Write};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
-
-use crate::bytecode::Code;
-use crate::compile::compile;
-use crate::error::Error;
-use crate::exec::{call_function, execute, Context, ExecError};
-use crate::io::{GlobalIo, IoError, IoMode};
+use std::time::Duration;
+
+use crate::bytecode::{disassemble, Code};
+use crate::compile::{compile, CompileError};
+use crate::encode::{read_bytecode, write_bytecode};
+use crate::error::{Error, ErrorValue, TracedError};
+use crate::exec::{call_function, execute, Context, ExecError, ExecState, Suspended};
+use crate::io::{GlobalIo, IoError, IoMode, IoProvider};
 use crate::lexer::{CodeMap, Lexer};
-use crate::module::{BuiltinModuleLoader, FileModuleLoader, ModuleLoader, ModuleRegistry};
-use crate::name::{debug_names, display_names, NameStore};
+use crate::module::{builtin_module_names, is_builtin_module,
+    load_module_from_source, BuiltinModuleLoader, FileModuleLoader,
+    ModuleCode, ModuleLoader, ModuleRegistry};
+use crate::name::{debug_names, display_names, Name, NameStore};
 use crate::parser::{ParseError, Parser};
 use crate::restrict::RestrictConfig;
-use crate::scope::{GlobalScope, Scope};
+use crate::scope::{GlobalScope, MasterScope, NameOrigin, Scope};
 use crate::structs::StructDefMap;
-use crate::trace::{get_traceback, take_traceback, Trace};
+use crate::trace::{clear_traceback, get_traceback, set_traceback, take_traceback, Trace, TraceItem};
 use crate::value::Value;
 
 /// Builds an `Interpreter` with configured parameters.
@@ -49,6 +56,11 @@ pub struct Builder {
     struct_defs: Option<Rc<RefCell<StructDefMap>>>,
     module_loader: Option<Box<dyn ModuleLoader>>,
     search_paths: Option<Vec<PathBuf>>,
+    intern_strings: bool,
+    max_parse_nesting: Option<usize>,
+    exact_decimals: bool,
+    fixed_integers: bool,
+    compile_cache_size: usize,
 }
 
 macro_rules! exclude {
@@ -71,6 +83,11 @@ impl Builder {
             struct_defs: None,
             module_loader: None,
             search_paths: None,
+            intern_strings: false,
+            max_parse_nesting: None,
+            exact_decimals: false,
+            fixed_integers: false,
+            compile_cache_size: 0,
         }
     }
 
@@ -154,9 +171,83 @@ impl Builder {
         self
     }
 
+    /// Sets whether string values created by the parser should be interned,
+    /// so that equal strings share storage.
+    ///
+    /// This setting applies to the whole scope, including a `Scope` or
+    /// `Context` supplied to the `Builder`.
+    pub fn intern_strings(mut self, set: bool) -> Self {
+        self.intern_strings = set;
+        self
+    }
+
+    /// Sets the maximum depth of nested groups accepted by the parser.
+    ///
+    /// This setting applies to the whole scope, including a `Scope` or
+    /// `Context` supplied to the `Builder`.
+    /// See `GlobalScope::set_max_parse_nesting` for details.
+    pub fn max_parse_nesting(mut self, n: usize) -> Self {
+        self.max_parse_nesting = Some(n);
+        self
+    }
+
+    /// Sets whether decimal literals are parsed as exact `ratio` values.
+    ///
+    /// This setting applies to the whole scope, including a `Scope` or
+    /// `Context` supplied to the `Builder`.
+    /// See `GlobalScope::set_exact_decimals` for details.
+    pub fn exact_decimals(mut self, set: bool) -> Self {
+        self.exact_decimals = set;
+        self
+    }
+
+    /// Sets whether integer arithmetic is limited to the range of `i64`,
+    /// returning an `Overflow` error instead of growing beyond it.
+    ///
+    /// This setting applies to the whole scope, including a `Scope` or
+    /// `Context` supplied to the `Builder`.
+    /// See `GlobalScope::set_fixed_integers` for details.
+    pub fn fixed_integers(mut self, set: bool) -> Self {
+        self.fixed_integers = set;
+        self
+    }
+
+    /// Sets the maximum number of compiled inputs retained by `run_code`.
+    ///
+    /// See `Interpreter::set_compile_cache_size` for details.
+    pub fn compile_cache_size(mut self, size: usize) -> Self {
+        self.compile_cache_size = size;
+        self
+    }
+
     /// Consumes the `Builder` and creates an `Interpreter`.
     pub fn finish(self) -> Interpreter {
-        Interpreter::with_context(self.build_context())
+        let intern = self.intern_strings;
+        let max_nesting = self.max_parse_nesting;
+        let exact = self.exact_decimals;
+        let fixed = self.fixed_integers;
+        let cache_size = self.compile_cache_size;
+        let ctx = self.build_context();
+
+        if intern {
+            ctx.scope().set_string_interning(true);
+        }
+
+        if let Some(n) = max_nesting {
+            ctx.scope().set_max_parse_nesting(n);
+        }
+
+        if exact {
+            ctx.scope().set_exact_decimals(true);
+        }
+
+        if fixed {
+            ctx.scope().set_fixed_integers(true);
+        }
+
+        let interp = Interpreter::with_context(ctx);
+        interp.set_compile_cache_size(cache_size);
+        interp
     }
 
     fn build_context(mut self) -> Context {
@@ -208,6 +299,108 @@ impl Builder {
 #[derive(Clone)]
 pub struct Interpreter {
     context: Context,
+    cache: Rc<RefCell<CompileCache>>,
+    form_callback: RefCell<Option<FormCallback>>,
+}
+
+/// Counts lookups into the compile cache of an `Interpreter`
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompileCacheStats {
+    /// Number of inputs whose compiled code was found in the cache
+    pub hits: usize,
+    /// Number of inputs which were compiled and inserted into the cache
+    pub misses: usize,
+}
+
+/// Series of code objects compiled from a single input
+type CompiledInput = Rc<[Rc<Code>]>;
+
+/// Function called with the value of each top-level form run by `run_code`
+type FormCallback = Rc<dyn Fn(&Value)>;
+
+/// Least-recently-used cache of compiled code, keyed by source path and input
+///
+/// Entries are found by a hash of the path and input; the stored path and
+/// input are compared on lookup so that a hash collision is never mistaken
+/// for a hit.
+#[derive(Default)]
+struct CompileCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<u64, CacheEntry>,
+    /// Hash of each entry, ordered by the tick of its last use
+    lru: BTreeMap<u64, u64>,
+    stats: CompileCacheStats,
+}
+
+struct CacheEntry {
+    path: Option<String>,
+    input: String,
+    last_used: u64,
+    code: CompiledInput,
+}
+
+impl CompileCache {
+    fn hash_input(path: Option<&str>, input: &str) -> u64 {
+        let mut h = DefaultHasher::new();
+        path.hash(&mut h);
+        input.hash(&mut h);
+        h.finish()
+    }
+
+    fn get(&mut self, hash: u64, path: Option<&str>, input: &str) -> Option<CompiledInput> {
+        self.tick += 1;
+        let tick = self.tick;
+
+        match self.entries.get_mut(&hash) {
+            Some(ent) if ent.path.as_deref() == path && ent.input == input => {
+                self.lru.remove(&ent.last_used);
+                self.lru.insert(tick, hash);
+                ent.last_used = tick;
+                self.stats.hits += 1;
+                Some(ent.code.clone())
+            }
+            _ => None
+        }
+    }
+
+    fn insert(&mut self, hash: u64, path: Option<String>, input: &str, code: CompiledInput) {
+        self.stats.misses += 1;
+        self.tick += 1;
+
+        // An entry whose hash collides with this input is replaced
+        if let Some(old) = self.entries.remove(&hash) {
+            self.lru.remove(&old.last_used);
+        } else if self.entries.len() >= self.capacity {
+            self.evict_oldest();
+        }
+
+        self.lru.insert(self.tick, hash);
+        self.entries.insert(hash, CacheEntry{
+            path,
+            input: input.to_owned(),
+            last_used: self.tick,
+            code,
+        });
+    }
+
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+
+        while self.entries.len() > capacity {
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.lru.keys().next().cloned();
+
+        if let Some(tick) = oldest {
+            if let Some(hash) = self.lru.remove(&tick) {
+                self.entries.remove(&hash);
+            }
+        }
+    }
 }
 
 impl Interpreter {
@@ -239,7 +432,49 @@ impl Interpreter {
 
     /// Creates a new `Interpreter` using the given `Context` instance.
     pub fn with_context(context: Context) -> Interpreter {
-        Interpreter{ context }
+        Interpreter{
+            context,
+            cache: Rc::new(RefCell::new(CompileCache::default())),
+            form_callback: RefCell::new(None),
+        }
+    }
+
+    /// Creates a new `Interpreter` which begins with a copy of the global
+    /// scope of this interpreter.
+    ///
+    /// Definitions made in either interpreter after forking are not visible
+    /// to the other. Functions defined before forking, including those held
+    /// in lists, structs, and closures, resolve global names in the new
+    /// interpreter. Values which contain mutable state, such as boxes, remain
+    /// shared if they were created before forking; functions held within
+    /// such values still refer to this interpreter's global scope and fail
+    /// with `ExecError::ScopeDropped` once this interpreter is dropped.
+    ///
+    /// The new interpreter has its own execution counters, used in applying
+    /// restrictions, its own compile cache, of the same size as this
+    /// interpreter's, and its own module registry. Modules loaded before
+    /// forking are available to both interpreters; a module loaded by either
+    /// interpreter afterward is loaded only into that interpreter's registry.
+    ///
+    /// # Limitations
+    ///
+    /// Forked interpreters are not independent in every respect:
+    ///
+    /// * `Interpreter` is not `Send`, so a fork must be used on the thread
+    ///   which created it. `fork` does not support running requests
+    ///   concurrently on separate threads.
+    /// * The name store and codemap are shared and only grow. Names interned
+    ///   and source compiled by any fork remain in memory for as long as any
+    ///   of the forked interpreters exist.
+    /// * The `ModuleLoader` is shared, so a module loaded after forking is
+    ///   loaded again by each interpreter which imports it.
+    pub fn fork(&self) -> Interpreter {
+        let ctx = Context::new(self.scope().fork_scope(),
+            self.context.restrict().clone());
+        let interp = Interpreter::with_context(ctx);
+
+        interp.set_compile_cache_size(self.cache.borrow().capacity);
+        interp
     }
 
     /// Creates a new `Interpreter` using the given `Scope` instance.
@@ -325,6 +560,18 @@ impl Interpreter {
         take_traceback()
     }
 
+    /// Calls `f` with this interpreter, pairing any error it returns
+    /// with the traceback recorded while it ran.
+    ///
+    /// Any interpreter method may be called within `f`, e.g.
+    /// `interp.traced(|i| i.call("main", vec![]))`. The traceback is also
+    /// left in place for `get_traceback`, as for an untraced call.
+    pub fn traced<T, F>(&self, f: F) -> Result<T, TracedError>
+            where F: FnOnce(&Interpreter) -> Result<T, Error> {
+        clear_traceback();
+        f(self).map_err(|e| TracedError::new(e, get_traceback()))
+    }
+
     /// Formats a value into a string.
     pub fn format_value(&self, value: &Value) -> String {
         debug_names(&self.scope().borrow_names(), value).to_string()
@@ -367,6 +614,26 @@ impl Interpreter {
         Ok(v)
     }
 
+    /// Returns a human-readable listing of the bytecode of the named function.
+    ///
+    /// The named value must be a function defined in the global scope;
+    /// system functions have no bytecode and result in a type error.
+    pub fn disassemble(&self, name: &str) -> Result<String, Error> {
+        let name = self.scope().borrow_names_mut().add(name);
+
+        match self.scope().resolve_value(name).ok_or(ExecError::NameError(name))? {
+            Value::Lambda(ref l) => Ok(disassemble(&self.scope().borrow_names(),
+                &l.code, l.values.as_deref())?),
+            ref v => Err(From::from(ExecError::expected("lambda", v)))
+        }
+    }
+
+    /// Returns a human-readable listing of the bytecode of a compiled
+    /// code object.
+    pub fn disassemble_code(&self, code: &Code) -> Result<String, Error> {
+        Ok(disassemble(&self.scope().borrow_names(), code, None)?)
+    }
+
     fn call_main(&self) -> Result<(), Error> {
         if let Some(v) = self.get_value("main") {
             self.call_value(v, Vec::new())?;
@@ -374,11 +641,79 @@ impl Interpreter {
         Ok(())
     }
 
+    /// Defines a named constant in the interpreter scope.
+    ///
+    /// As with the `const` operator, references to the constant are replaced
+    /// by its value at compile time and code may not redefine the name.
+    /// Returns an error if the name is a standard name or an existing constant.
+    pub fn define_constant_value(&self, name: &str, value: Value) -> Result<(), Error> {
+        let name = self.scope().add_name(name);
+
+        if !MasterScope::can_define(name) {
+            return Err(From::from(CompileError::CannotDefine(name)));
+        }
+
+        if self.scope().contains_constant(name) {
+            return Err(From::from(CompileError::ConstantExists(name)));
+        }
+
+        self.scope().add_constant(name, value);
+        Ok(())
+    }
+
+    /// Defines `new` in the interpreter scope as an alias of `existing`,
+    /// binding it to the value to which `existing` currently resolves.
+    ///
+    /// Later redefinition of either name does not affect the other.
+    /// Returns an error if `existing` is not defined or if `new`
+    /// is a standard name which cannot be defined.
+    pub fn define_alias(&self, new: &str, existing: &str) -> Result<(), Error> {
+        let existing = self.scope().add_name(existing);
+        let value = self.scope().resolve_value(existing)
+            .ok_or(ExecError::NameError(existing))?;
+
+        let name = self.scope().add_name(new);
+
+        if !MasterScope::can_define(name) {
+            return Err(From::from(CompileError::CannotDefine(name)));
+        }
+
+        self.scope().add_value(name, value);
+        Ok(())
+    }
+
     /// Returns a value, if present, in the interpreter scope.
     pub fn get_value(&self, name: &str) -> Option<Value> {
         self.scope().get_named_value(name)
     }
 
+    /// Returns the names of all builtin functions and operators,
+    /// in sorted order.
+    pub fn builtin_names(&self) -> Vec<String> {
+        let mut names = MasterScope::names()
+            .map(|name| self.scope().with_name(name, |s| s.to_owned()))
+            .collect::<Vec<_>>();
+
+        names.sort();
+        names
+    }
+
+    /// Returns the names of all builtin modules and all modules loaded
+    /// into the interpreter, in sorted order.
+    pub fn module_names(&self) -> Vec<String> {
+        let mut names = builtin_module_names()
+            .map(|s| s.to_owned())
+            .collect::<Vec<_>>();
+
+        for name in self.scope().modules().module_names() {
+            names.push(self.scope().with_name(name, |s| s.to_owned()));
+        }
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
     /// Returns a borrowed reference to the contained context.
     pub fn context(&self) -> &Context {
         &self.context
@@ -389,6 +724,18 @@ impl Interpreter {
         self.context.scope()
     }
 
+    /// Returns a shared handle to the contained scope.
+    ///
+    /// The handle keeps the scope, including its names, alive after the
+    /// interpreter is dropped, so it may be used to resolve names when
+    /// decoding values with `decode_value`.
+    ///
+    /// `Scope` is reference-counted with `Rc` and is not `Send`; the handle
+    /// must remain on the thread which created the interpreter.
+    pub fn scope_handle(&self) -> Scope {
+        self.scope().clone()
+    }
+
     /// Sets the value of `argv` within the execution scope.
     pub fn set_args<T: AsRef<str>>(&self, args: &[T]) {
         let args = args.iter()
@@ -398,6 +745,38 @@ impl Interpreter {
         self.scope().add_named_value("argv", args.into());
     }
 
+    /// Compiles source code into a module and registers it with the
+    /// interpreter, allowing scripts to import it with `use`.
+    ///
+    /// Returns an error if a module of the same name is already loaded
+    /// or names a builtin module.
+    pub fn add_module(&self, name: &str, source: &str) -> Result<(), Error> {
+        let mods = self.scope().modules();
+        let mod_name = self.scope().add_name(name);
+
+        if is_builtin_module(name) || mods.get_module(mod_name).is_some() {
+            return Err(From::from(CompileError::DuplicateModule(mod_name)));
+        }
+
+        let scope = GlobalScope::new_using(mod_name, self.scope());
+        let m = load_module_from_source(self.context.with_scope(scope),
+            mod_name, source, Some(format!("<module {}>", name)))?;
+
+        mods.add_module(mod_name, m);
+        Ok(())
+    }
+
+    /// Compiles and executes a prelude of definitions in the interpreter scope,
+    /// making them available to all code subsequently run by the interpreter
+    /// without the use of `use`.
+    ///
+    /// No prelude is loaded unless this method is called. It is intended to
+    /// be called once, immediately after the interpreter is created.
+    /// Errors are reported as arising from the source `<prelude>`.
+    pub fn load_prelude(&self, source: &str) -> Result<(), Error> {
+        self.run_code_named(source, "<prelude>").map(|_| ())
+    }
+
     /// Compiles and executes the contents of a file.
     pub fn run_file(&self, path: &Path) -> Result<(), Error> {
         let mut f = File::open(path)
@@ -417,10 +796,238 @@ impl Interpreter {
         self.execute(c)
     }
 
+    /// Parses and executes a series of expressions and returns the last value.
+    ///
+    /// Unlike `run_code`, any error is returned as an `ErrorValue`,
+    /// wrapped in a foreign value, rather than as `Err`.
+    pub fn eval_catching(&self, input: &str) -> Value {
+        self.run_code(input, None).unwrap_or_else(|e| {
+            Value::new_foreign(ErrorValue::new(&e, &self.scope().borrow_names()))
+        })
+    }
+
     /// Parses and executes a series of expressions and return the last value.
+    ///
+    /// If the compile cache is enabled, code compiled from an identical
+    /// input string and path is reused rather than compiled again.
+    ///
+    /// If a form callback is set, it is called with the value of each
+    /// top-level expression, in order, as it is executed.
     pub fn run_code(&self, input: &str, path: Option<String>) -> Result<Value, Error> {
-        let code = self.compile_code(input, path)?;
-        self.execute_program(code)
+        let mut last_v = Value::Unit;
+        self.run_code_each(input, path, |v| last_v = v)?;
+        Ok(last_v)
+    }
+
+    /// Parses and executes a series of expressions and returns the value
+    /// of each top-level expression, in order.
+    ///
+    /// Execution stops at the first error, which is returned;
+    /// the values of any expressions executed before it are discarded.
+    /// Otherwise, this behaves as `run_code`.
+    pub fn run_code_returning_all(&self, input: &str) -> Result<Vec<Value>, Error> {
+        let mut values = Vec::new();
+        self.run_code_each(input, None, |v| values.push(v))?;
+        Ok(values)
+    }
+
+    /// Runs input as `run_code`, passing the value of each top-level
+    /// expression to `f`.
+    fn run_code_each<F>(&self, input: &str, path: Option<String>, mut f: F)
+            -> Result<(), Error> where F: FnMut(Value) {
+        let hash = if self.cache.borrow().capacity == 0 {
+            None
+        } else {
+            Some(CompileCache::hash_input(path.as_deref(), input))
+        };
+
+        let cached = hash.and_then(|hash|
+            self.cache.borrow_mut().get(hash, path.as_deref(), input));
+
+        let code = match cached {
+            Some(code) => code,
+            None => {
+                let code = self.compile_code(input, path.clone())?
+                    .into_iter().map(Rc::new).collect::<Rc<[_]>>();
+
+                if let Some(hash) = hash {
+                    self.cache.borrow_mut().insert(hash, path, input, code.clone());
+                }
+                code
+            }
+        };
+
+        for c in code.iter() {
+            let v = self.execute_code(c.clone())?;
+
+            let callback = self.form_callback.borrow().clone();
+
+            if let Some(callback) = callback {
+                callback(&v);
+            }
+
+            f(v);
+        }
+
+        Ok(())
+    }
+
+    /// Parses and executes a series of expressions, as `run_code`,
+    /// identifying the source by the given logical name.
+    ///
+    /// The name is used in place of a file name when reporting parse errors.
+    /// If an error occurs, the name is recorded as the outermost item
+    /// of the traceback.
+    pub fn run_code_named(&self, input: &str, name: &str) -> Result<Value, Error> {
+        let r = self.run_code(input, Some(name.to_owned()));
+
+        if r.is_err() {
+            let item = TraceItem::Source(self.scope().add_name(name));
+
+            match take_traceback() {
+                Some(mut trace) => {
+                    trace.push_outer(item);
+                    set_traceback(trace);
+                }
+                None => set_traceback(Trace::single(item, None))
+            }
+        }
+
+        r
+    }
+
+    /// Parses and executes a series of expressions, limiting both the
+    /// execution time and the number of instructions executed,
+    /// and returns the last value.
+    ///
+    /// The limits apply to the input as a whole and replace those of the
+    /// interpreter's `RestrictConfig` for this call only. Execution stops
+    /// at whichever limit is reached first, returning either
+    /// `RestrictError::ExecutionTimeExceeded` or
+    /// `RestrictError::StepLimitExceeded`.
+    pub fn eval_with_timeout_and_steps(&self, input: &str,
+            timeout: Duration, max_steps: u64) -> Result<Value, Error> {
+        let code = self.compile_code(input, None)?;
+
+        let ctx = self.context.with_restrict(RestrictConfig{
+            execution_time: Some(timeout),
+            max_steps: Some(max_steps),
+            .. self.context.restrict().clone()
+        });
+
+        // Hold the run level so that both limits span all expressions
+        ctx.inc_run_level();
+
+        let r = code.into_iter().try_fold(Value::Unit,
+            |_, code| execute(&ctx, Rc::new(code)));
+
+        ctx.dec_run_level();
+        r
+    }
+
+    /// Parses a series of expressions and executes them for at most
+    /// `steps` instructions.
+    ///
+    /// If execution completes within the budget, `ExecState::Done` is
+    /// returned with the value of the last expression. Otherwise,
+    /// `ExecState::Suspended` is returned, from which execution may be
+    /// continued with a further budget using `Suspended::resume`.
+    /// Running in successive budgets produces the same result as `run_code`.
+    pub fn run_code_with_budget(&self, input: &str, steps: u64) -> Result<ExecState, Error> {
+        let code = self.compile_code(input, None)?
+            .into_iter().map(Rc::new).collect();
+
+        Suspended::new(&self.context, code).resume(steps)
+    }
+
+    /// Executes compiled bytecode, as produced by `compile_to_bytes`,
+    /// within the interpreter scope and returns the value of the final
+    /// expression.
+    ///
+    /// An error is returned if the bytecode header does not contain the
+    /// expected magic number and version.
+    pub fn run_bytes(&self, bytes: &[u8]) -> Result<Value, Error> {
+        let mcode = read_bytecode(&mut &bytes[..],
+            Path::new("<bytes>"), &self.context)?;
+        mcode.run_in_context(&self.context)
+    }
+
+    /// Compiles a series of expressions into bytecode,
+    /// which may be executed using `run_bytes`.
+    ///
+    /// Compilation takes place in a new scope, so that macros and constants
+    /// defined by the input are not added to the interpreter scope.
+    pub fn compile_to_bytes(&self, input: &str) -> Result<Vec<u8>, Error> {
+        let scope = GlobalScope::new_using(self.scope().name(), self.scope());
+        let ctx = self.context.with_scope(scope);
+
+        let v = self.parse_exprs(input, None)?;
+        let code = v.iter()
+            .map(|v| compile(&ctx, v).map(Rc::new))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mcode = ModuleCode::new(code, ctx.scope());
+        let mut buf = Vec::new();
+
+        write_bytecode(&mut buf, Path::new("<bytes>"), &mcode,
+            &self.scope().borrow_names())?;
+
+        Ok(buf)
+    }
+
+    /// Sets the provider of file access for builtin functions.
+    ///
+    /// The provider is shared by all scopes using the same `GlobalIo` instance.
+    /// Whether the functions of the `file` module may be called is controlled
+    /// by the `Capabilities::FILES` capability.
+    pub fn set_io_provider(&self, provider: Rc<dyn IoProvider>) {
+        self.scope().io().set_provider(provider);
+    }
+
+    /// Sets the maximum number of compiled inputs retained by `run_code`.
+    ///
+    /// The cache is keyed by a hash of the source string and path. It is disabled by default,
+    /// with a size of `0`. When full, the least recently used entry is evicted.
+    ///
+    /// Compilation may modify the global scope, for example, by defining
+    /// `const` values or macros. Such effects are not repeated when
+    /// cached code is reused.
+    pub fn set_compile_cache_size(&self, size: usize) {
+        self.cache.borrow_mut().set_capacity(size);
+    }
+
+    /// Sets a function to be called by `run_code` with the value of each
+    /// top-level expression after it is executed.
+    ///
+    /// The callback only observes values; it does not affect the result
+    /// of `run_code`.
+    pub fn set_form_callback<F>(&self, f: F) where F: Fn(&Value) + 'static {
+        *self.form_callback.borrow_mut() = Some(Rc::new(f));
+    }
+
+    /// Removes the function set by `set_form_callback`, if any.
+    pub fn clear_form_callback(&self) {
+        *self.form_callback.borrow_mut() = None;
+    }
+
+    /// Sets a function to be called with each name reference resolved
+    /// while compiling code in the interpreter scope, along with where
+    /// the name resolves. This is intended for debugging name resolution,
+    /// e.g. when a local binding shadows a global value.
+    ///
+    /// See `GlobalScope::set_name_observer` for details.
+    pub fn set_name_observer<F>(&self, f: F) where F: Fn(Name, NameOrigin) + 'static {
+        self.scope().set_name_observer(Some(Rc::new(f)));
+    }
+
+    /// Removes the function set by `set_name_observer`, if any.
+    pub fn clear_name_observer(&self) {
+        self.scope().set_name_observer(None);
+    }
+
+    /// Returns the number of hits and misses in the compile cache.
+    pub fn compile_cache_stats(&self) -> CompileCacheStats {
+        self.cache.borrow().stats
     }
 
     /// Compiles and compiles a single expression and returns a code object.
@@ -487,3 +1094,47 @@ impl Interpreter {
         self.call_main()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+    use super::CompileCache;
+
+    #[test]
+    fn test_cache_collision() {
+        let mut cache = CompileCache::default();
+        cache.set_capacity(2);
+
+        cache.insert(1, None, "a", Rc::new([]));
+        assert!(cache.get(1, None, "a").is_some());
+        // An input with the same hash is not mistaken for the cached one
+        assert!(cache.get(1, None, "b").is_none());
+        assert!(cache.get(1, Some("a.ket"), "a").is_none());
+
+        cache.insert(1, None, "b", Rc::new([]));
+        assert!(cache.get(1, None, "a").is_none());
+        assert!(cache.get(1, None, "b").is_some());
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.lru.len(), 1);
+    }
+
+    #[test]
+    fn test_cache_eviction() {
+        let mut cache = CompileCache::default();
+        cache.set_capacity(2);
+
+        cache.insert(1, None, "a", Rc::new([]));
+        cache.insert(2, None, "b", Rc::new([]));
+        assert!(cache.get(1, None, "a").is_some());
+        cache.insert(3, None, "c", Rc::new([]));
+
+        assert!(cache.get(2, None, "b").is_none());
+        assert!(cache.get(1, None, "a").is_some());
+        assert!(cache.get(3, None, "c").is_some());
+
+        cache.set_capacity(1);
+        assert!(cache.get(1, None, "a").is_none());
+        assert!(cache.get(3, None, "c").is_some());
+        assert_eq!(cache.lru.len(), 1);
+    }
+}
@@ -1,8 +1,9 @@
 //! Creates an abstraction layer to I/O operations
 
+use std::cell::RefCell;
 use std::fmt::{self, Arguments};
 use std::fs;
-use std::io::{self, Stdout, Stderr, Write};
+use std::io::{self, Read, Stdout, Stderr, Write};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
@@ -15,13 +16,32 @@ pub struct GlobalIo {
 
     /// Shared standard error writer
     pub stderr: Rc<dyn SharedWrite>,
+
+    /// Provider of file access for builtin functions
+    provider: RefCell<Rc<dyn IoProvider>>,
 }
 
 impl GlobalIo {
     /// Creates a `GlobalIo` instance using the given `stdout` and `stderr`
     /// writers.
+    ///
+    /// File access is provided by the real filesystem.
     pub fn new(stdout: Rc<dyn SharedWrite>, stderr: Rc<dyn SharedWrite>) -> GlobalIo {
-        GlobalIo{ stdout, stderr }
+        GlobalIo{
+            stdout,
+            stderr,
+            provider: RefCell::new(Rc::new(FileSystem)),
+        }
+    }
+
+    /// Returns the provider of file access for builtin functions.
+    pub fn provider(&self) -> Rc<dyn IoProvider> {
+        self.provider.borrow().clone()
+    }
+
+    /// Sets the provider of file access for builtin functions.
+    pub fn set_provider(&self, provider: Rc<dyn IoProvider>) {
+        *self.provider.borrow_mut() = provider;
     }
 
     /// Creates a `GlobalIo` instance that ignores all output.
@@ -143,6 +163,43 @@ macro_rules! shared_write {
 shared_write!{ Stdout => "<stdout>" }
 shared_write!{ Stderr => "<stderr>" }
 
+/// Provides file access to builtin functions.
+///
+/// Builtin functions which operate on files do so only through the
+/// `IoProvider` of the `GlobalIo` instance, allowing a host to virtualize
+/// or deny filesystem access.
+pub trait IoProvider {
+    /// Reads the entire contents of the file at `path`.
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, IoError>;
+
+    /// Writes `data` to the file at `path`, replacing any existing contents.
+    fn write_file(&self, path: &Path, data: &[u8]) -> Result<(), IoError>;
+}
+
+/// An `IoProvider` which operates on the real filesystem.
+pub struct FileSystem;
+
+impl IoProvider for FileSystem {
+    fn read_file(&self, path: &Path) -> Result<Vec<u8>, IoError> {
+        let mut f = fs::File::open(path)
+            .map_err(|e| IoError::new(IoMode::Open, path, e))?;
+
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)
+            .map_err(|e| IoError::new(IoMode::Read, path, e))?;
+
+        Ok(buf)
+    }
+
+    fn write_file(&self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+        let mut f = fs::File::create(path)
+            .map_err(|e| IoError::new(IoMode::Create, path, e))?;
+
+        f.write_all(data)
+            .map_err(|e| IoError::new(IoMode::Write, path, e))
+    }
+}
+
 /// A shared writer which sends all data into the void.
 pub struct Sink;
 
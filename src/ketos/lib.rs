@@ -38,24 +38,24 @@ pub use ketos_derive::*;
 pub use crate::bytecode::Code;
 pub use crate::bytes::Bytes;
 pub use crate::completion::complete_name;
-pub use crate::compile::CompileError;
+pub use crate::compile::{CompileError, CompileWarning};
 pub use crate::encode::{DecodeError, EncodeError};
-pub use crate::error::Error;
-pub use crate::exec::{Context, ExecError, panic, panic_none};
+pub use crate::error::{Error, ErrorValue, TracedError};
+pub use crate::exec::{Context, ExecError, ExecState, Suspended, panic, panic_none};
 pub use crate::function::Arity;
-pub use crate::interpreter::{Builder, Interpreter};
+pub use crate::interpreter::{Builder, CompileCacheStats, Interpreter};
 pub use crate::integer::{Integer, Ratio};
-pub use crate::io::{File, GlobalIo, IoError, SharedWrite};
+pub use crate::io::{File, FileSystem, GlobalIo, IoError, IoProvider, SharedWrite};
 pub use crate::module::{BuiltinModuleLoader, FileModuleLoader, Module, ModuleBuilder, ModuleLoader};
 pub use crate::name::{Name, NameStore};
 pub use crate::parser::{ParseError, ParseErrorKind};
-pub use crate::restrict::{RestrictConfig, RestrictError};
+pub use crate::restrict::{Capabilities, RestrictConfig, RestrictError};
 pub use crate::run::run_code;
-pub use crate::scope::{GlobalScope, Scope};
+pub use crate::scope::{GlobalScope, NameOrigin, Scope};
 pub use crate::structs::{StructDef, StructValue};
 pub use crate::trace::{clear_traceback, get_traceback, set_traceback, take_traceback, Trace};
-pub use crate::value::{ForeignValue, FromValue, FromValueRef, Value};
-#[cfg(feature = "serde")] pub use value_decode::decode_value;
+pub use crate::value::{DeepCopier, ForeignValue, FromValue, FromValueRef, Value};
+#[cfg(feature = "serde")] pub use value_decode::{decode_value, decode_value_seed, decode_value_with, DecodeOptions, DuplicateKeyPolicy};
 #[cfg(feature = "serde")] pub use value_encode::encode_value;
 
 #[macro_use] pub mod any;
@@ -89,5 +89,13 @@ pub mod value;
 #[cfg(feature = "serde")] pub mod value_decode;
 #[cfg(feature = "serde")] pub mod value_encode;
 
+mod mod_box;
+mod mod_bytes;
 mod mod_code;
+mod mod_env;
+mod mod_file;
+mod mod_json;
 mod mod_math;
+mod mod_regex;
+mod mod_string;
+mod mod_struct;
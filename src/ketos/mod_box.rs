@@ -0,0 +1,137 @@
+//! Implements builtin `box` module.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::exec::{Context, ExecError};
+use crate::function::Arity::{Exact, Min};
+use crate::module::{Module, ModuleBuilder};
+use crate::name::{debug_names, NameStore};
+use crate::scope::Scope;
+use crate::value::{DeepCopier, ForeignValue, Value};
+
+/// Loads the `box` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("box", scope)
+        .add_function("box",        fn_box,     Exact(1), Some("\
+Returns a new mutable box containing the given value.
+
+Boxes are compared by identity; a box is equal only to itself."))
+        .add_function("copy",       fn_copy,    Exact(1), Some("\
+Returns a deep copy of the given value.
+
+Lists, strings, byte strings, and structs are copied recursively.
+Foreign values are copied according to their own definition of a deep copy."))
+        .add_function("eq?",        fn_is_same, Min(2), Some("\
+Returns whether the given arguments are the same object.
+
+Strings, byte strings, lists, structs, functions, and foreign values are
+the same only if they share the same allocation; e.g. two lists created
+separately are not the same, even if their contents are equal.
+Other values, such as numbers, are the same if they are equal."))
+        .add_function("set-box!",   fn_set_box, Exact(2), Some("\
+Replaces the value contained in a box, returning the previous value."))
+        .add_function("unbox",      fn_unbox,   Exact(1), Some("\
+Returns the value contained in a box."))
+        .finish()
+}
+
+/// Mutable cell created by the `box` function.
+///
+/// A `ValueBox` is compared by identity; it is equal only to itself.
+/// `copy` produces a new box containing a deep copy of the contained value.
+#[derive(Debug)]
+struct ValueBox {
+    value: RefCell<Value>,
+    /// Set while formatting, to avoid infinite recursion
+    /// when a box contains itself
+    formatting: Cell<bool>,
+}
+
+impl ValueBox {
+    fn new(value: Value) -> ValueBox {
+        ValueBox{
+            value: RefCell::new(value),
+            formatting: Cell::new(false),
+        }
+    }
+
+    fn get(&self) -> Value {
+        self.value.borrow().clone()
+    }
+
+    fn replace(&self, value: Value) -> Value {
+        self.value.replace(value)
+    }
+
+    fn from_value_ref(v: &Value) -> Result<&ValueBox, ExecError> {
+        match *v {
+            Value::Foreign(ref a) => a.downcast_ref::<ValueBox>()
+                .ok_or_else(|| ExecError::expected("box", v)),
+            ref v => Err(ExecError::expected("box", v))
+        }
+    }
+}
+
+impl ForeignValue for ValueBox {
+    fn is_equal_to(&self, rhs: &dyn ForeignValue) -> Result<bool, ExecError> {
+        match rhs.downcast_ref::<ValueBox>() {
+            Some(rhs) => Ok(std::ptr::eq(self, rhs)),
+            None => Err(ExecError::TypeMismatch{
+                lhs: self.type_name(),
+                rhs: rhs.type_name(),
+            })
+        }
+    }
+
+    fn fmt_debug(&self, names: &NameStore, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.formatting.replace(true) {
+            return f.write_str("<box ...>");
+        }
+
+        let r = write!(f, "<box {}>", debug_names(names, &*self.value.borrow()));
+        self.formatting.set(false);
+        r
+    }
+
+    fn type_name(&self) -> &'static str { "box" }
+
+    fn deep_copy(&self, copier: &mut DeepCopier) -> Result<Option<Value>, ExecError> {
+        let copy = Rc::new(ValueBox::new(Value::Unit));
+        let v = Value::Foreign(copy.clone());
+
+        copier.set_copy(&v);
+        copy.replace(copier.copy(&self.value.borrow())?);
+
+        Ok(Some(v))
+    }
+}
+
+/// `box` returns a new mutable box containing a value.
+fn fn_box(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    Ok(Value::new_foreign(ValueBox::new(args[0].take())))
+}
+
+/// `copy` returns a deep copy of the given value.
+fn fn_copy(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    args[0].deep_copy().map_err(From::from)
+}
+
+/// `eq?` returns whether all arguments are the same object.
+fn fn_is_same(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let v = &args[0];
+    Ok(args[1..].iter().all(|arg| v.is_same(arg)).into())
+}
+
+/// `set-box!` replaces the value contained in a box.
+fn fn_set_box(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let v = args[1].take();
+    Ok(ValueBox::from_value_ref(&args[0])?.replace(v))
+}
+
+/// `unbox` returns the value contained in a box.
+fn fn_unbox(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    Ok(ValueBox::from_value_ref(&args[0])?.get())
+}
@@ -0,0 +1,333 @@
+//! Implements builtin `bytes` module.
+
+use crate::bytes::Bytes;
+use crate::error::Error;
+use crate::exec::{Context, ExecError};
+use crate::function::Arity::{Exact, Range};
+use crate::integer::Integer;
+use crate::module::{Module, ModuleBuilder};
+use crate::scope::Scope;
+use crate::value::{FromValueRef, Value};
+
+/// Loads the `bytes` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("bytes", scope)
+        .add_function("base64-decode", fn_base64_decode, Range(1, 3), Some("\
+    (base64-decode string)
+    (base64-decode string alphabet)
+    (base64-decode string alphabet lenient)
+
+Decodes a base64 string into a byte string.
+`alphabet` is either `:standard` or `:url-safe`; the default is `:standard`.
+Padding is required unless `lenient` is `true`, in which case it may be omitted.
+Whitespace and other characters are not permitted."))
+        .add_function("base64-encode", fn_base64_encode, Range(1, 2), Some("\
+    (base64-encode bytes)
+    (base64-encode bytes alphabet)
+
+Encodes a byte string as a padded base64 string.
+`alphabet` is either `:standard` or `:url-safe`; the default is `:standard`."))
+        .add_function("hex-decode", fn_hex_decode, Exact(1), Some("\
+    (hex-decode string)
+
+Decodes a string of hexadecimal digits into a byte string.
+Both uppercase and lowercase digits are accepted.
+Whitespace and other characters are not permitted."))
+        .add_function("hex-encode", fn_hex_encode, Range(1, 2), Some("\
+    (hex-encode bytes)
+    (hex-encode bytes upper)
+
+Encodes a byte string as a string of hexadecimal digits.
+Digits are lowercase unless `upper` is `true`."))
+        .add_function("read-u16",   fn_read_u16,    Range(2, 3), Some("\
+    (read-u16 bytes offset)
+    (read-u16 bytes offset endian)
+
+Reads an unsigned 16-bit integer at `offset` within a byte string.
+`endian` is either `:big` or `:little`; the default is `:big`."))
+        .add_function("read-u32",   fn_read_u32,    Range(2, 3), Some("\
+    (read-u32 bytes offset)
+    (read-u32 bytes offset endian)
+
+Reads an unsigned 32-bit integer at `offset` within a byte string.
+`endian` is either `:big` or `:little`; the default is `:big`."))
+        .add_function("read-u64",   fn_read_u64,    Range(2, 3), Some("\
+    (read-u64 bytes offset)
+    (read-u64 bytes offset endian)
+
+Reads an unsigned 64-bit integer at `offset` within a byte string.
+`endian` is either `:big` or `:little`; the default is `:big`."))
+        .add_function("write-u16",  fn_write_u16,   Range(3, 4), Some("\
+    (write-u16 bytes offset value)
+    (write-u16 bytes offset value endian)
+
+Returns a copy of a byte string with an unsigned 16-bit integer
+written at `offset`. The byte string is not extended.
+`endian` is either `:big` or `:little`; the default is `:big`."))
+        .add_function("write-u32",  fn_write_u32,   Range(3, 4), Some("\
+    (write-u32 bytes offset value)
+    (write-u32 bytes offset value endian)
+
+Returns a copy of a byte string with an unsigned 32-bit integer
+written at `offset`. The byte string is not extended.
+`endian` is either `:big` or `:little`; the default is `:big`."))
+        .add_function("write-u64",  fn_write_u64,   Range(3, 4), Some("\
+    (write-u64 bytes offset value)
+    (write-u64 bytes offset value endian)
+
+Returns a copy of a byte string with an unsigned 64-bit integer
+written at `offset`. The byte string is not extended.
+`endian` is either `:big` or `:little`; the default is `:big`."))
+        .finish()
+}
+
+/// `base64-decode` decodes a base64 string into a byte string.
+fn fn_base64_decode(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let alphabet = get_base64_alphabet(ctx, args.get(1))?;
+    let lenient = match args.get(2) {
+        Some(v) => bool::from_value_ref(v)?,
+        None => false
+    };
+
+    Ok(Value::Bytes(Bytes::new(base64_decode(s, alphabet, lenient)?)))
+}
+
+/// `base64-encode` encodes a byte string as a base64 string.
+fn fn_base64_encode(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let b = <&[u8]>::from_value_ref(&args[0])?;
+    let alphabet = get_base64_alphabet(ctx, args.get(1))?;
+
+    Ok(base64_encode(b, alphabet).into())
+}
+
+/// `hex-decode` decodes a string of hexadecimal digits into a byte string.
+fn fn_hex_decode(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    Ok(Value::Bytes(Bytes::new(hex_decode(s)?)))
+}
+
+/// `hex-encode` encodes a byte string as a string of hexadecimal digits.
+fn fn_hex_encode(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let b = <&[u8]>::from_value_ref(&args[0])?;
+    let upper = match args.get(1) {
+        Some(v) => bool::from_value_ref(v)?,
+        None => false
+    };
+
+    Ok(hex_encode(b, upper).into())
+}
+
+/// `read-u16` reads an unsigned 16-bit integer from a byte string.
+fn fn_read_u16(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    read_uint(ctx, args, 2)
+}
+
+/// `read-u32` reads an unsigned 32-bit integer from a byte string.
+fn fn_read_u32(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    read_uint(ctx, args, 4)
+}
+
+/// `read-u64` reads an unsigned 64-bit integer from a byte string.
+fn fn_read_u64(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    read_uint(ctx, args, 8)
+}
+
+/// `write-u16` writes an unsigned 16-bit integer into a copy of a byte string.
+fn fn_write_u16(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    write_uint(ctx, args, 2)
+}
+
+/// `write-u32` writes an unsigned 32-bit integer into a copy of a byte string.
+fn fn_write_u32(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    write_uint(ctx, args, 4)
+}
+
+/// `write-u64` writes an unsigned 64-bit integer into a copy of a byte string.
+fn fn_write_u64(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    write_uint(ctx, args, 8)
+}
+
+fn read_uint(ctx: &Context, args: &[Value], width: usize) -> Result<Value, Error> {
+    let b = <&[u8]>::from_value_ref(&args[0])?;
+    let offset = usize::from_value_ref(&args[1])?;
+    let little = get_little_endian(ctx, args.get(2))?;
+
+    let field = &b[byte_range(b.len(), offset, width)?];
+
+    let n = if little {
+        field.iter().rev().fold(0, |n, &byte| n << 8 | byte as u64)
+    } else {
+        field.iter().fold(0, |n, &byte| n << 8 | byte as u64)
+    };
+
+    Ok(Integer::from_u64(n).into())
+}
+
+fn write_uint(ctx: &Context, args: &[Value], width: usize) -> Result<Value, Error> {
+    let b = <&[u8]>::from_value_ref(&args[0])?;
+    let offset = usize::from_value_ref(&args[1])?;
+    let n = u64::from_value_ref(&args[2])?;
+    let little = get_little_endian(ctx, args.get(3))?;
+
+    if width < 8 && n >> (width * 8) != 0 {
+        return Err(From::from(ExecError::Overflow));
+    }
+
+    let range = byte_range(b.len(), offset, width)?;
+    let mut v = b.to_vec();
+    let be = n.to_be_bytes();
+    let field = &mut v[range];
+
+    field.copy_from_slice(&be[8 - width..]);
+
+    if little {
+        field.reverse();
+    }
+
+    Ok(Value::Bytes(Bytes::new(v)))
+}
+
+/// Returns the range of `width` bytes at `offset`,
+/// if it lies within a byte string of length `len`.
+fn byte_range(len: usize, offset: usize, width: usize)
+        -> Result<std::ops::Range<usize>, ExecError> {
+    match offset.checked_add(width) {
+        Some(end) if end <= len => Ok(offset..end),
+        _ => Err(ExecError::BytesOutOfBounds{offset, width, len})
+    }
+}
+
+/// Returns whether an optional endianness argument is `:little`.
+fn get_little_endian(ctx: &Context, v: Option<&Value>) -> Result<bool, ExecError> {
+    match v {
+        None => Ok(false),
+        Some(&Value::Keyword(name)) => ctx.scope().with_name(name, |s| match s {
+            "big" => Some(false),
+            "little" => Some(true),
+            _ => None
+        }).ok_or(ExecError::UnrecognizedKeyword(name)),
+        Some(v) => Err(ExecError::expected("keyword", v))
+    }
+}
+
+const BASE64_STANDARD: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL_SAFE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Returns the base64 alphabet named by an optional keyword argument.
+fn get_base64_alphabet(ctx: &Context, v: Option<&Value>)
+        -> Result<&'static [u8; 64], ExecError> {
+    match v {
+        None => Ok(BASE64_STANDARD),
+        Some(&Value::Keyword(name)) => ctx.scope().with_name(name, |s| match s {
+            "standard" => Some(BASE64_STANDARD),
+            "url-safe" => Some(BASE64_URL_SAFE),
+            _ => None
+        }).ok_or(ExecError::UnrecognizedKeyword(name)),
+        Some(v) => Err(ExecError::expected("keyword", v))
+    }
+}
+
+fn base64_decode(s: &str, alphabet: &[u8; 64], lenient: bool)
+        -> Result<Vec<u8>, ExecError> {
+    fn base64_error(reason: &'static str) -> ExecError {
+        ExecError::InvalidEncoding{ encoding: "base64", reason }
+    }
+
+    let bytes = s.as_bytes();
+    let data = bytes.strip_suffix(b"==")
+        .or_else(|| bytes.strip_suffix(b"="))
+        .unwrap_or(bytes);
+
+    if (data.len() != bytes.len() || !lenient) && !bytes.len().is_multiple_of(4) {
+        return Err(base64_error("incorrect padding"));
+    }
+
+    if data.len() % 4 == 1 {
+        return Err(base64_error("invalid length"));
+    }
+
+    let mut res = Vec::with_capacity(data.len() * 3 / 4);
+
+    for chunk in data.chunks(4) {
+        let mut n = 0u32;
+
+        for &b in chunk {
+            let d = alphabet.iter().position(|&a| a == b)
+                .ok_or_else(|| base64_error("invalid base64 character"))?;
+            n = n << 6 | d as u32;
+        }
+
+        // Left-align the bits of a partial final chunk
+        n <<= 6 * (4 - chunk.len());
+
+        let decoded = &n.to_be_bytes()[1..chunk.len()];
+
+        if n & (0xff_ffff >> (8 * decoded.len())) != 0 {
+            return Err(base64_error("non-zero trailing bits"));
+        }
+
+        res.extend_from_slice(decoded);
+    }
+
+    Ok(res)
+}
+
+fn base64_encode(b: &[u8], alphabet: &[u8; 64]) -> String {
+    let mut s = String::with_capacity(b.len().div_ceil(3) * 4);
+
+    for chunk in b.chunks(3) {
+        let n = chunk.iter().enumerate()
+            .fold(0u32, |n, (i, &byte)| n | (byte as u32) << (16 - 8 * i));
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+
+    s
+}
+
+fn hex_decode(s: &str) -> Result<Vec<u8>, ExecError> {
+    fn hex_error(reason: &'static str) -> ExecError {
+        ExecError::InvalidEncoding{ encoding: "hex", reason }
+    }
+
+    fn digit(b: u8) -> Result<u8, ExecError> {
+        (b as char).to_digit(16).map(|d| d as u8)
+            .ok_or_else(|| hex_error("invalid hex digit"))
+    }
+
+    let pairs = s.as_bytes().chunks_exact(2);
+
+    if !pairs.remainder().is_empty() {
+        return Err(hex_error("odd number of digits"));
+    }
+
+    pairs.map(|pair| Ok(digit(pair[0])? << 4 | digit(pair[1])?))
+        .collect()
+}
+
+fn hex_encode(b: &[u8], upper: bool) -> String {
+    let digits: &[u8; 16] = if upper {
+        b"0123456789ABCDEF"
+    } else {
+        b"0123456789abcdef"
+    };
+
+    let mut s = String::with_capacity(b.len() * 2);
+
+    for &byte in b {
+        s.push(digits[(byte >> 4) as usize] as char);
+        s.push(digits[(byte & 0xf) as usize] as char);
+    }
+
+    s
+}
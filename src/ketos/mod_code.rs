@@ -2,14 +2,14 @@
 
 use std::rc::Rc;
 
-use crate::bytecode::{CodeReader, Instruction};
+use crate::bytecode::disassemble;
 use crate::compile::compile;
 use crate::error::Error;
 use crate::exec::{Context, ExecError};
 use crate::function::Arity::*;
-use crate::function::{plural, Lambda};
+use crate::function::Lambda;
 use crate::module::{Module, ModuleBuilder};
-use crate::name::{debug_names, get_standard_name};
+use crate::restrict::Capabilities;
 use crate::scope::Scope;
 use crate::value::{FromValueRef, Value};
 
@@ -57,202 +57,30 @@ pub fn load(scope: Scope) -> Module {
 
 /// `compile` compiles an expression into a code object.
 fn fn_compile(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    ctx.restrict().require(Capabilities::EVAL)?;
+
     let code = compile(ctx, &args[0])?;
     Ok(Value::Lambda(Lambda::new(Rc::new(code), ctx.scope())))
 }
 
 /// `disassemble` prints information about a `Lambda` code object.
 fn fn_disassemble(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    ctx.restrict().require(Capabilities::IO)?;
+
     let l = match args[0] {
         Value::Lambda(ref l) => l,
         ref v => return Err(From::from(ExecError::expected("lambda", v))),
     };
 
     let scope = ctx.scope();
-    let code = &l.code;
+    let listing = disassemble(&scope.borrow_names(), &l.code, l.values.as_deref())?;
     let out = &scope.io().stdout;
 
-    writeln!(
-        out,
-        "{} positional argument{} total",
-        code.n_params,
-        plural(code.n_params)
-    )?;
-    writeln!(
-        out,
-        "{} positional argument{} required",
-        code.req_params,
-        plural(code.req_params)
-    )?;
-
-    if code.kw_params.is_empty() {
-        writeln!(out, "0 keyword arguments")?;
-    } else {
-        let names = scope.borrow_names();
-
-        writeln!(
-            out,
-            "{} keyword argument{}: {}",
-            code.kw_params.len(),
-            plural(code.kw_params.len() as u32),
-            code.kw_params
-                .iter()
-                .map(|&n| names.get(n))
-                .collect::<Vec<_>>()
-                .join(" ")
-        )?;
-    }
-
-    if code.has_rest_params() {
-        writeln!(out, "Has rest parameter")?;
-    } else {
-        writeln!(out, "No rest parameter")?;
-    }
-
-    if code.consts.is_empty() {
-        writeln!(out, "0 const values")?;
-    } else {
-        writeln!(
-            out,
-            "{} const value{}:",
-            code.consts.len(),
-            plural(code.consts.len() as u32)
-        )?;
-
-        let names = scope.borrow_names();
-
-        for (i, v) in code.consts.iter().enumerate() {
-            writeln!(out, "  {} = {}", i, debug_names(&names, v))?;
-        }
-    }
-
-    if let Some(ref v) = l.values {
-        writeln!(out, "{} enclosed value{}:", v.len(), plural(v.len() as u32))?;
-
-        let names = scope.borrow_names();
-
-        for (i, v) in v.iter().enumerate() {
-            writeln!(out, "  {} = {}", i, debug_names(&names, v))?;
-        }
-    } else {
-        writeln!(out, "0 enclosed values")?;
-    }
-
-    let instrs = get_instructions(&code.code)?;
-    let mut jumps = Vec::with_capacity(16);
-
-    // Collect all jump labels
-    for &(_, ref instr) in &instrs {
-        if let Some(off) = instr.jump_label() {
-            match jumps.binary_search(&off) {
-                Ok(_) => (),
-                Err(pos) => jumps.insert(pos, off),
-            }
-        }
-    }
-
-    writeln!(
-        out,
-        "{} bytecode instruction{}:",
-        instrs.len(),
-        plural(instrs.len() as u32)
-    )?;
-
-    for (off, instr) in instrs {
-        let is_label = jumps.binary_search(&off).is_ok();
-        print_instruction(ctx, l, off, instr, is_label)?;
-    }
-
+    out.write_all(listing.as_bytes())?;
     out.flush()?;
     Ok(().into())
 }
 
-fn get_instructions(code: &[u8]) -> Result<Vec<(u32, Instruction)>, ExecError> {
-    let mut res = Vec::new();
-    let mut r = CodeReader::new(code, 0);
-
-    loop {
-        let off = r.offset() as u32;
-
-        let instr = match r.read_instruction() {
-            Ok(instr) => instr,
-            Err(ExecError::UnexpectedEnd) => break,
-            Err(e) => return Err(e),
-        };
-
-        res.push((off, instr));
-    }
-
-    Ok(res)
-}
-
-fn print_instruction(
-    ctx: &Context,
-    lambda: &Lambda,
-    offset: u32,
-    instr: Instruction,
-    is_label: bool,
-) -> Result<(), Error> {
-    use crate::bytecode::Instruction::*;
-
-    let scope = ctx.scope();
-    let label_str = if is_label { ">>" } else { "  " };
-    let code = &lambda.code;
-    let out = &scope.io().stdout;
-
-    let extra = {
-        let names = scope.borrow_names();
-
-        match instr {
-            LoadC(n) | LoadCPush(n) => lambda
-                .values
-                .as_ref()
-                .and_then(|v| v.get(n as usize))
-                .map(|v| debug_names(&names, v).to_string()),
-            GetDef(n)
-            | Const(n)
-            | GetDefPush(n)
-            | ConstPush(n)
-            | EqConst(n)
-            | NotEqConst(n)
-            | SetDef(n)
-            | BuildClosure(n, _)
-            | CallConst(n, _) => code
-                .consts
-                .get(n as usize)
-                .map(|c| debug_names(&names, c).to_string()),
-            Jump(l)
-            | JumpIf(l)
-            | JumpIfNull(l)
-            | JumpIfNotNull(l)
-            | JumpIfNot(l)
-            | JumpIfEq(l)
-            | JumpIfNotEq(l)
-            | JumpIfBound(l, _) => Some(format!("L{}", l)),
-            JumpIfEqConst(l, n) | JumpIfNotEqConst(l, n) => {
-                Some(match code.consts.get(n as usize) {
-                    None => format!("L{}", l),
-                    Some(c) => format!("L{} {}", l, debug_names(&names, c)),
-                })
-            }
-            CallSys(n) | CallSysArgs(n, _) => get_standard_name(n).map(|n| names.get(n).to_owned()),
-            _ => None,
-        }
-    };
-
-    match extra {
-        Some(s) => {
-            // fmt::Debug does not honor "<n" formatting,
-            // so we make this string first and format again.
-            let instr = format!("{:?}", instr);
-            writeln!(out, "  {} {:>4}  {:<30} ; {}", label_str, offset, instr, s)?;
-        }
-        None => writeln!(out, "  {} {:>4}  {:?}", label_str, offset, instr)?,
-    }
-
-    Ok(())
-}
-
 /// `documentation` returns the docstring for a value.
 ///
 /// If the named value does not exist or does not have a docstring,
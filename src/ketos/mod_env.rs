@@ -0,0 +1,44 @@
+//! Implements builtin `env` module.
+//!
+//! Access to environment variables requires the `ENV` capability
+//! and is limited to the variables permitted by `RestrictConfig`.
+
+use std::env::{self, VarError};
+
+use crate::error::Error;
+use crate::exec::{Context, ExecError};
+use crate::function::Arity::Exact;
+use crate::module::{Module, ModuleBuilder};
+use crate::restrict::{Capabilities, RestrictError};
+use crate::scope::Scope;
+use crate::value::{FromValueRef, Value};
+
+/// Loads the `env` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("env", scope)
+        .add_function("get",    fn_get,     Exact(1), Some("\
+    (get name)
+
+Returns the value of an environment variable as a string,
+or `()` if the variable is not set."))
+        .finish()
+}
+
+/// `get` returns the value of an environment variable.
+fn fn_get(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let name = <&str>::from_value_ref(&args[0])?;
+
+    let restrict = ctx.restrict();
+    restrict.require(Capabilities::ENV)?;
+
+    if !restrict.allows_env_var(name) {
+        return Err(From::from(RestrictError::PermissionDenied));
+    }
+
+    match env::var(name) {
+        Ok(s) => Ok(s.into()),
+        Err(VarError::NotPresent) => Ok(Value::Unit),
+        Err(VarError::NotUnicode(_)) => Err(From::from(ExecError::InvalidEncoding{
+            encoding: "UTF-8", reason: "invalid character in environment variable" })),
+    }
+}
@@ -0,0 +1,84 @@
+//! Implements builtin `file` module.
+//!
+//! All file access goes through the `IoProvider` of the `GlobalIo` instance
+//! and requires the `FILES` capability.
+
+use std::io;
+use std::path::Path;
+
+use crate::bytes::Bytes;
+use crate::error::Error;
+use crate::exec::{Context, ExecError};
+use crate::function::Arity::Exact;
+use crate::io::{IoError, IoMode};
+use crate::module::{Module, ModuleBuilder};
+use crate::restrict::Capabilities;
+use crate::scope::Scope;
+use crate::value::Value;
+
+/// Loads the `file` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("file", scope)
+        .add_function("read",       fn_read,        Exact(1), Some("\
+    (read path)
+
+Returns the contents of a file as a string.
+An error is raised if the contents are not valid UTF-8."))
+        .add_function("read-bytes", fn_read_bytes,  Exact(1), Some("\
+    (read-bytes path)
+
+Returns the contents of a file as a byte string."))
+        .add_function("write",      fn_write,       Exact(2), Some("\
+    (write path data)
+
+Writes a string or byte string to a file, replacing any existing contents."))
+        .finish()
+}
+
+/// `read` returns the contents of a file as a string.
+fn fn_read(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let path = get_path(&args[0])?;
+    let buf = read_file(ctx, path)?;
+
+    let s = String::from_utf8(buf).map_err(|e| IoError::new(IoMode::Read, path,
+        io::Error::new(io::ErrorKind::InvalidData, e)))?;
+
+    Ok(s.into())
+}
+
+/// `read-bytes` returns the contents of a file as a byte string.
+fn fn_read_bytes(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let path = get_path(&args[0])?;
+    let buf = read_file(ctx, path)?;
+
+    Ok(Value::Bytes(Bytes::new(buf)))
+}
+
+/// `write` writes a string or byte string to a file.
+fn fn_write(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let path = get_path(&args[0])?;
+
+    let data = match args[1] {
+        Value::String(ref s) => s.as_bytes(),
+        Value::Bytes(ref b) => b,
+        ref v => return Err(From::from(ExecError::expected("string or bytes", v)))
+    };
+
+    ctx.restrict().require(Capabilities::FILES)?;
+    ctx.scope().io().provider().write_file(path, data)?;
+
+    Ok(Value::Unit)
+}
+
+fn read_file(ctx: &Context, path: &Path) -> Result<Vec<u8>, Error> {
+    ctx.restrict().require(Capabilities::FILES)?;
+    Ok(ctx.scope().io().provider().read_file(path)?)
+}
+
+fn get_path(v: &Value) -> Result<&Path, ExecError> {
+    match *v {
+        Value::String(ref s) => Ok(Path::new(&s[..])),
+        Value::Path(ref p) => Ok(p),
+        ref v => Err(ExecError::expected("string or path", v))
+    }
+}
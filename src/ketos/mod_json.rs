@@ -0,0 +1,153 @@
+//! Implements builtin `json` module.
+//!
+//! Functions in this module are available only when the `json` feature
+//! is enabled. Otherwise, calling any of them returns an error.
+
+use crate::function::Arity::Exact;
+use crate::module::{Module, ModuleBuilder};
+use crate::scope::Scope;
+
+use self::imp::{fn_parse, fn_stringify};
+
+/// Loads the `json` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("json", scope)
+        .add_function("parse",      fn_parse,       Exact(1), Some("\
+    (parse string)
+
+Parses a JSON document into a value.
+Arrays become lists; objects become lists of `(:key value)` pairs;
+`null` becomes `()`."))
+        .add_function("stringify",  fn_stringify,   Exact(1), Some("\
+    (stringify value)
+
+Writes a value as a JSON document.
+`()` is written as `null`; a list of `(:key value)` pairs is written as
+an object; any other list is written as an array."))
+        .finish()
+}
+
+#[cfg(not(feature = "json"))]
+mod imp {
+    use crate::error::Error;
+    use crate::exec::{Context, ExecError};
+    use crate::value::Value;
+
+    pub use self::unavailable as fn_parse;
+    pub use self::unavailable as fn_stringify;
+
+    /// Returns an error, as the `json` feature is not enabled.
+    pub fn unavailable(_ctx: &Context, _args: &mut [Value]) -> Result<Value, Error> {
+        Err(From::from(ExecError::FeatureUnavailable("json")))
+    }
+}
+
+#[cfg(feature = "json")]
+mod imp {
+    use serde_json::{Map, Number, Value as Json};
+
+    use crate::error::Error;
+    use crate::exec::{Context, ExecError};
+    use crate::integer::Integer;
+    use crate::scope::Scope;
+    use crate::value::{FromValueRef, Value};
+
+    /// `parse` parses a JSON document into a value.
+    pub fn fn_parse(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+        let s = <&str>::from_value_ref(&args[0])?;
+
+        let json = serde_json::from_str(s)
+            .map_err(|e| ExecError::InvalidJson(e.to_string()))?;
+
+        Ok(from_json(ctx.scope(), json))
+    }
+
+    /// `stringify` writes a value as a JSON document.
+    pub fn fn_stringify(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+        let json = to_json(ctx.scope(), &args[0])?;
+        Ok(json.to_string().into())
+    }
+
+    fn from_json(scope: &Scope, json: Json) -> Value {
+        match json {
+            Json::Null => Value::Unit,
+            Json::Bool(b) => b.into(),
+            Json::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    Integer::from_i64(i).into()
+                } else if let Some(u) = n.as_u64() {
+                    Integer::from_u64(u).into()
+                } else {
+                    n.as_f64().unwrap_or(f64::NAN).into()
+                }
+            }
+            Json::String(s) => s.into(),
+            Json::Array(arr) => arr.into_iter()
+                .map(|v| from_json(scope, v)).collect::<Vec<_>>().into(),
+            Json::Object(obj) => obj.into_iter()
+                .map(|(k, v)| vec![
+                    Value::Keyword(scope.add_name(&k)),
+                    from_json(scope, v),
+                ].into())
+                .collect::<Vec<Value>>().into(),
+        }
+    }
+
+    fn to_json(scope: &Scope, v: &Value) -> Result<Json, ExecError> {
+        Ok(match *v {
+            Value::Unit => Json::Null,
+            Value::Bool(b) => Json::Bool(b),
+            Value::Integer(ref i) => {
+                if let Some(n) = i.to_i64() {
+                    Json::Number(n.into())
+                } else if let Some(n) = i.to_u64() {
+                    Json::Number(n.into())
+                } else {
+                    return Err(ExecError::Overflow);
+                }
+            }
+            Value::Ratio(ref r) => float_to_json(r.to_f64(), v)?,
+            Value::Float(f) => float_to_json(Some(f), v)?,
+            Value::Char(c) => Json::String(c.to_string()),
+            Value::String(ref s) => Json::String(s.to_string()),
+            Value::Name(name) | Value::Keyword(name) =>
+                Json::String(scope.with_name(name, |s| s.to_owned())),
+            Value::List(ref li) => {
+                if is_object(li) {
+                    let mut obj = Map::new();
+
+                    for pair in li.iter() {
+                        if let Value::List(ref pair) = *pair {
+                            if let Value::Keyword(k) = pair[0] {
+                                obj.insert(scope.with_name(k, |s| s.to_owned()),
+                                    to_json(scope, &pair[1])?);
+                            }
+                        }
+                    }
+
+                    Json::Object(obj)
+                } else {
+                    Json::Array(li.iter()
+                        .map(|v| to_json(scope, v))
+                        .collect::<Result<_, _>>()?)
+                }
+            }
+            ref v => return Err(ExecError::expected("json value", v))
+        })
+    }
+
+    fn float_to_json(f: Option<f64>, v: &Value) -> Result<Json, ExecError> {
+        f.and_then(Number::from_f64)
+            .map(Json::Number)
+            .ok_or_else(|| ExecError::expected("finite number", v))
+    }
+
+    /// Returns whether a list consists entirely of `(:key value)` pairs.
+    fn is_object(li: &[Value]) -> bool {
+        li.iter().all(|v| match *v {
+            Value::List(ref pair) =>
+                pair.len() == 2 && matches!(pair[0], Value::Keyword(_)),
+            _ => false
+        })
+    }
+}
@@ -1,10 +1,14 @@
 //! Implements builtin `math` module.
 
+use std::cmp::Ordering;
 use std::f64::consts;
 
 use crate::error::Error;
 use crate::exec::{Context, ExecError};
 use crate::function::Arity::Exact;
+use crate::function::{add_number, check_fixed, coerce_numbers, expect_number,
+    expt_number, fixed_integer, mul_number, sub_number, test_zero, try_mul_assign};
+use crate::integer::{Integer, Ratio};
 use crate::module::{Module, ModuleBuilder};
 use crate::scope::Scope;
 use crate::value::Value;
@@ -16,6 +20,10 @@ pub fn load(scope: Scope) -> Module {
         .add_doc     ("e",  "Euler's number")
         .add_constant("pi",         consts::PI)
         .add_doc     ("pi", "The ratio of a circle's circumference to its diameter")
+        .add_function("->bool",     fn_to_bool, Exact(1), Some("\
+Converts a value to a boolean.
+
+`false` and `()` are converted to `false`; all other values to `true`."))
         .add_function("acos",       fn_acos,    Exact(1), Some("\
 Computes the arctangent of a number.
 Return value is in radians in the range `[-pi/2, pi/2]`."))
@@ -41,12 +49,47 @@ Computes the four quadrant arctangent of `y` and `x`.
 * `x >= 0`: `arctan(y/x)` -> `[-pi/2, pi/2]`
 * `y >= 0`: `arctan(y/x) + pi` -> `(pi/2, pi]`
 * `y < 0`: `arctan(y/x) - pi` -> `(-pi, -pi/2)`"))
+        .add_function("bit-length", fn_bit_length, Exact(1), Some("\
+Returns the number of bits required to represent the absolute value
+of an integer. Returns `0` for `0`."))
+        .add_function("bool->int",  fn_bool_to_int, Exact(1), Some("\
+Converts a boolean to an integer; `true` to `1` and `false` to `0`."))
+        .add_function("clamp",      fn_clamp,   Exact(3), Some("\
+    (clamp value lo hi)
+
+Restricts a number to a range.
+
+Returns `lo` if `value` is less than `lo`, `hi` if `value` is greater than
+`hi`, and otherwise `value`. Returns an error if `lo` is greater than `hi`."))
         .add_function("cos",        fn_cos,     Exact(1),
             Some("Computes the cosine of a number, in radians."))
         .add_function("cosh",       fn_cosh,    Exact(1),
             Some("Hyperbolic cosine function."))
+        .add_function("count-ones", fn_count_ones, Exact(1), Some("\
+Returns the number of one bits in the absolute value of an integer."))
         .add_function("degrees",    fn_degrees, Exact(1),
             Some("Converts a value in radians to degrees."))
+        .add_function("even?",      fn_even,    Exact(1),
+            Some("Returns whether an integer is even."))
+        .add_function("expt",       fn_expt,    Exact(2), Some("\
+    (expt base exponent)
+
+Returns a number raised to a power.
+
+An integer or ratio raised to an integer power produces an exact result;
+a negative exponent produces a ratio. Otherwise, the result is a float."))
+        .add_function("factorial",  fn_factorial, Exact(1), Some("\
+Returns the factorial of a non-negative integer.
+The result is subject to the interpreter's integer size limit."))
+        .add_function("isqrt",      fn_isqrt,   Exact(1), Some("\
+Returns the integer square root of a non-negative integer;
+that is, the greatest integer whose square does not exceed the argument."))
+        .add_function("lerp",       fn_lerp,    Exact(3), Some("\
+    (lerp a b t)
+
+Linearly interpolates between two numbers.
+
+Returns `(+ a (* (- b a) t))`; `a` when `t` is `0` and `b` when `t` is `1`."))
         .add_function("ln",         fn_ln,      Exact(1),
             Some("Returns the natural logarithm of a number."))
         .add_function("log",        fn_log,     Exact(2), Some("\
@@ -57,8 +100,19 @@ Returns the logarithm of a number with respect to an arbitrary base."))
 Returns the base 2 logarithm of a number."))
         .add_function("log10",      fn_log10,   Exact(1), Some("\
 Returns the base 10 logarithm of a number."))
+        .add_function("mod",        fn_mod,     Exact(2), Some("\
+Returns the modulus of two arguments.
+
+The result has the sign of the divisor, e.g. `(mod -7 3)` is `2`.
+An error is returned if the divisor is zero."))
+        .add_function("odd?",       fn_odd,     Exact(1),
+            Some("Returns whether an integer is odd."))
         .add_function("radians",    fn_radians, Exact(1),
             Some("Converts a value in degrees to radians."))
+        .add_function("signum",     fn_signum,  Exact(1), Some("\
+Returns `-1`, `0`, or `1` according to the sign of a number.
+The result is of the same numeric type as the argument.
+Returns `NaN` if the number is `NaN`."))
         .add_function("sin",        fn_sin,     Exact(1),
             Some("Computes the sine of a number, in radians."))
         .add_function("sinh",       fn_sinh,    Exact(1),
@@ -70,9 +124,20 @@ Returns `NaN` if the number is negative."))
             Some("Computes the tangent of a number, in radians."))
         .add_function("tanh",       fn_tanh,    Exact(1),
             Some("Hyperbolic tangent function"))
+        .add_function("trailing-zeros", fn_trailing_zeros, Exact(1), Some("\
+Returns the number of trailing zero bits in the absolute value of an integer.
+Returns `0` for `0`."))
         .finish()
 }
 
+/// `->bool` converts a value to a boolean.
+fn fn_to_bool(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    match args[0] {
+        Value::Bool(false) | Value::Unit => Ok(false.into()),
+        _ => Ok(true.into())
+    }
+}
+
 /// `acos` returns the arccosine of a number. Return value is in radians
 /// in the range `[0, pi]` or `NaN` if the number is outside the range `[-1, 1]`.
 fn fn_acos(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
@@ -120,6 +185,40 @@ fn fn_atan2(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     Ok(fa.atan2(fb).into())
 }
 
+/// `bit-length` returns the number of bits in the absolute value of an integer.
+fn fn_bit_length(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let i = get_integer(&args[0])?;
+    Ok(Integer::from_usize(i.bits()).into())
+}
+
+/// `bool->int` converts a boolean to an integer.
+fn fn_bool_to_int(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    match args[0] {
+        Value::Bool(b) => Ok(Integer::from_u32(b as u32).into()),
+        ref v => Err(From::from(ExecError::expected("bool", v)))
+    }
+}
+
+/// `clamp` restricts a number to an inclusive range.
+fn fn_clamp(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    for arg in args.iter() {
+        expect_number(arg)?;
+    }
+
+    if args[1].compare(&args[2])? == Ordering::Greater {
+        return Err(From::from(ExecError::InvalidRange(
+            Box::new((args[1].take(), args[2].take())))));
+    }
+
+    if args[0].compare(&args[1])? == Ordering::Less {
+        Ok(args[1].take())
+    } else if args[0].compare(&args[2])? == Ordering::Greater {
+        Ok(args[2].take())
+    } else {
+        Ok(args[0].take())
+    }
+}
+
 /// `cos` computes the cosine of a number, in radians.
 fn fn_cos(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let f = get_float(&args[0])?;
@@ -132,12 +231,67 @@ fn fn_cosh(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     Ok(f.cosh().into())
 }
 
+/// `count-ones` returns the number of one bits in the absolute value
+/// of an integer.
+fn fn_count_ones(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let i = get_integer(&args[0])?;
+    Ok(Integer::from_u64(i.count_ones()).into())
+}
+
 /// `degrees` converts a value in radians to the equivalent value in degrees.
 fn fn_degrees(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let f = get_float(&args[0])?;
     Ok(f.to_degrees().into())
 }
 
+/// `even?` returns whether an integer is even.
+fn fn_even(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let i = get_integer(&args[0])?;
+    Ok(i.is_multiple_of(&Integer::from_u32(2)).into())
+}
+
+/// `expt` returns a number raised to a power.
+fn fn_expt(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let base = args[0].take();
+    let exp = args[1].take();
+
+    expt_number(ctx, base, exp)
+}
+
+/// `factorial` returns the factorial of a non-negative integer.
+fn fn_factorial(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let n = get_non_negative(&args[0])?;
+    let n = n.to_u64().ok_or(ExecError::Overflow)?;
+
+    let mut acc = Integer::one();
+
+    for i in 2..=n {
+        try_mul_assign(ctx, &mut acc, &Integer::from_u64(i))?;
+        check_fixed(ctx, &acc)?;
+    }
+
+    Ok(acc.into())
+}
+
+/// `isqrt` returns the integer square root of a non-negative integer.
+fn fn_isqrt(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let i = get_non_negative(&args[0])?;
+    fixed_integer(ctx, i.sqrt())
+}
+
+/// `lerp` linearly interpolates between two numbers.
+fn fn_lerp(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    for arg in args.iter() {
+        expect_number(arg)?;
+    }
+
+    let a = args[0].take();
+    let diff = sub_number(ctx, args[1].take(), &a)?;
+    let step = mul_number(ctx, diff, &args[2])?;
+
+    add_number(ctx, a, &step)
+}
+
 /// `ln` returns the natural logarithm of a number.
 fn fn_ln(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let f = get_float(&args[0])?;
@@ -163,12 +317,91 @@ fn fn_log10(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     Ok(f.log10().into())
 }
 
+/// `mod` returns the modulus of two arguments.
+fn fn_mod(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let a = args[0].take();
+    expect_number(&a)?;
+
+    let b = &args[1];
+    expect_number(b)?;
+
+    mod_number(a, b)
+}
+
+/// Returns the remainder of floored division,
+/// which has the sign of the divisor.
+fn mod_number(lhs: Value, rhs: &Value) -> Result<Value, Error> {
+    let (lhs, rhs) = coerce_numbers(lhs, rhs)?;
+
+    match (lhs, &*rhs) {
+        (Value::Float(a), &Value::Float(b)) => {
+            test_zero(&b)?;
+            let r = a % b;
+
+            if r != 0.0 && (r < 0.0) != (b < 0.0) {
+                Ok((r + b).into())
+            } else {
+                Ok(r.into())
+            }
+        }
+        (Value::Integer(ref a), &Value::Integer(ref b)) => {
+            test_zero(b)?;
+            let r = a % b;
+
+            if !r.is_zero() && r.is_negative() != b.is_negative() {
+                Ok((&r + b).into())
+            } else {
+                Ok(r.into())
+            }
+        }
+        (Value::Ratio(ref a), &Value::Ratio(ref b)) => {
+            test_zero(b)?;
+            let r = a % b;
+
+            if !r.is_zero() && r.is_negative() != b.is_negative() {
+                Ok((&r + b).into())
+            } else {
+                Ok(r.into())
+            }
+        }
+        (a, b) => Err(From::from(ExecError::TypeMismatch{
+            lhs: a.type_name(),
+            rhs: b.type_name(),
+        }))
+    }
+}
+
+/// `odd?` returns whether an integer is odd.
+fn fn_odd(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let i = get_integer(&args[0])?;
+    Ok((!i.is_multiple_of(&Integer::from_u32(2))).into())
+}
+
 /// `radians` converts a value in degrees to the equivalent value in radians.
 fn fn_radians(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let f = get_float(&args[0])?;
     Ok(f.to_radians().into())
 }
 
+/// `signum` returns `-1`, `0`, or `1`, retaining the numeric type of its
+/// argument.
+fn fn_signum(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    fn sign(neg: bool, pos: bool) -> i32 {
+        if neg { -1 } else if pos { 1 } else { 0 }
+    }
+
+    match args[0] {
+        Value::Float(f) if f == 0.0 || f.is_nan() => Ok(f.into()),
+        Value::Float(f) => Ok(f.signum().into()),
+        Value::Integer(ref i) =>
+            Ok(Integer::from_i32(sign(i.is_negative(), i.is_positive())).into()),
+        Value::Ratio(ref r) =>
+            Ok(Ratio::from_integer(Integer::from_i32(
+                sign(r.is_negative(), r.is_positive()))).into()),
+        ref v => Err(From::from(ExecError::expected("number", v)))
+    }
+}
+
 /// `sin` computes the sine of a number, in radians.
 fn fn_sin(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     let f = get_float(&args[0])?;
@@ -199,6 +432,27 @@ fn fn_tanh(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
     Ok(f.tanh().into())
 }
 
+/// `trailing-zeros` returns the number of trailing zero bits in the absolute
+/// value of an integer.
+fn fn_trailing_zeros(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let i = get_integer(&args[0])?;
+    Ok(Integer::from_u64(i.trailing_zeros().unwrap_or(0)).into())
+}
+
+fn get_integer(v: &Value) -> Result<&Integer, ExecError> {
+    match *v {
+        Value::Integer(ref i) => Ok(i),
+        ref v => Err(ExecError::expected("integer", v))
+    }
+}
+
+fn get_non_negative(v: &Value) -> Result<&Integer, ExecError> {
+    match *v {
+        Value::Integer(ref i) if !i.is_negative() => Ok(i),
+        ref v => Err(ExecError::expected("non-negative integer", v))
+    }
+}
+
 fn get_float(v: &Value) -> Result<f64, ExecError> {
     match *v {
         Value::Float(f) => Ok(f),
@@ -0,0 +1,91 @@
+//! Implements builtin `regex` module.
+//!
+//! Functions in this module are available only when the `regex` feature
+//! is enabled. Otherwise, calling any of them returns an error.
+
+use crate::function::Arity::Exact;
+use crate::module::{Module, ModuleBuilder};
+use crate::scope::Scope;
+
+use self::imp::{fn_find, fn_match, fn_replace};
+
+/// Loads the `regex` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("regex", scope)
+        .add_function("find",       fn_find,    Exact(2), Some("\
+    (find pattern string)
+
+Returns a list containing the text of the first match of a pattern
+followed by the text of each capture group, or `()` if there is no match.
+Capture groups which did not participate in the match are `()`."))
+        .add_function("match?",     fn_match,   Exact(2), Some("\
+    (match? pattern string)
+
+Returns whether a pattern matches any part of a string."))
+        .add_function("replace",    fn_replace, Exact(3), Some("\
+    (replace pattern string replacement)
+
+Replaces all matches of a pattern within a string.
+Within the replacement, `$1` or `${name}` expands to the text of a capture group."))
+        .finish()
+}
+
+#[cfg(not(feature = "regex"))]
+mod imp {
+    use crate::error::Error;
+    use crate::exec::{Context, ExecError};
+    use crate::value::Value;
+
+    pub use self::unavailable as fn_find;
+    pub use self::unavailable as fn_match;
+    pub use self::unavailable as fn_replace;
+
+    /// Returns an error, as the `regex` feature is not enabled.
+    pub fn unavailable(_ctx: &Context, _args: &mut [Value]) -> Result<Value, Error> {
+        Err(From::from(ExecError::FeatureUnavailable("regex")))
+    }
+}
+
+#[cfg(feature = "regex")]
+mod imp {
+    use regex::Regex;
+
+    use crate::error::Error;
+    use crate::exec::{Context, ExecError};
+    use crate::value::{FromValueRef, Value};
+
+    fn get_regex(v: &Value) -> Result<Regex, ExecError> {
+        let pat = <&str>::from_value_ref(v)?;
+        Regex::new(pat).map_err(|e| ExecError::InvalidRegex(e.to_string()))
+    }
+
+    /// `find` returns the text of the first match and its capture groups.
+    pub fn fn_find(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+        let re = get_regex(&args[0])?;
+        let s = <&str>::from_value_ref(&args[1])?;
+
+        match re.captures(s) {
+            Some(caps) => Ok(caps.iter()
+                .map(|m| m.map_or(Value::Unit, |m| m.as_str().into()))
+                .collect::<Vec<_>>().into()),
+            None => Ok(Value::Unit)
+        }
+    }
+
+    /// `match?` returns whether a pattern matches a string.
+    pub fn fn_match(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+        let re = get_regex(&args[0])?;
+        let s = <&str>::from_value_ref(&args[1])?;
+
+        Ok(re.is_match(s).into())
+    }
+
+    /// `replace` replaces all matches of a pattern within a string.
+    pub fn fn_replace(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+        let re = get_regex(&args[0])?;
+        let s = <&str>::from_value_ref(&args[1])?;
+        let rep = <&str>::from_value_ref(&args[2])?;
+
+        Ok(re.replace_all(s, rep).into_owned().into())
+    }
+}
@@ -0,0 +1,518 @@
+//! Implements builtin `string` module.
+
+use crate::error::Error;
+use crate::exec::{Context, ExecError};
+use crate::function::Arity::{Exact, Range};
+use crate::integer::Integer;
+use crate::lexer::{Lexer, Token};
+use crate::module::{Module, ModuleBuilder};
+use crate::parser::ParseErrorKind;
+use crate::scope::Scope;
+use crate::string::{escape_string, unescape_string};
+use crate::value::{FromValueRef, Value};
+
+/// Loads the `string` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("string", scope)
+        .add_function("byte-length",    fn_byte_length, Exact(1), Some("\
+Returns the length of a string in bytes, as does `len`.
+This is the length used by `slice` and other string indices."))
+        .add_function("capitalize",     fn_capitalize,  Exact(1), Some("\
+Returns a string with its first letter converted to uppercase
+and all following characters converted to lowercase.
+Characters preceding the first letter, such as punctuation, are unchanged."))
+        .add_function("center",         fn_center,      Range(2, 3), Some("\
+Pads a string on both sides with a fill char, which defaults to a space,
+so that it is `width` chars long. If the padding cannot be divided evenly,
+the extra char is placed on the right.
+A string of at least `width` chars is returned unchanged."))
+        .add_function("char-length",    fn_char_length, Exact(1), Some("\
+Returns the number of chars in a string.
+This is less than the byte length if the string contains any non-ASCII chars."))
+        .add_function("escape",         fn_escape,      Exact(1), Some("\
+Returns a string with quotes, backslashes, and control characters escaped,
+as they would appear within a string literal. Surrounding quotes are not added."))
+        .add_function("format-csv-line", fn_format_csv_line, Exact(1), Some("\
+Formats a list of strings as a single CSV record, without a line ending.
+A field containing a comma, quote, or line ending is enclosed in quotes,
+with each quote within it doubled."))
+        .add_function("normalize-newlines", fn_normalize_newlines, Exact(1), Some("\
+Returns a string with each `\\r\\n` or lone `\\r` line ending replaced with `\\n`."))
+        .add_function("keyword->string", fn_keyword_to_string, Exact(1), Some("\
+Returns the name of a keyword as a string, without the leading `:`."))
+        .add_function("parse-csv-line", fn_parse_csv_line, Exact(1), Some("\
+Parses a single CSV record into a list of field strings.
+A field may be enclosed in quotes, in which case it may contain commas,
+and a doubled quote represents a single quote.
+An error is raised if a quoted field is not terminated or is followed
+by anything other than a comma, or if an unquoted field contains a quote."))
+        .add_function("parse-float",    fn_parse_float, Exact(1), Some("\
+Parses a string as a float. An integer, such as `\"3\"`, is accepted.
+An error is raised if the string contains any other text, including whitespace."))
+        .add_function("parse-int",      fn_parse_int,   Range(1, 2), Some("\
+Parses a string as an integer in the given radix, which defaults to 10.
+An error is raised if the string contains any other text, including whitespace."))
+        .add_function("rsplit-once",    fn_rsplit_once, Exact(2), Some("\
+Splits a string at the last occurrence of a separator, returning a list of
+the text before and after it, or `()` if the separator does not occur.
+An error is raised if the separator is empty."))
+        .add_function("split-lines-keepends", fn_split_lines_keepends, Exact(1), Some("\
+Splits a string into a list of lines, each retaining its line ending.
+Any of `\\n`, `\\r\\n`, or a lone `\\r` ends a line.
+A final line without a line ending is included if it is not empty."))
+        .add_function("split-once",     fn_split_once,  Exact(2), Some("\
+Splits a string at the first occurrence of a separator, returning a list of
+the text before and after it, or `()` if the separator does not occur.
+An error is raised if the separator is empty."))
+        .add_function("split-whitespace", fn_split_whitespace, Exact(1), Some("\
+Splits a string on runs of Unicode whitespace, returning a list of the
+non-empty tokens between them. Leading and trailing whitespace is ignored."))
+        .add_function("string->keyword", fn_string_to_keyword, Exact(1), Some("\
+Returns a keyword with the given name, which must not include the leading `:`.
+An error is raised if the string is not a valid keyword name."))
+        .add_function("string->symbol", fn_string_to_symbol, Exact(1), Some("\
+Returns a name value with the given name.
+An error is raised if the string is not a valid name."))
+        .add_function("symbol->string", fn_symbol_to_string, Exact(1), Some("\
+Returns the given name value as a string."))
+        .add_function("title-case",     fn_title_case,  Exact(1), Some("\
+Capitalizes each word of a string, as if by `capitalize`.
+Words are separated by whitespace, which is preserved."))
+        .add_function("truncate-ellipsis", fn_truncate_ellipsis, Exact(2), Some("\
+Truncates a string to at most `width` chars. If the string is truncated,
+its final char is replaced with `…`, which counts toward the width.
+A string of at most `width` chars is returned unchanged."))
+        .add_function("unescape",       fn_unescape,    Exact(1), Some("\
+Returns a string with escape sequences replaced by the characters
+they represent, as within a string literal.
+An error is raised if the string contains an invalid escape sequence."))
+        .add_function("word-wrap",      fn_word_wrap,   Exact(2), Some("\
+Inserts newlines between words of a string so that no line is longer than
+`width` characters. A word longer than `width` is placed on its own line.
+Existing newlines are preserved."))
+        .finish()
+}
+
+/// `byte-length` returns the length of a string in bytes.
+fn fn_byte_length(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    Ok(s.len().into())
+}
+
+/// `capitalize` uppercases the first letter of a string and lowercases the rest.
+fn fn_capitalize(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let mut res = String::with_capacity(s.len());
+
+    capitalize_into(&mut res, s);
+    Ok(res.into())
+}
+
+/// `center` pads a string on both sides to a given width.
+fn fn_center(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let width = usize::from_value_ref(&args[1])?;
+    let fill = match args.get(2) {
+        Some(v) => char::from_value_ref(v)?,
+        None => ' '
+    };
+
+    let n = s.chars().count();
+
+    if n >= width {
+        return Ok(s.into());
+    }
+
+    let left = (width - n) / 2;
+    let right = width - n - left;
+    let mut res = String::with_capacity(s.len() + (left + right) * fill.len_utf8());
+
+    res.extend(std::iter::repeat_n(fill, left));
+    res.push_str(s);
+    res.extend(std::iter::repeat_n(fill, right));
+
+    Ok(res.into())
+}
+
+/// `char-length` returns the number of chars in a string.
+fn fn_char_length(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    Ok(s.chars().count().into())
+}
+
+/// `escape` escapes a string as it would appear within a string literal.
+fn fn_escape(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    Ok(escape_string(s).into())
+}
+
+/// `format-csv-line` formats a list of strings as a CSV record.
+fn fn_format_csv_line(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let fields = match args[0] {
+        Value::Unit => &[][..],
+        Value::List(ref li) => &li[..],
+        ref v => return Err(From::from(ExecError::expected("list", v)))
+    };
+
+    let mut res = String::new();
+
+    for (i, field) in fields.iter().enumerate() {
+        let field = <&str>::from_value_ref(field)?;
+
+        if i != 0 {
+            res.push(',');
+        }
+
+        if field.contains([',', '"', '\n', '\r']) {
+            res.push('"');
+            res.push_str(&field.replace('"', "\"\""));
+            res.push('"');
+        } else {
+            res.push_str(field);
+        }
+    }
+
+    Ok(res.into())
+}
+
+/// `keyword->string` returns the name of a keyword as a string.
+fn fn_keyword_to_string(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    match args[0] {
+        Value::Keyword(name) => Ok(ctx.scope().with_name(name, |s| s.into())),
+        ref v => Err(From::from(ExecError::expected("keyword", v)))
+    }
+}
+
+/// `normalize-newlines` converts all line endings in a string to `\n`.
+fn fn_normalize_newlines(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\r' {
+            chars.next_if_eq(&'\n');
+            res.push('\n');
+        } else {
+            res.push(ch);
+        }
+    }
+
+    Ok(res.into())
+}
+
+/// `parse-csv-line` parses a single CSV record into a list of fields.
+fn fn_parse_csv_line(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    fn csv_error(reason: &'static str) -> ExecError {
+        ExecError::InvalidEncoding{ encoding: "csv", reason }
+    }
+
+    let s = <&str>::from_value_ref(&args[0])?;
+    let mut chars = s.chars().peekable();
+    let mut fields = Vec::new();
+
+    loop {
+        let mut field = String::new();
+
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') => {
+                        if chars.next_if_eq(&'"').is_none() {
+                            break;
+                        }
+                        field.push('"');
+                    }
+                    Some(ch) => field.push(ch),
+                    None => return Err(From::from(
+                        csv_error("unterminated quoted field")))
+                }
+            }
+
+            fields.push(Value::from(field));
+
+            match chars.next() {
+                Some(',') => (),
+                Some(_) => return Err(From::from(
+                    csv_error("unexpected text after quoted field"))),
+                None => break
+            }
+        } else {
+            let more = loop {
+                match chars.next() {
+                    Some(',') => break true,
+                    None => break false,
+                    Some('"') => return Err(From::from(
+                        csv_error("quote within unquoted field"))),
+                    Some(ch) => field.push(ch)
+                }
+            };
+
+            fields.push(Value::from(field));
+
+            if !more {
+                break;
+            }
+        }
+    }
+
+    Ok(fields.into())
+}
+
+/// `parse-float` strictly parses a string as a float.
+fn fn_parse_float(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    // Reject the names `inf` and `NaN`, which Rust would accept
+    if !s.bytes().all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b)) {
+        return Err(From::from(invalid_number("float")));
+    }
+
+    match s.parse::<f64>() {
+        Ok(f) => Ok(f.into()),
+        Err(_) => Err(From::from(invalid_number("float")))
+    }
+}
+
+/// `parse-int` strictly parses a string as an integer.
+fn fn_parse_int(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    let radix = match args.get(1) {
+        Some(v) => match u32::from_value_ref(v) {
+            Ok(r) if (2..=36).contains(&r) => r,
+            _ => return Err(From::from(ExecError::expected("radix from 2 to 36", v)))
+        },
+        None => 10
+    };
+
+    // Signs and digit separators are checked here,
+    // as `from_str_radix` accepts a leading `+` and `_` between digits.
+    let digits = s.strip_prefix('-').unwrap_or(s);
+
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(From::from(invalid_number("integer")));
+    }
+
+    match Integer::from_str_radix(s, radix) {
+        Ok(i) => Ok(i.into()),
+        Err(_) => Err(From::from(invalid_number("integer")))
+    }
+}
+
+/// `rsplit-once` splits a string at the last occurrence of a separator.
+fn fn_rsplit_once(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let sep = expect_separator(&args[1])?;
+
+    Ok(split_pair(s.rsplit_once(sep)))
+}
+
+/// `split-once` splits a string at the first occurrence of a separator.
+fn fn_split_once(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let sep = expect_separator(&args[1])?;
+
+    Ok(split_pair(s.split_once(sep)))
+}
+
+fn expect_separator(v: &Value) -> Result<&str, ExecError> {
+    match <&str>::from_value_ref(v)? {
+        "" => Err(ExecError::expected("non-empty string", v)),
+        sep => Ok(sep)
+    }
+}
+
+fn split_pair(pair: Option<(&str, &str)>) -> Value {
+    match pair {
+        Some((a, b)) => vec![Value::from(a), Value::from(b)].into(),
+        None => Value::Unit
+    }
+}
+
+/// `split-lines-keepends` splits a string into lines, retaining line endings.
+fn fn_split_lines_keepends(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let bytes = s.as_bytes();
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\r' if bytes.get(i + 1) == Some(&b'\n') => i += 2,
+            b'\r' | b'\n' => i += 1,
+            _ => {
+                i += 1;
+                continue;
+            }
+        }
+
+        lines.push(&s[start..i]);
+        start = i;
+    }
+
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+
+    Ok(lines.into())
+}
+
+/// `split-whitespace` splits a string on runs of whitespace.
+fn fn_split_whitespace(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    Ok(s.split_whitespace().map(Value::from).collect::<Vec<_>>().into())
+}
+
+/// `string->keyword` returns a keyword with the given name.
+fn fn_string_to_keyword(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    match Lexer::new(&format!(":{}", s), 0).next_token() {
+        Ok((_, Token::Keyword(k))) if k.len() == s.len() =>
+            Ok(Value::Keyword(ctx.scope().add_name(s))),
+        _ => Err(From::from(invalid_identifier("keyword")))
+    }
+}
+
+/// `string->symbol` returns a name value with the given name.
+fn fn_string_to_symbol(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    match Lexer::new(s, 0).next_token() {
+        Ok((_, Token::Name(n))) if n.len() == s.len() =>
+            Ok(Value::Name(ctx.scope().add_name(s))),
+        _ => Err(From::from(invalid_identifier("name")))
+    }
+}
+
+/// `symbol->string` returns a name value as a string.
+fn fn_symbol_to_string(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    match args[0] {
+        Value::Name(name) => Ok(ctx.scope().with_name(name, |s| s.into())),
+        ref v => Err(From::from(ExecError::expected("name", v)))
+    }
+}
+
+/// `title-case` capitalizes each whitespace-separated word of a string.
+fn fn_title_case(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let mut res = String::with_capacity(s.len());
+    let mut word_start = None;
+
+    for (i, ch) in s.char_indices() {
+        if ch.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                capitalize_into(&mut res, &s[start..i]);
+            }
+            res.push(ch);
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+
+    if let Some(start) = word_start {
+        capitalize_into(&mut res, &s[start..]);
+    }
+
+    Ok(res.into())
+}
+
+/// `truncate-ellipsis` truncates a string to a given width, ending with `…`.
+fn fn_truncate_ellipsis(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let width = usize::from_value_ref(&args[1])?;
+
+    if s.chars().count() <= width {
+        return Ok(s.into());
+    }
+
+    let mut res = s.chars().take(width.saturating_sub(1)).collect::<String>();
+
+    if width != 0 {
+        res.push('…');
+    }
+
+    Ok(res.into())
+}
+
+/// `unescape` replaces escape sequences within a string.
+fn fn_unescape(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+
+    let res = unescape_string(s).map_err(|e| {
+        let reason = match e.kind {
+            ParseErrorKind::UnknownCharEscape(_) => "unknown escape sequence",
+            ParseErrorKind::InvalidNumericEscape(_) => "invalid numeric escape",
+            ParseErrorKind::UnterminatedString => "incomplete escape sequence",
+            _ => "invalid character"
+        };
+        ExecError::InvalidEncoding{ encoding: "escaped string", reason }
+    })?;
+
+    Ok(res.into())
+}
+
+/// `word-wrap` breaks lines of a string at word boundaries.
+fn fn_word_wrap(_ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let s = <&str>::from_value_ref(&args[0])?;
+    let width = usize::from_value_ref(&args[1])?;
+    let mut res = String::with_capacity(s.len());
+
+    for (i, line) in s.split('\n').enumerate() {
+        if i != 0 {
+            res.push('\n');
+        }
+
+        let mut col = 0;
+
+        for word in line.split_whitespace() {
+            let n = word.chars().count();
+
+            if col != 0 {
+                if col + 1 + n > width {
+                    res.push('\n');
+                    col = 0;
+                } else {
+                    res.push(' ');
+                    col += 1;
+                }
+            }
+
+            res.push_str(word);
+            col += n;
+        }
+    }
+
+    Ok(res.into())
+}
+
+/// Appends `s` to `res`, with the first alphabetic character in uppercase
+/// and all following characters in lowercase.
+fn capitalize_into(res: &mut String, s: &str) {
+    let mut chars = s.chars();
+
+    for ch in chars.by_ref() {
+        if ch.is_alphabetic() {
+            res.extend(ch.to_uppercase());
+            break;
+        }
+        res.push(ch);
+    }
+
+    for ch in chars {
+        res.extend(ch.to_lowercase());
+    }
+}
+
+/// Returns an error for a string that is not a valid number.
+fn invalid_number(encoding: &'static str) -> ExecError {
+    ExecError::InvalidEncoding{ encoding, reason: "not a valid number" }
+}
+
+/// Returns an error for a string that is not a valid name or keyword.
+fn invalid_identifier(encoding: &'static str) -> ExecError {
+    ExecError::InvalidEncoding{ encoding, reason: "not a valid identifier" }
+}
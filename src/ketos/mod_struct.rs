@@ -0,0 +1,136 @@
+//! Implements builtin `struct` module.
+
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::exec::{call_function, Context, ExecError};
+use crate::function::Arity::{Exact, Range};
+use crate::module::{Module, ModuleBuilder};
+use crate::name::Name;
+use crate::scope::Scope;
+use crate::structs::StructDef;
+use crate::value::{FromValueRef, Value};
+
+/// Loads the `struct` module into the given scope.
+pub fn load(scope: Scope) -> Module {
+    ModuleBuilder::new("struct", scope)
+        .add_function("map->struct",    fn_map_to_struct, Range(2, 3), Some("\
+Returns a struct value of the given struct definition, with fields taken
+from a list of `(:field value)` pairs.
+
+An error is raised if a field is missing or if a key does not name a field.
+If the optional third argument is `true`, keys which do not name a field
+are ignored instead.
+
+    (map->struct foo '((:a 1) (:b 2)))"))
+        .add_function("struct->map",    fn_struct_to_map, Exact(1), Some("\
+Returns a list of `(:field value)` pairs for each field of a struct value."))
+        .add_function("update-in",      fn_update_in,   Exact(3), Some("\
+Returns a struct value with the field at the given path replaced
+with the result of calling a function on its current value.
+
+The path is a list of keywords, each naming a field of the struct
+found at the previous step.
+
+    (update-in foo '(:bar :baz) (lambda (n) (+ n 1)))"))
+        .finish()
+}
+
+/// `map->struct` builds a struct value from a list of `(:field value)` pairs.
+fn fn_map_to_struct(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let def = match args[0] {
+        Value::StructDef(ref def) => def.clone(),
+        ref v => return Err(From::from(ExecError::expected("struct-def", v)))
+    };
+
+    let lenient = match args.get(2) {
+        Some(v) => bool::from_value_ref(v)?,
+        None => false
+    };
+
+    let field_names = def.def().field_names();
+    let mut fields = Vec::new();
+
+    let pairs = match args[1] {
+        Value::Unit => &[][..],
+        Value::List(ref li) => &li[..],
+        ref v => return Err(From::from(ExecError::expected("list", v)))
+    };
+
+    for pair in pairs {
+        let (name, value) = match *pair {
+            Value::List(ref li) if li.len() == 2 => (get_keyword(&li[0])?, li[1].clone()),
+            ref v => return Err(From::from(ExecError::expected("(:field value) pair", v)))
+        };
+
+        if fields.iter().any(|&(n, _)| n == name) {
+            return Err(From::from(ExecError::DuplicateField(name)));
+        }
+
+        if lenient && !field_names.contains(&name) {
+            continue;
+        }
+
+        fields.push((name, value));
+    }
+
+    def.def().from_fields(ctx.scope(), &def, &mut fields)
+}
+
+/// `struct->map` returns a list of `(:field value)` pairs of a struct value.
+fn fn_struct_to_map(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let value = &args[0];
+    let def = get_struct_def_for(ctx.scope(), value)?;
+
+    let pairs = def.def().field_names().into_iter()
+        .map(|name| {
+            let v = def.def().get_field(ctx.scope(), &def, value, name)?;
+            Ok(vec![Value::Keyword(name), v].into())
+        })
+        .collect::<Result<Vec<Value>, Error>>()?;
+
+    Ok(pairs.into())
+}
+
+/// `update-in` applies a function to a nested struct field.
+fn fn_update_in(ctx: &Context, args: &mut [Value]) -> Result<Value, Error> {
+    let value = args[0].take();
+    let f = args[2].take();
+
+    let path = match args[1] {
+        Value::Unit => Vec::new(),
+        Value::List(ref li) => li.iter().map(get_keyword).collect::<Result<Vec<_>, _>>()?,
+        ref v => return Err(From::from(ExecError::expected("list", v)))
+    };
+
+    update_in(ctx, value, &path, f)
+}
+
+fn update_in(ctx: &Context, value: Value, path: &[Name], f: Value) -> Result<Value, Error> {
+    let (&field, rest) = match path.split_first() {
+        Some(p) => p,
+        None => return call_function(ctx, f, vec![value])
+    };
+
+    let def = get_struct_def_for(ctx.scope(), &value)?;
+    let inner = def.def().get_field(ctx.scope(), &def, &value, field)?;
+    let inner = update_in(ctx, inner, rest, f)?;
+
+    def.def().replace_fields(ctx.scope(), &def, value, &mut [(field, inner)])
+}
+
+fn get_keyword(v: &Value) -> Result<Name, ExecError> {
+    match *v {
+        Value::Keyword(name) => Ok(name),
+        ref v => Err(ExecError::expected("keyword", v))
+    }
+}
+
+fn get_struct_def_for(scope: &Scope, v: &Value) -> Result<Rc<StructDef>, ExecError> {
+    match *v {
+        Value::Struct(ref s) => Ok(s.def().clone()),
+        Value::Foreign(ref fv) => scope.get_struct_def(fv.type_id())
+            .ok_or_else(|| ExecError::expected("struct", v)),
+        ref v => Err(ExecError::expected("struct", v))
+    }
+}
@@ -19,8 +19,16 @@ use crate::parser::Parser;
 use crate::scope::{GlobalScope, ImportSet, Scope};
 use crate::value::Value;
 
+use crate::mod_box;
+use crate::mod_bytes;
 use crate::mod_code;
+use crate::mod_env;
+use crate::mod_file;
+use crate::mod_json;
 use crate::mod_math;
+use crate::mod_regex;
+use crate::mod_string;
+use crate::mod_struct;
 
 /// Contains the values in a loaded module's namespace.
 #[derive(Clone)]
@@ -201,11 +209,45 @@ impl ModuleCode {
 
         Ok(())
     }
+
+    /// Loads contained values into the given scope and sequentially executes
+    /// all contained code objects, returning the value of the last.
+    ///
+    /// Unlike `load_in_context`, the scope need not be empty;
+    /// its exports and module documentation are left unchanged.
+    pub fn run_in_context(self, ctx: &Context) -> Result<Value, Error> {
+        for (name, value) in self.constants {
+            ctx.scope().add_constant(name, value);
+        }
+
+        for (name, code) in self.macros {
+            let mac = Lambda::new(code, ctx.scope());
+            ctx.scope().add_macro(name, mac);
+        }
+
+        for (name, value) in self.values {
+            ctx.scope().add_value(name, value);
+        }
+
+        process_imports(ctx, &self.imports)?;
+
+        for (name, doc) in self.docs {
+            ctx.scope().add_doc_string(name, doc);
+        }
+
+        let mut last_v = Value::Unit;
+
+        for code in self.code {
+            last_v = execute(ctx, code)?;
+        }
+
+        Ok(last_v)
+    }
 }
 
 /// Loads modules into the running program and caches previously loaded modules
 pub struct ModuleRegistry {
-    loader: Box<dyn ModuleLoader>,
+    loader: Rc<dyn ModuleLoader>,
     modules: RefCell<NameMap<Module>>,
 }
 
@@ -214,11 +256,23 @@ impl ModuleRegistry {
     /// to load new modules.
     pub fn new(loader: Box<dyn ModuleLoader>) -> ModuleRegistry {
         ModuleRegistry{
-            loader,
+            loader: Rc::from(loader),
             modules: RefCell::new(NameMap::new()),
         }
     }
 
+    /// Creates a new `ModuleRegistry` which uses the same `ModuleLoader`
+    /// and begins with the modules already loaded into this registry.
+    ///
+    /// Modules loaded into either registry afterward are not visible
+    /// to the other.
+    pub fn fork(&self) -> ModuleRegistry {
+        ModuleRegistry{
+            loader: self.loader.clone(),
+            modules: RefCell::new(self.modules.borrow().clone()),
+        }
+    }
+
     /// Inserts a named module into the registry.
     pub fn add_module(&self, name: Name, module: Module) -> Option<Module> {
         self.modules.borrow_mut().insert(name, module)
@@ -229,6 +283,11 @@ impl ModuleRegistry {
         self.modules.borrow().get(name).cloned()
     }
 
+    /// Returns the names of all loaded modules.
+    pub fn module_names(&self) -> Vec<Name> {
+        self.modules.borrow().iter().map(|&(name, _)| name).collect()
+    }
+
     /// Returns a loaded module. If the module has not been loaded in this
     /// registry; the contained `ModuleLoader` instance will be used to load it.
     pub fn load_module(&self, name: Name, ctx: &Context) -> Result<Module, Error> {
@@ -317,12 +376,35 @@ impl ModuleLoader for BuiltinModuleLoader {
     }
 }
 
-fn get_loader(name: &str) -> Option<fn(Scope) -> Module> {
-    match name {
-        "code" => Some(mod_code::load),
-        "math" => Some(mod_math::load),
-        _ => None
-    }
+type LoadFn = fn(Scope) -> Module;
+
+const BUILTIN_MODULES: &[(&str, LoadFn)] = &[
+    ("box", mod_box::load),
+    ("bytes", mod_bytes::load),
+    ("code", mod_code::load),
+    ("env", mod_env::load),
+    ("file", mod_file::load),
+    ("json", mod_json::load),
+    ("math", mod_math::load),
+    ("regex", mod_regex::load),
+    ("string", mod_string::load),
+    ("struct", mod_struct::load),
+];
+
+fn get_loader(name: &str) -> Option<LoadFn> {
+    BUILTIN_MODULES.iter()
+        .find(|&&(n, _)| n == name)
+        .map(|&(_, load)| load)
+}
+
+/// Returns whether the given name refers to a builtin module.
+pub fn is_builtin_module(name: &str) -> bool {
+    get_loader(name).is_some()
+}
+
+/// Returns an iterator over the names of all builtin modules.
+pub fn builtin_module_names() -> impl Iterator<Item=&'static str> {
+    BUILTIN_MODULES.iter().map(|&(name, _)| name)
 }
 
 fn load_builtin_module(name: Name, scope: &Scope) -> Result<Module, Error> {
@@ -522,15 +604,8 @@ fn load_module_from_file(ctx: Context, name: Name,
     file.read_to_string(&mut buf)
         .map_err(|e| IoError::new(IoMode::Read, src_path, e))?;
 
-    let exprs = {
-        let offset = ctx.scope().borrow_codemap_mut().add_source(&buf,
-            Some(src_path.to_string_lossy().into_owned()));
-
-        Parser::new(&ctx, Lexer::new(&buf, offset)).parse_exprs()?
-    };
-
-    let code = exprs.iter()
-        .map(|e| compile(&ctx, e).map(Rc::new)).collect::<Result<Vec<_>, _>>()?;
+    let code = compile_source(&ctx, &buf,
+        Some(src_path.to_string_lossy().into_owned()))?;
 
     if let Some(code_path) = code_path {
         // Grab compile-time values before executing code
@@ -564,6 +639,39 @@ fn load_module_from_file(ctx: Context, name: Name,
     })
 }
 
+/// Compiles and executes source code as the named module.
+///
+/// The given `Context` should contain a new, empty scope, such as one
+/// created by `GlobalScope::new_using`. The module is not inserted into
+/// the `ModuleRegistry`; the caller may do so using `add_module`.
+pub fn load_module_from_source(ctx: Context, name: Name,
+        source: &str, path: Option<String>) -> Result<Module, Error> {
+    let code = compile_source(&ctx, source, path)?;
+
+    for code in &code {
+        execute(&ctx, code.clone())?;
+    }
+
+    check_exports(ctx.scope(), name)?;
+
+    Ok(Module{
+        name,
+        scope: ctx.scope().clone(),
+    })
+}
+
+fn compile_source(ctx: &Context, source: &str, path: Option<String>)
+        -> Result<Vec<Rc<Code>>, Error> {
+    let exprs = {
+        let offset = ctx.scope().borrow_codemap_mut().add_source(source, path);
+
+        Parser::new(ctx, Lexer::new(source, offset)).parse_exprs()?
+    };
+
+    exprs.iter()
+        .map(|e| compile(ctx, e).map(Rc::new)).collect()
+}
+
 fn process_imports(ctx: &Context, imports: &[ImportSet]) -> Result<(), Error> {
     let mods = ctx.scope().modules();
 
@@ -62,6 +62,12 @@ pub fn is_system_operator(name: Name) -> bool {
     name.0 >= SYSTEM_OPERATORS_BEGIN && name.0 < SYSTEM_OPERATORS_END
 }
 
+/// Returns whether the given name corresponds to a dynamic variable,
+/// which may be bound using `dynamic-let`.
+pub fn is_dynamic_var(name: Name) -> bool {
+    name == standard_names::PRINT_READABLY
+}
+
 macro_rules! standard_names {
     ( $( $s:expr => $name:ident = $value:expr , )+ ) => {
         /// Contains constants standard name values; interned names which are
@@ -174,73 +180,82 @@ standard_names!{
     "panic" => PANIC = 68,
     "xor" => XOR = 69,
     "not" => NOT = 70,
+    "make-ratio" => MAKE_RATIO = 71,
+    "numerator" => NUMERATOR = 72,
+    "denominator" => DENOMINATOR = 73,
     // End of names referring to system functions.
     // The constant `NUM_SYSTEM_FNS` below should be one greater than
     // the value immediately above this comment.
 
     // Boolean names; the parser will replace these with boolean values.
     // These names must follow immediately after system function names.
-    "false" => FALSE = 71,
-    "true" => TRUE = 72,
+    "false" => FALSE = 74,
+    "true" => TRUE = 75,
     // End of names referring to standard values.
     // The constant `NUM_STANDARD_VALUES` below should be one greater than
     // the value immediately above this comment.
 
     // Special operators follow; these are not represented as values in global
     // scope. They are only handled by the compiler.
-    "apply" => APPLY = 73,
-    "do" => DO = 74,
-    "let" => LET = 75,
-    "define" => DEFINE = 76,
-    "macro" => MACRO = 77,
-    "struct" => STRUCT = 78,
-    "if" => IF = 79,
-    "and" => AND = 80,
-    "or" => OR = 81,
-    "case" => CASE = 82,
-    "cond" => COND = 83,
-    "lambda" => LAMBDA = 84,
-    "export" => EXPORT = 85,
-    "use" => USE = 86,
-    "const" => CONST = 87,
-    "set-module-doc" => SET_MODULE_DOC = 88,
-    "call-self" => CALL_SELF = 89,
+    "apply" => APPLY = 76,
+    "do" => DO = 77,
+    "let" => LET = 78,
+    "define" => DEFINE = 79,
+    "macro" => MACRO = 80,
+    "struct" => STRUCT = 81,
+    "if" => IF = 82,
+    "and" => AND = 83,
+    "or" => OR = 84,
+    "case" => CASE = 85,
+    "cond" => COND = 86,
+    "lambda" => LAMBDA = 87,
+    "export" => EXPORT = 88,
+    "use" => USE = 89,
+    "const" => CONST = 90,
+    "set-module-doc" => SET_MODULE_DOC = 91,
+    "call-self" => CALL_SELF = 92,
+    "let-syntax" => LET_SYNTAX = 93,
+    "dynamic-let" => DYNAMIC_LET = 94,
+    "match" => MATCH = 95,
 
     // Just plain names follow; these are used by system functions or operators
     // to delineate syntactical constructs or just as name values.
-    "all" => ALL = 90,
-    "else" => ELSE = 91,
-    "optional" => OPTIONAL = 92,
-    "key" => KEY = 93,
-    "rest" => REST = 94,
-    "unbound" => UNBOUND = 95,
-    "unit" => UNIT = 96,
-    "bool" => BOOL = 97,
-    "char" => CHAR = 98,
-    "integer" => INTEGER = 99,
-    "ratio" => RATIO = 100,
-    "struct-def" => STRUCT_DEF = 101,
-    "keyword" => KEYWORD = 102,
-    "object" => OBJECT = 103,
-    "name" => NAME = 104,
-    "number" => NUMBER = 105,
-    "function" => FUNCTION = 106,
-    "self" => SELF = 107,
+    "all" => ALL = 96,
+    "else" => ELSE = 97,
+    "optional" => OPTIONAL = 98,
+    "key" => KEY = 99,
+    "rest" => REST = 100,
+    "unbound" => UNBOUND = 101,
+    "unit" => UNIT = 102,
+    "bool" => BOOL = 103,
+    "char" => CHAR = 104,
+    "integer" => INTEGER = 105,
+    "ratio" => RATIO = 106,
+    "struct-def" => STRUCT_DEF = 107,
+    "keyword" => KEYWORD = 108,
+    "object" => OBJECT = 109,
+    "name" => NAME = 110,
+    "number" => NUMBER = 111,
+    "function" => FUNCTION = 112,
+    "self" => SELF = 113,
+
+    // Dynamic variables, bound with `dynamic-let`
+    "*print-readably*" => PRINT_READABLY = 114,
 }
 
 /// Number of standard names
-pub const NUM_STANDARD_NAMES: u32 = 108;
+pub const NUM_STANDARD_NAMES: u32 = 115;
 
 /// Number of names, starting at `0`, which refer to system functions.
-pub const NUM_SYSTEM_FNS: usize = 71;
+pub const NUM_SYSTEM_FNS: usize = 74;
 
 /// Number of names, starting at `0`, which refer to standard values.
-pub const NUM_STANDARD_VALUES: u32 = 73;
+pub const NUM_STANDARD_VALUES: u32 = 76;
 
 /// First standard name which refers to a system operator.
 pub const SYSTEM_OPERATORS_BEGIN: u32 = NUM_STANDARD_VALUES;
 /// One-past-the-end of standard names which refer to system operators.
-pub const SYSTEM_OPERATORS_END: u32 = 90;
+pub const SYSTEM_OPERATORS_END: u32 = 96;
 
 /// Number of system operators, beginning at `SYSTEM_OPERATORS_BEGIN`.
 pub const NUM_SYSTEM_OPERATORS: usize =
@@ -553,6 +568,13 @@ impl<T> NameMap<T> {
         }
     }
 
+    /// Removes the value for the given name from the map, returning it
+    /// if it was present.
+    pub fn remove(&mut self, name: Name) -> Option<T> {
+        self.values.binary_search_by(|(n, _)| n.cmp(&name))
+            .ok().map(|pos| self.values.remove(pos).1)
+    }
+
     /// Returns the number of name-value pairs contained in the map.
     pub fn len(&self) -> usize {
         self.values.len()
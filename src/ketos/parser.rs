@@ -7,7 +7,7 @@ use std::path::PathBuf;
 
 use crate::bytes::Bytes;
 use crate::error::Error;
-use crate::exec::Context;
+use crate::exec::{Context, ExecError};
 use crate::integer::{Integer, Ratio};
 use crate::lexer::{Lexer, Span, Token};
 use crate::name::{get_standard_name_for, standard_names, Name, NameDisplay, NameStore};
@@ -16,6 +16,9 @@ use crate::string;
 use crate::value::Value;
 
 const MODULE_DOC_COMMENT: &str = ";;;";
+/// Greatest magnitude of exponent accepted in an exact decimal literal,
+/// regardless of the configured integer size limit
+const MAX_DECIMAL_EXPONENT: u64 = 100_000;
 
 /// Parses a stream of tokens into an expression.
 pub struct Parser<'a, 'lex> {
@@ -76,6 +79,8 @@ pub enum ParseErrorKind {
     LiteralParseError,
     /// Missing closing parenthesis
     MissingCloseParen,
+    /// Groups nested beyond the configured limit
+    NestingTooDeep,
     /// More commas than backquotes
     UnbalancedComma,
     /// Unexpected end-of-file
@@ -117,6 +122,7 @@ impl fmt::Display for ParseErrorKind {
                 write!(f, "invalid character in escape sequence: {:?}", ch),
             ParseErrorKind::LiteralParseError => f.write_str("literal parse error"),
             ParseErrorKind::MissingCloseParen => f.write_str("missing close paren"),
+            ParseErrorKind::NestingTooDeep => f.write_str("expression nested too deeply"),
             ParseErrorKind::UnbalancedComma => f.write_str("unbalanced ` and ,"),
             ParseErrorKind::UnexpectedEof => f.write_str("unexpected end-of-file"),
             ParseErrorKind::UnexpectedToken{expected, found} =>
@@ -229,6 +235,7 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                         }
                     }
 
+                    self.check_nesting(stack.len(), sp)?;
                     stack.push(Group::Parens(kind, Vec::new(), doc.take()));
                     continue;
                 }
@@ -245,6 +252,9 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                             }).into())
                     }
                 }
+                Token::Float(f) if self.ctx.scope().exact_decimals() =>
+                    parse_decimal(self.ctx, f, sp)
+                        .map(Value::Ratio),
                 Token::Float(f) => parse_float(f)
                     .map(Value::Float)
                     .map_err(|kind| From::from(ParseError::new(sp, kind))),
@@ -255,7 +265,8 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                 Token::Char(ch) => parse_char(ch)
                     .map(Value::Char).map_err(From::from),
                 Token::String(s) => parse_string(s)
-                    .map(|s| s.into()).map_err(From::from),
+                    .map(|s| Value::String(self.ctx.scope().intern_string(&s)))
+                    .map_err(From::from),
                 Token::Byte(b) => parse_byte(b)
                     .map(|v| v.into()).map_err(From::from),
                 Token::Bytes(b) => parse_bytes(b)
@@ -270,6 +281,7 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                         *n += 1;
                         continue;
                     }
+                    self.check_nesting(stack.len(), sp)?;
                     stack.push(Group::Backticks(1));
                     continue;
                 }
@@ -282,6 +294,7 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                         *n -= 1;
                         continue;
                     }
+                    self.check_nesting(stack.len(), sp)?;
                     stack.push(Group::Backticks(-1));
                     continue;
                 }
@@ -290,6 +303,7 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                         return Err(From::from(ParseError::new(sp, ParseErrorKind::UnbalancedComma)));
                     }
                     total_backticks -= 1;
+                    self.check_nesting(stack.len(), sp)?;
                     stack.push(Group::CommaAt);
                     continue;
                 }
@@ -298,6 +312,7 @@ impl<'a, 'lex> Parser<'a, 'lex> {
                         *n += 1;
                         continue;
                     }
+                    self.check_nesting(stack.len(), sp)?;
                     stack.push(Group::Quotes(1));
                     continue;
                 }
@@ -397,6 +412,15 @@ impl<'a, 'lex> Parser<'a, 'lex> {
         Ok(res)
     }
 
+    /// Returns an error if a new group cannot be opened at the given depth.
+    fn check_nesting(&self, depth: usize, sp: Span) -> Result<(), ParseError> {
+        if depth >= self.ctx.scope().max_parse_nesting() {
+            Err(ParseError::new(sp, ParseErrorKind::NestingTooDeep))
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns a borrowed reference to the contained `Lexer`.
     pub fn lexer(&self) -> &Lexer<'lex> {
         &self.lexer
@@ -542,6 +566,43 @@ fn parse_float(s: &str) -> Result<f64, ParseErrorKind> {
         .map_err(|_| ParseErrorKind::LiteralParseError)
 }
 
+/// Parses a decimal literal into the exact `Ratio` it represents.
+fn parse_decimal(ctx: &Context, s: &str, sp: Span) -> Result<Ratio, Error> {
+    let err = || Error::from(ParseError::new(sp, ParseErrorKind::LiteralParseError));
+
+    let s = strip_underscores(s);
+
+    let (mantissa, exp) = match s.find(['e', 'E']) {
+        Some(pos) => (&s[..pos], s[pos + 1..].parse::<i32>().map_err(|_| err())?),
+        None => (&s[..], 0)
+    };
+
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(pos) => (&mantissa[..pos], &mantissa[pos + 1..]),
+        None => (mantissa, "")
+    };
+
+    let exp = i64::from(exp) - frac_part.len() as i64;
+    let digits = format!("{}{}", int_part, frac_part);
+
+    if exp.unsigned_abs() > MAX_DECIMAL_EXPONENT {
+        return Err(err());
+    }
+
+    // Bound the combined size of the digits and the scale
+    let n_digits = digits.trim_start_matches('-').trim_start_matches('0').len();
+    check_digits(ctx, n_digits + exp.unsigned_abs() as usize, 10)?;
+
+    let numer = Integer::from_str_radix(&digits, 10).map_err(|_| err())?;
+    let scale = Integer::from_u32(10).pow(exp.unsigned_abs() as usize);
+
+    if exp < 0 {
+        Ok(Ratio::new(numer, scale))
+    } else {
+        Ok(Ratio::from_integer(numer * scale))
+    }
+}
+
 fn parse_integer(ctx: &Context, s: &str, base: u32, sp: Span)
         -> Result<Integer, Error> {
     let s = match base {
@@ -553,9 +614,15 @@ fn parse_integer(ctx: &Context, s: &str, base: u32, sp: Span)
 
     check_integer(ctx, &s, base)?;
 
-    Integer::from_str_radix(&s, base)
-        .map_err(|_| From::from(ParseError::new(sp,
-            ParseErrorKind::LiteralParseError)))
+    let i = Integer::from_str_radix(&s, base)
+        .map_err(|_| Error::from(ParseError::new(sp,
+            ParseErrorKind::LiteralParseError)))?;
+
+    if ctx.scope().fixed_integers() && i.to_i64().is_none() {
+        return Err(From::from(ExecError::Overflow));
+    }
+
+    Ok(i)
 }
 
 fn parse_ratio(ctx: &Context, s: &str, sp: Span) -> Result<Ratio, Error> {
@@ -580,8 +647,14 @@ fn check_integer(ctx: &Context, mut s: &str, base: u32) -> Result<(), RestrictEr
         s = s.trim_start_matches('0');
     }
 
+    check_digits(ctx, s.len(), base)
+}
+
+fn check_digits(ctx: &Context, n_digits: usize, base: u32) -> Result<(), RestrictError> {
+    let limit = ctx.restrict().max_integer_size;
+
     // Approximate the number of bits that could be represented by a number of bytes.
-    let n_bits = (s.len() as f32 * (base as f32).log2()).ceil() as usize;
+    let n_bits = (n_digits as f32 * (base as f32).log2()).ceil() as usize;
 
     if n_bits > limit {
         Err(RestrictError::IntegerLimitExceeded)
@@ -602,7 +675,7 @@ fn strip_underscores(s: &str) -> Cow<str> {
 mod test {
     use super::{ParseError, ParseErrorKind, Parser};
     use crate::error::Error;
-    use crate::interpreter::Interpreter;
+    use crate::interpreter::{Builder, Interpreter};
     use crate::lexer::{Span, Lexer};
     use crate::value::Value;
 
@@ -638,6 +711,31 @@ mod test {
             span: Span{lo: 7, hi: 8}, kind: ParseErrorKind::UnbalancedComma});
     }
 
+    #[test]
+    fn test_nesting_limit() {
+        let deep = "(".repeat(10_000);
+
+        assert_eq!(parse(&deep).unwrap_err(), ParseError{
+            span: Span{lo: 256, hi: 257}, kind: ParseErrorKind::NestingTooDeep});
+        assert_eq!(parse(&"'(".repeat(200)).unwrap_err(), ParseError{
+            span: Span{lo: 256, hi: 257}, kind: ParseErrorKind::NestingTooDeep});
+
+        // The default limit applies under permissive restrictions
+        let deep = format!("{})", "(".repeat(200_000));
+        assert_matches!(Interpreter::new().run_code(&deep, None).unwrap_err(),
+            Error::ParseError(ParseError{kind: ParseErrorKind::NestingTooDeep, ..}));
+
+        let interp = Builder::new().max_parse_nesting(3).finish();
+
+        let mut p = Parser::new(interp.context(), Lexer::new("(((a)))", 0));
+        p.parse_expr().unwrap();
+
+        let mut p = Parser::new(interp.context(), Lexer::new("((((a))))", 0));
+        assert_matches!(p.parse_expr().unwrap_err(),
+            Error::ParseError(ParseError{
+                span: Span{lo: 3, hi: 4}, kind: ParseErrorKind::NestingTooDeep}));
+    }
+
     #[test]
     fn test_lexer_position() {
         let interp = Interpreter::new();
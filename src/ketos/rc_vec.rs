@@ -1,9 +1,10 @@
 //! Implements a reference-counted `Vec` supporting efficient subslicing.
 
-use std::borrow::Cow;
+use std::borrow::{Borrow, Cow};
 use std::cmp::Ordering;
 use std::ffi::OsStr;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops;
 use std::path::Path;
 use std::rc::Rc;
@@ -61,6 +62,13 @@ impl RcString {
         self.end - self.start
     }
 
+    /// Returns whether two `RcString` values are views of the same range
+    /// within the same shared allocation.
+    pub fn ptr_eq(&self, other: &RcString) -> bool {
+        Rc::ptr_eq(&self.data, &other.data) &&
+            self.start == other.start && self.end == other.end
+    }
+
     /// Returns a subslice of the `RcString`.
     pub fn slice<R: RangeArgument<usize>>(&self, range: R) -> RcString {
         let start = range.start().map_or(0, |v| *v);
@@ -208,6 +216,14 @@ impl Ord for RcString {
     fn cmp(&self, rhs: &RcString) -> Ordering { self[..].cmp(&rhs[..]) }
 }
 
+impl Hash for RcString {
+    fn hash<H: Hasher>(&self, h: &mut H) { self[..].hash(h) }
+}
+
+impl Borrow<str> for RcString {
+    fn borrow(&self) -> &str { &self[..] }
+}
+
 impl<'a> From<&'a str> for RcString {
     fn from(s: &str) -> RcString {
         RcString::new(s.to_owned())
@@ -251,6 +267,13 @@ impl<T> RcVec<T> {
         self.end - self.start
     }
 
+    /// Returns whether two `RcVec` values are views of the same range
+    /// within the same shared allocation.
+    pub fn ptr_eq(&self, other: &RcVec<T>) -> bool {
+        Rc::ptr_eq(&self.data, &other.data) &&
+            self.start == other.start && self.end == other.end
+    }
+
     /// Returns a subslice of the `RcVec`, with the range being relative
     /// to this slice's boundaries.
     pub fn slice<R: RangeArgument<usize>>(&self, range: R) -> RcVec<T> {
@@ -463,6 +486,11 @@ mod test {
         assert_eq!(b.is_empty(), false);
         assert_eq!(b.len(), 2);
 
+        assert!(a.ptr_eq(&a.clone()));
+        assert!(b.ptr_eq(&a.slice(1..3)));
+        assert!(!a.ptr_eq(&b));
+        assert!(!a.ptr_eq(&RcVec::new(vec![1, 2, 3])));
+
         b.push(4);
         assert_eq!(b, [2, 3, 4]);
 
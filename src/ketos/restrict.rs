@@ -30,6 +30,7 @@
 //! ```
 
 use std::fmt;
+use std::ops::{BitOr, Sub};
 use std::time::Duration;
 
 use crate::name::{NameDisplay, NameStore};
@@ -45,7 +46,18 @@ pub struct RestrictConfig {
     /// If the user desires to limit total execution time of multiple separate
     /// function calls, the user must track execution time and adjust this
     /// limit manually.
+    ///
+    /// The limit is checked periodically, after a fixed number of instructions,
+    /// so that code which never returns is interrupted with an
+    /// `ExecutionTimeExceeded` error. Time spent within a single call to a
+    /// foreign function is not interrupted.
     pub execution_time: Option<Duration>,
+    /// Limits the number of bytecode instructions executed, beginning from
+    /// a call into the virtual machine, until the topmost function returns.
+    ///
+    /// Unlike `execution_time`, the limit does not depend on the speed of
+    /// the host, so a given program is always stopped at the same point.
+    pub max_steps: Option<u64>,
     /// Limits the call stack depth during execution to a number of nested
     /// functions calls
     pub call_stack_size: usize,
@@ -62,6 +74,86 @@ pub struct RestrictConfig {
     pub max_integer_size: usize,
     /// Maximum nested depth of syntactical elements
     pub max_syntax_nesting: usize,
+    /// Groups of builtin functionality available to executing code.
+    /// Calling a function belonging to a group not present in this set
+    /// results in a `PermissionDenied` error.
+    pub capabilities: Capabilities,
+    /// Names of environment variables readable by executing code,
+    /// when the `ENV` capability is enabled.
+    /// If `None`, any environment variable may be read.
+    pub env_vars: Option<Vec<String>>,
+}
+
+/// Set of capability groups which may be granted to executing code
+///
+/// Capabilities may be combined using the `|` operator and removed
+/// using the `-` operator.
+///
+/// ```
+/// use ketos::{Capabilities, RestrictConfig};
+///
+/// let restrict = RestrictConfig{
+///     capabilities: Capabilities::all() - Capabilities::EVAL,
+///     .. RestrictConfig::strict()
+/// };
+/// # let _ = restrict;
+/// ```
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Capabilities {
+    bits: u32,
+}
+
+impl Capabilities {
+    /// Calling `print`, `println`, `eprint`, `eprintln`, and `code/disassemble`
+    ///
+    /// Output written by the host, e.g. through `Interpreter` methods,
+    /// is not affected.
+    pub const IO: Capabilities = Capabilities{bits: 1 << 0};
+    /// Calling `code/compile`
+    ///
+    /// Compilation performed by the host, or of modules imported with `use`,
+    /// is not affected.
+    pub const EVAL: Capabilities = Capabilities{bits: 1 << 1};
+    /// Calling functions of the `file` module
+    ///
+    /// Source and bytecode files of modules imported with `use` are read
+    /// and written by the `ModuleLoader`, which is not affected.
+    pub const FILES: Capabilities = Capabilities{bits: 1 << 2};
+    /// Reading environment variables, using functions of the `env` module
+    pub const ENV: Capabilities = Capabilities{bits: 1 << 3};
+
+    const ALL_BITS: u32 = (1 << 4) - 1;
+
+    /// Returns a set containing all capabilities.
+    pub fn all() -> Capabilities {
+        Capabilities{bits: Capabilities::ALL_BITS}
+    }
+
+    /// Returns a set containing no capabilities.
+    pub fn none() -> Capabilities {
+        Capabilities{bits: 0}
+    }
+
+    /// Returns whether all capabilities in `other` are contained in `self`.
+    pub fn contains(self, other: Capabilities) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, rhs: Capabilities) -> Capabilities {
+        Capabilities{bits: self.bits | rhs.bits}
+    }
+}
+
+impl Sub for Capabilities {
+    type Output = Capabilities;
+
+    fn sub(self, rhs: Capabilities) -> Capabilities {
+        Capabilities{bits: self.bits & !rhs.bits}
+    }
 }
 
 /// Represents an error caused by breach of runtime execution restrictions
@@ -69,6 +161,8 @@ pub struct RestrictConfig {
 pub enum RestrictError {
     /// Execution time exceeded limit
     ExecutionTimeExceeded,
+    /// Number of executed instructions exceeded limit
+    StepLimitExceeded,
     /// Call stack exceeded limit
     CallStackExceeded,
     /// Value stack exceeded limit
@@ -81,6 +175,8 @@ pub enum RestrictError {
     IntegerLimitExceeded,
     /// Nested syntax exceeded limit
     MaxSyntaxNestingExceeded,
+    /// Call to a function whose capability group is not enabled
+    PermissionDenied,
 }
 
 impl RestrictError {
@@ -90,12 +186,14 @@ impl RestrictError {
 
         match self {
             ExecutionTimeExceeded => "execution time exceeded",
+            StepLimitExceeded => "step limit exceeded",
             CallStackExceeded => "max call stack exceeded",
             ValueStackExceeded => "max value stack exceeded",
             NamespaceSizeExceeded => "max namespace size exceeded",
             MemoryLimitExceeded => "max memory limit exceeded",
             IntegerLimitExceeded => "integer size limit exceeded",
             MaxSyntaxNestingExceeded => "max syntax nesting exceeded",
+            PermissionDenied => "permission denied",
         }
     }
 }
@@ -131,28 +229,52 @@ impl RestrictConfig {
     pub fn permissive() -> RestrictConfig {
         RestrictConfig{
             execution_time: None,
+            max_steps: None,
             call_stack_size: PERMISSIVE_CALL_STACK_SIZE,
             value_stack_size: PERMISSIVE_VALUE_STACK_SIZE,
             namespace_size: usize::max_value(),
             memory_limit: usize::max_value(),
             max_integer_size: usize::max_value(),
             max_syntax_nesting: usize::max_value(),
+            capabilities: Capabilities::all(),
+            env_vars: None,
         }
     }
 
     /// Returns a `RestrictConfig` that is most strict.
     ///
     /// Small programs with short runtimes should not have a problem operating
-    /// within these restrictions.
+    /// within these restrictions. Access to files and environment variables
+    /// is not permitted.
     pub fn strict() -> RestrictConfig {
         RestrictConfig{
             execution_time: Some(Duration::from_millis(100)),
+            max_steps: None,
             call_stack_size: STRICT_CALL_STACK_SIZE,
             value_stack_size: STRICT_VALUE_STACK_SIZE,
             namespace_size: 32,
             memory_limit: STRICT_VALUE_STACK_SIZE,
             max_integer_size: 100,
             max_syntax_nesting: 32,
+            capabilities: Capabilities::all() - Capabilities::FILES - Capabilities::ENV,
+            env_vars: None,
+        }
+    }
+
+    /// Returns an error if any of the given capabilities is not enabled.
+    pub fn require(&self, caps: Capabilities) -> Result<(), RestrictError> {
+        if self.capabilities.contains(caps) {
+            Ok(())
+        } else {
+            Err(RestrictError::PermissionDenied)
+        }
+    }
+
+    /// Returns whether the named environment variable may be read.
+    pub fn allows_env_var(&self, name: &str) -> bool {
+        match self.env_vars {
+            Some(ref names) => names.iter().any(|n| n == name),
+            None => true,
         }
     }
 }
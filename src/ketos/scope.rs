@@ -1,17 +1,22 @@
 //! Contains values associated with names in a given execution context.
 
 use std::any::TypeId;
-use std::cell::{Ref, RefMut, RefCell};
+use std::cell::{Cell, Ref, RefMut, RefCell};
+use std::collections::HashSet;
+use std::ptr;
 use std::rc::{Rc, Weak};
 
+use crate::bytecode::Code;
+use crate::compile::CompileWarning;
 use crate::function::{Function, Lambda};
 use crate::io::GlobalIo;
 use crate::lexer::CodeMap;
 use crate::module::{BuiltinModuleLoader, ModuleRegistry};
+use crate::rc_vec::{RcString, RcVec};
 use crate::name::{get_standard_name, get_system_fn, is_system_operator,
     is_standard_value, NUM_STANDARD_VALUES,
     SYSTEM_OPERATORS_END, Name, NameMap, NameSetSlice, NameStore};
-use crate::structs::{ForeignStructDef, StructDef, StructDefMap, StructValue};
+use crate::structs::{ForeignStructDef, Struct, StructDef, StructDefMap, StructValue};
 use crate::value::Value;
 
 /// Represents the global namespace of an execution context.
@@ -23,6 +28,38 @@ pub struct GlobalScope {
     modules: Rc<ModuleRegistry>,
     io: Rc<GlobalIo>,
     struct_defs: Rc<RefCell<StructDefMap>>,
+    strings: Rc<StringPool>,
+    max_parse_nesting: Rc<Cell<usize>>,
+    exact_decimals: Rc<Cell<bool>>,
+    fixed_integers: Rc<Cell<bool>>,
+    diagnostics: RefCell<Vec<CompileWarning>>,
+    name_observer: RefCell<Option<NameObserver>>,
+}
+
+/// Function called with each name reference resolved by the compiler
+pub type NameObserver = Rc<dyn Fn(Name, NameOrigin)>;
+
+/// Indicates where a name referenced by compiled code is resolved
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NameOrigin {
+    /// A function parameter, `let` binding, or value enclosed by a lambda
+    Local,
+    /// A value or constant defined in the global scope
+    Global,
+    /// A value imported from another module by `use`
+    Module,
+    /// A builtin function or value
+    Builtin,
+}
+
+/// Default maximum depth of nested groups accepted by the parser
+pub const DEFAULT_MAX_PARSE_NESTING: usize = 256;
+
+/// Pool of shared string values, used when string interning is enabled
+#[derive(Default)]
+struct StringPool {
+    enabled: Cell<bool>,
+    strings: RefCell<HashSet<RcString>>,
 }
 
 #[derive(Clone)]
@@ -87,6 +124,12 @@ impl GlobalScope {
             modules: registry,
             io,
             struct_defs,
+            strings: Rc::new(StringPool::default()),
+            max_parse_nesting: Rc::new(Cell::new(DEFAULT_MAX_PARSE_NESTING)),
+            exact_decimals: Rc::new(Cell::new(false)),
+            fixed_integers: Rc::new(Cell::new(false)),
+            diagnostics: RefCell::new(Vec::new()),
+            name_observer: RefCell::new(None),
         }
     }
 
@@ -106,13 +149,19 @@ impl GlobalScope {
 
     /// Creates a new global scope using the shared data from the given scope.
     pub fn new_using(name: Name, scope: &Scope) -> Scope {
-        Rc::new(GlobalScope::new(
-            name,
-            scope.name_store.clone(),
-            scope.codemap.clone(),
-            scope.modules.clone(),
-            scope.io.clone(),
-            scope.struct_defs.clone()))
+        Rc::new(GlobalScope{
+            strings: scope.strings.clone(),
+            max_parse_nesting: scope.max_parse_nesting.clone(),
+            exact_decimals: scope.exact_decimals.clone(),
+            fixed_integers: scope.fixed_integers.clone(),
+            .. GlobalScope::new(
+                name,
+                scope.name_store.clone(),
+                scope.codemap.clone(),
+                scope.modules.clone(),
+                scope.io.clone(),
+                scope.struct_defs.clone())
+        })
     }
 
     /// Creates a semi-"deep" clone of the `GlobalScope` object.
@@ -129,9 +178,64 @@ impl GlobalScope {
             modules: self.modules.clone(),
             io: self.io.clone(),
             struct_defs: self.struct_defs.clone(),
+            strings: self.strings.clone(),
+            max_parse_nesting: self.max_parse_nesting.clone(),
+            exact_decimals: self.exact_decimals.clone(),
+            fixed_integers: self.fixed_integers.clone(),
+            diagnostics: RefCell::new(self.diagnostics.borrow().clone()),
+            name_observer: RefCell::new(self.name_observer.borrow().clone()),
         })
     }
 
+    /// Creates an independent copy of the `GlobalScope` object.
+    ///
+    /// As with `clone_scope`, all constants, macros, and values are cloned
+    /// into the new scope, while the name store, codemap, module registry,
+    /// and I/O are shared. Unlike `clone_scope`, settings such as exact
+    /// decimals and string interning are copied rather than shared, so that
+    /// changing them in one scope does not affect the other.
+    ///
+    /// Functions and macros created in this scope are rebound to the new
+    /// scope, so that global names within them resolve in the new scope.
+    /// This includes functions contained in lists, structs, and closure
+    /// values, but not those held by foreign values, which are shared
+    /// rather than copied.
+    pub fn fork_scope(&self) -> Scope {
+        let scope = Rc::new(GlobalScope{
+            name: self.name,
+            namespace: self.namespace.clone(),
+            name_store: self.name_store.clone(),
+            codemap: self.codemap.clone(),
+            modules: Rc::new(self.modules.fork()),
+            io: self.io.clone(),
+            struct_defs: self.struct_defs.clone(),
+            strings: Rc::new(StringPool{
+                enabled: self.strings.enabled.clone(),
+                strings: self.strings.strings.clone(),
+            }),
+            max_parse_nesting: Rc::new(Cell::new(self.max_parse_nesting.get())),
+            exact_decimals: Rc::new(Cell::new(self.exact_decimals.get())),
+            fixed_integers: Rc::new(Cell::new(self.fixed_integers.get())),
+            diagnostics: RefCell::new(Vec::new()),
+            name_observer: RefCell::new(self.name_observer.borrow().clone()),
+        });
+
+        {
+            let rebind = Rebinder{from: self, to: &scope};
+            let mut ns = scope.namespace.borrow_mut();
+
+            ns.constants = ns.constants.iter()
+                .map(|(name, v)| (*name, rebind.value_or_clone(v))).collect();
+            ns.values = ns.values.iter()
+                .map(|(name, v)| (*name, rebind.value_or_clone(v))).collect();
+            ns.macros = ns.macros.iter()
+                .map(|(name, l)| (*name, rebind.lambda(l).unwrap_or_else(|| l.clone())))
+                .collect();
+        }
+
+        scope
+    }
+
     /// Adds a named constant value to the scope.
     pub fn add_constant(&self, name: Name, value: Value) {
         self.namespace.borrow_mut().constants.insert(name, value);
@@ -147,6 +251,12 @@ impl GlobalScope {
         self.namespace.borrow_mut().macros.insert(name, lambda);
     }
 
+    /// Removes a macro function from the global scope, returning it
+    /// if it was present.
+    pub fn remove_macro(&self, name: Name) -> Option<Lambda> {
+        self.namespace.borrow_mut().macros.remove(name)
+    }
+
     /// Adds a string representation to the contained `NameStore`.
     pub fn add_name(&self, name: &str) -> Name {
         self.name_store.borrow_mut().add(name)
@@ -196,6 +306,124 @@ impl GlobalScope {
         self.name_store.borrow_mut()
     }
 
+    /// Returns a string value for the given string.
+    ///
+    /// If string interning is enabled, a previously created string of equal
+    /// value is returned, sharing its storage. Otherwise, a new string
+    /// is created.
+    pub fn intern_string(&self, s: &str) -> RcString {
+        if !self.strings.enabled.get() {
+            return RcString::from(s);
+        }
+
+        let mut strings = self.strings.strings.borrow_mut();
+
+        if let Some(s) = strings.get(s) {
+            return s.clone();
+        }
+
+        let s = RcString::from(s);
+        strings.insert(s.clone());
+        s
+    }
+
+    /// Returns whether string interning is enabled.
+    pub fn is_string_interning(&self) -> bool {
+        self.strings.enabled.get()
+    }
+
+    /// Enables or disables string interning for string values created
+    /// by the parser and by bytecode decoding.
+    ///
+    /// This setting is shared with all scopes created using `new_using`.
+    /// Disabling string interning clears the pool of interned strings.
+    pub fn set_string_interning(&self, set: bool) {
+        self.strings.enabled.set(set);
+
+        if !set {
+            self.strings.strings.borrow_mut().clear();
+        }
+    }
+
+    /// Returns the maximum depth of nested groups accepted by the parser.
+    pub fn max_parse_nesting(&self) -> usize {
+        self.max_parse_nesting.get()
+    }
+
+    /// Sets the maximum depth of nested groups accepted by the parser.
+    /// Input nested beyond this depth produces a `NestingTooDeep` parse error.
+    ///
+    /// The limit defaults to `DEFAULT_MAX_PARSE_NESTING` and protects the host
+    /// from overflowing its stack, so it applies whatever the `RestrictConfig`.
+    /// A lower `RestrictConfig::max_syntax_nesting` is reported instead as
+    /// a `RestrictError`.
+    ///
+    /// This setting is shared with all scopes created using `new_using`.
+    pub fn set_max_parse_nesting(&self, n: usize) {
+        self.max_parse_nesting.set(n);
+    }
+
+    /// Returns whether decimal literals are parsed as exact `ratio` values.
+    pub fn exact_decimals(&self) -> bool {
+        self.exact_decimals.get()
+    }
+
+    /// Sets whether decimal literals are parsed as exact `ratio` values.
+    /// When disabled, the default, decimal literals are parsed as `float`
+    /// values, taking the nearest representable value.
+    ///
+    /// This setting is shared with all scopes created using `new_using`.
+    pub fn set_exact_decimals(&self, set: bool) {
+        self.exact_decimals.set(set);
+    }
+
+    /// Returns whether integer arithmetic is limited to the range of `i64`.
+    pub fn fixed_integers(&self) -> bool {
+        self.fixed_integers.get()
+    }
+
+    /// Sets whether integers are limited to the range of `i64`.
+    /// When disabled, the default, integers have arbitrary precision.
+    ///
+    /// When enabled, integer literals outside the range of `i64` are rejected.
+    /// `+`, `-`, `*`, and `//` operate on `i64` values using checked arithmetic,
+    /// and other integer operations check the range of their results.
+    /// An operand or result which does not fit in an `i64`, such as
+    /// a larger integer passed in from Rust code, produces an `Overflow` error.
+    ///
+    /// This setting is shared with all scopes created using `new_using`.
+    pub fn set_fixed_integers(&self, set: bool) {
+        self.fixed_integers.set(set);
+    }
+
+    /// Returns the function called with each name resolved by the compiler
+    /// in this scope, if any.
+    pub fn name_observer(&self) -> Option<NameObserver> {
+        self.name_observer.borrow().clone()
+    }
+
+    /// Sets a function to be called with each name reference resolved by
+    /// the compiler in this scope, along with where the name resolves.
+    /// `None` removes any such function, which is the default.
+    ///
+    /// A name which resolves to no value at compile time is reported as
+    /// `NameOrigin::Global`, as it is looked up in the global scope at runtime.
+    /// Names of macros and operators are not reported.
+    pub fn set_name_observer(&self, f: Option<NameObserver>) {
+        *self.name_observer.borrow_mut() = f;
+    }
+
+    /// Adds a non-fatal diagnostic produced while compiling code in this scope.
+    pub fn add_diagnostic(&self, warning: CompileWarning) {
+        self.diagnostics.borrow_mut().push(warning);
+    }
+
+    /// Removes and returns all diagnostics produced while compiling code
+    /// in this scope.
+    pub fn take_diagnostics(&self) -> Vec<CompileWarning> {
+        self.diagnostics.borrow_mut().drain(..).collect()
+    }
+
     /// Returns the number of contained constants.
     pub fn num_constants(&self) -> usize {
         self.namespace.borrow().constants.len()
@@ -314,6 +542,14 @@ impl GlobalScope {
         self.namespace.borrow().values.get(name).cloned()
     }
 
+    /// Returns the value to which a name resolves within this scope:
+    /// a system function or boolean, a global value, or a constant.
+    pub fn resolve_value(&self, name: Name) -> Option<Value> {
+        MasterScope::get(name)
+            .or_else(|| self.get_value(name))
+            .or_else(|| self.get_constant(name))
+    }
+
     /// Clones all exported values from a scope into this scope.
     pub fn import_all(&self, other: &GlobalScope) -> Vec<Name> {
         self.namespace.borrow_mut()
@@ -517,6 +753,78 @@ impl Namespace {
     }
 }
 
+/// Rebinds functions created within one scope to another scope,
+/// copying the values and code objects which contain them.
+///
+/// Each method returns `None` if the value contains no such functions
+/// and may be shared as it is.
+struct Rebinder<'a> {
+    from: *const GlobalScope,
+    to: &'a Scope,
+}
+
+impl<'a> Rebinder<'a> {
+    fn value_or_clone(&self, v: &Value) -> Value {
+        self.value(v).unwrap_or_else(|| v.clone())
+    }
+
+    fn value(&self, v: &Value) -> Option<Value> {
+        match *v {
+            Value::Lambda(ref l) => self.lambda(l).map(Value::Lambda),
+            Value::List(ref li) => self.values(li)
+                .map(|li| Value::List(RcVec::new(li))),
+            Value::Struct(ref s) => self.values(s.fields())
+                .map(|fields| Value::Struct(Rc::new(
+                    Struct::new(s.def().clone(), fields.into_boxed_slice())))),
+            Value::Quasiquote(ref v, n) =>
+                self.value(v).map(|v| Value::Quasiquote(Box::new(v), n)),
+            Value::Comma(ref v, n) =>
+                self.value(v).map(|v| Value::Comma(Box::new(v), n)),
+            Value::CommaAt(ref v, n) =>
+                self.value(v).map(|v| Value::CommaAt(Box::new(v), n)),
+            Value::Quote(ref v, n) =>
+                self.value(v).map(|v| Value::Quote(Box::new(v), n)),
+            _ => None
+        }
+    }
+
+    fn values(&self, values: &[Value]) -> Option<Vec<Value>> {
+        let mut changed = false;
+
+        let values = values.iter().map(|v| match self.value(v) {
+            Some(v) => { changed = true; v }
+            None => v.clone()
+        }).collect();
+
+        if changed { Some(values) } else { None }
+    }
+
+    fn lambda(&self, lambda: &Lambda) -> Option<Lambda> {
+        let bound_here = ptr::eq(lambda.scope.as_ptr(), self.from);
+        let code = self.code(&lambda.code);
+        let values = lambda.values.as_ref().and_then(|v| self.values(v));
+
+        if !bound_here && code.is_none() && values.is_none() {
+            return None;
+        }
+
+        Some(Lambda{
+            code: code.unwrap_or_else(|| lambda.code.clone()),
+            scope: if bound_here { Rc::downgrade(self.to) } else { lambda.scope.clone() },
+            values: values.map(Rc::from).or_else(|| lambda.values.clone()),
+        })
+    }
+
+    /// Rebinds lambda templates held in the constants of a code object,
+    /// from which closures are built at runtime.
+    fn code(&self, code: &Rc<Code>) -> Option<Rc<Code>> {
+        self.values(&code.consts).map(|consts| Rc::new(Code{
+            consts: consts.into_boxed_slice(),
+            ..(**code).clone()
+        }))
+    }
+}
+
 /// Represents the universal namespace containing built-in symbols
 /// which are available in any context.
 pub enum MasterScope {}
@@ -41,6 +41,23 @@ pub fn parse_raw_string(s: &str, pos: BytePos) -> Result<(String, usize), ParseE
     r.parse_string()
 }
 
+/// Escapes a string as it would appear within a string literal,
+/// without the surrounding quotes.
+///
+/// Escapes are those produced when printing a string value,
+/// all of which are accepted by `unescape_string`.
+pub fn escape_string(s: &str) -> String {
+    let quoted = format!("{:?}", s);
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+/// Processes escape sequences within the contents of a string literal,
+/// given without the surrounding quotes.
+pub fn unescape_string(s: &str) -> Result<String, ParseError> {
+    let mut r = StringReader::new(s, 0, StringType::Normal);
+    r.unescape_string()
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum StringType {
     Single,
@@ -178,6 +195,23 @@ impl<'a> StringReader<'a> {
         Ok((res, self.last_index + 1))
     }
 
+    fn unescape_string(&mut self) -> Result<String, ParseError> {
+        let mut res = String::new();
+
+        while self.chars.clone().next().is_some() {
+            match self.consume_char()? {
+                '\\' => {
+                    if let Some(ch) = self.parse_string_escape()? {
+                        res.push(ch);
+                    }
+                }
+                ch => res.push(ch)
+            }
+        }
+
+        Ok(res)
+    }
+
     fn parse_raw_prefix(&mut self) -> Result<usize, ParseError> {
         let mut n_hash = 0;
 
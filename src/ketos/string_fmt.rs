@@ -108,8 +108,18 @@ impl fmt::Display for FormatError {
 /// Constructs a formatted string using given the format `fmt` and input values.
 pub fn format_string(names: &NameStore, fmt: &str, values: &[Value])
         -> Result<String, ExecError> {
+    format_string_with(names, fmt, values, false)
+}
+
+/// Constructs a formatted string using given the format `fmt` and input values.
+///
+/// If `readably` is `true`, the `~a` directive prints values in the same
+/// manner as `~s`.
+pub fn format_string_with(names: &NameStore, fmt: &str, values: &[Value],
+        readably: bool) -> Result<String, ExecError> {
     let mut buf = String::new();
     let mut fmter = StringFormatter::new(fmt, names, values);
+    fmter.readably = readably;
     fmter.format_string(&mut buf)?;
     fmter.finish()?;
     Ok(buf)
@@ -140,6 +150,8 @@ struct StringFormatter<'fmt, 'names, 'value> {
     last_index: usize,
     /// Byte index in `fmt` at the end of the last char consumed
     end_index: usize,
+    /// Whether `~a` should print values as `~s` does
+    readably: bool,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -262,6 +274,7 @@ impl<'fmt, 'names, 'value> StringFormatter<'fmt, 'names, 'value> {
             arg_index: 0,
             last_index: 0,
             end_index: 0,
+            readably: false,
         }
     }
 
@@ -269,6 +282,7 @@ impl<'fmt, 'names, 'value> StringFormatter<'fmt, 'names, 'value> {
             -> StringFormatter<'fmt, 'names, 'value> {
         StringFormatter{
             full_fmt: self.full_fmt,
+            readably: self.readably,
             .. StringFormatter::new(s, self.names, values)
         }
     }
@@ -498,7 +512,11 @@ impl<'fmt, 'names, 'value> StringFormatter<'fmt, 'names, 'value> {
 
         let arg = self.consume_arg(dir.span)?;
 
-        let s = display_names(self.names, arg).to_string();
+        let s = if self.readably {
+            debug_names(self.names, arg).to_string()
+        } else {
+            display_names(self.names, arg).to_string()
+        };
         pad_str(buf, &s, min_col, col_inc, min_pad, pad_char, dir.at);
         Ok(())
     }
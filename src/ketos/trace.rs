@@ -15,7 +15,7 @@ use crate::value::Value;
 
 /// Represents a series of items, beginning with the outermost context
 /// and culminating with the context in which an error was generated.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Trace {
     items: Vec<TraceItem>,
     expr: Option<Value>,
@@ -32,6 +32,11 @@ impl Trace {
         Trace::new(vec![item], expr)
     }
 
+    /// Inserts an item at the beginning of the trace, as the outermost context.
+    pub fn push_outer(&mut self, item: TraceItem) {
+        self.items.insert(0, item);
+    }
+
     /// Returns the series of traced items.
     pub fn items(&self) -> &[TraceItem] {
         &self.items
@@ -100,6 +105,8 @@ pub enum TraceItem {
     DefineStruct(Name, Name),
     /// Module import declaration; `(scope name, module name)`
     UseModule(Name, Name),
+    /// Evaluation of a named unit of source code
+    Source(Name),
 }
 
 impl NameDisplay for Trace {
@@ -132,6 +139,8 @@ impl NameDisplay for Trace {
                     "  In {}, struct {}", names.get(m), names.get(n))?,
                 UseModule(m, n) => writeln!(f,
                     "  In {}, use {}", names.get(m), names.get(n))?,
+                Source(n) => writeln!(f,
+                    "  In source {}", names.get(n))?,
             }
         }
 
@@ -2,9 +2,11 @@
 
 use std::any::Any;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
 use std::f64::{INFINITY, NEG_INFINITY};
 use std::ffi::{OsStr, OsString};
 use std::fmt;
+use std::hash::{BuildHasher, Hash};
 use std::mem::replace;
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
@@ -169,6 +171,10 @@ impl Value {
 
     /// Tests two values for equality; returns an error if the values cannot be
     /// compared.
+    ///
+    /// Lists which share the same underlying buffer are considered equal
+    /// without comparing their elements. This includes lists which
+    /// contain float `NaN` values.
     pub fn is_equal(&self, rhs: &Value) -> Result<bool, ExecError> {
         let eq = match (self, rhs) {
             (&Value::Unit, &Value::Unit) => true,
@@ -204,7 +210,7 @@ impl Value {
             (&Value::Unit, &Value::List(_)) => false,
             (&Value::List(_), &Value::Unit) => false,
             (&Value::List(ref a), &Value::List(ref b)) =>
-                eq_value_slice(a, b)?,
+                a.ptr_eq(b) || eq_value_slice(a, b)?,
             (&Value::Struct(ref a), &Value::Struct(ref b)) => {
                 if a.def() == b.def() {
                     eq_value_slice(a.fields(), b.fields())?
@@ -259,7 +265,7 @@ impl Value {
             (&Value::Quote(ref a, na), &Value::Quote(ref b, nb)) =>
                 na == nb && a.is_identical(b),
             (&Value::List(ref a), &Value::List(ref b)) =>
-                list_is_identical(a, b),
+                a.ptr_eq(b) || list_is_identical(a, b),
             (&Value::Function(ref a), &Value::Function(ref b)) => a == b,
             (&Value::Lambda(ref a), &Value::Lambda(ref b)) => a == b,
 
@@ -270,6 +276,35 @@ impl Value {
         }
     }
 
+    /// Returns whether this value is the same object as another.
+    ///
+    /// Strings, byte strings, lists, structs, struct definitions, lambdas,
+    /// and foreign values are the same only if they refer to the same shared
+    /// allocation. All other values are the same if they are identical.
+    pub fn is_same(&self, rhs: &Value) -> bool {
+        match (self, rhs) {
+            (&Value::String(ref a), &Value::String(ref b)) => a.ptr_eq(b),
+            (&Value::Bytes(ref a), &Value::Bytes(ref b)) => a.ptr_eq(b),
+            (&Value::List(ref a), &Value::List(ref b)) => a.ptr_eq(b),
+            (&Value::Struct(ref a), &Value::Struct(ref b)) => Rc::ptr_eq(a, b),
+            (&Value::StructDef(ref a), &Value::StructDef(ref b)) => Rc::ptr_eq(a, b),
+            (&Value::Lambda(ref a), &Value::Lambda(ref b)) =>
+                a == b && match (&a.values, &b.values) {
+                    (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                    (None, None) => true,
+                    _ => false
+                },
+            (&Value::Foreign(ref a), &Value::Foreign(ref b)) =>
+                std::ptr::addr_eq(Rc::as_ptr(a), Rc::as_ptr(b)),
+            (&Value::Quasiquote(ref a, na), &Value::Quasiquote(ref b, nb)) |
+            (&Value::Comma(ref a, na), &Value::Comma(ref b, nb)) |
+            (&Value::CommaAt(ref a, na), &Value::CommaAt(ref b, nb)) |
+            (&Value::Quote(ref a, na), &Value::Quote(ref b, nb)) =>
+                na == nb && a.is_same(b),
+            _ => self.is_identical(rhs)
+        }
+    }
+
     /// Replaces the value with `Unit` and returns the old value.
     pub fn take(&mut self) -> Value {
         replace(self, Value::Unit)
@@ -356,6 +391,18 @@ impl Value {
         }
     }
 
+    /// Returns a deep copy of the value.
+    ///
+    /// Lists, strings, byte strings, and structs are copied recursively.
+    /// Foreign values are copied using `ForeignValue::deep_copy`.
+    /// Other values are immutable and are shared with the original.
+    ///
+    /// Returns an error if a foreign value is reached through a reference
+    /// cycle and its `deep_copy` implementation does not support cycles.
+    pub fn deep_copy(&self) -> Result<Value, ExecError> {
+        DeepCopier::new().copy(self)
+    }
+
     /// Returns a string describing the type of the value.
     pub fn type_name(&self) -> &'static str {
         match *self {
@@ -483,10 +530,119 @@ pub trait ForeignValue: Any + fmt::Debug {
     /// The result will be used in applying memory restrictions to executing code.
     /// The result **MUST NOT** change for the lifetime of the value.
     fn size(&self) -> usize { 2 }
+
+    /// Adds a value to this value, as the left operand of `+`.
+    ///
+    /// `None` indicates that the operation is not supported, in which case
+    /// a type error is raised. The default implementation returns `None`.
+    fn add_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> { None }
+
+    /// Subtracts a value from this value, as the left operand of `-`.
+    ///
+    /// The default implementation returns `None`.
+    fn sub_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> { None }
+
+    /// Multiplies this value by a value, as the left operand of `*`.
+    ///
+    /// The default implementation returns `None`.
+    fn mul_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> { None }
+
+    /// Divides this value by a value, as the left operand of `/`.
+    ///
+    /// The default implementation returns `None`.
+    fn div_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> { None }
+
+    /// Returns a deep copy of the value, for use by the `copy` function.
+    ///
+    /// Any values held by the foreign value should be copied using `copier`.
+    /// A value which may contain a reference to itself should create its
+    /// copy and pass it to `DeepCopier::set_copy` before copying its
+    /// contents; otherwise, copying a reference cycle returns an error.
+    ///
+    /// The default implementation returns `Ok(None)`, in which case the copy
+    /// shares the original value.
+    fn deep_copy(&self, copier: &mut DeepCopier) -> Result<Option<Value>, ExecError> {
+        Ok(None)
+    }
 }
 
 impl_any_cast!{ ForeignValue }
 
+/// Produces deep copies of values; see `Value::deep_copy`.
+///
+/// Each foreign value is copied at most once by a `DeepCopier`; further
+/// references to the same foreign value produce the same copy.
+/// If a foreign value is reached again while it is being copied,
+/// through a reference cycle, the copy given to `set_copy` is used at that
+/// point. If no copy was given, the cycle cannot be copied and
+/// `ExecError::ReferenceCycle` is returned.
+///
+/// A `DeepCopier` should be used only for the duration of a single copy.
+#[derive(Default)]
+pub struct DeepCopier {
+    foreign: Vec<(*const (), Option<Value>)>,
+    /// Indices into `foreign` of values whose copy is in progress
+    in_progress: Vec<usize>,
+}
+
+impl DeepCopier {
+    /// Creates a new `DeepCopier`.
+    pub fn new() -> DeepCopier {
+        DeepCopier::default()
+    }
+
+    /// Returns a deep copy of the given value.
+    pub fn copy(&mut self, v: &Value) -> Result<Value, ExecError> {
+        Ok(match *v {
+            Value::Struct(ref s) => Value::Struct(Rc::new(Struct::new(
+                s.def().clone(),
+                s.fields().iter().map(|v| self.copy(v)).collect::<Result<_, _>>()?))),
+            Value::String(ref s) => Value::String(RcString::new(s.to_string())),
+            Value::Bytes(ref b) => Value::Bytes(Bytes::new(b.to_vec())),
+            Value::Quasiquote(ref v, n) => Value::Quasiquote(Box::new(self.copy(v)?), n),
+            Value::Comma(ref v, n) => Value::Comma(Box::new(self.copy(v)?), n),
+            Value::CommaAt(ref v, n) => Value::CommaAt(Box::new(self.copy(v)?), n),
+            Value::Quote(ref v, n) => Value::Quote(Box::new(self.copy(v)?), n),
+            Value::List(ref li) => Value::List(RcVec::new(
+                li.iter().map(|v| self.copy(v)).collect::<Result<_, _>>()?)),
+            Value::Foreign(ref a) => self.copy_foreign(a)?,
+            ref v => v.clone()
+        })
+    }
+
+    /// Sets the copy of the foreign value currently being copied.
+    ///
+    /// Called from `ForeignValue::deep_copy` before copying the contents
+    /// of a value, so that references to the value reached through
+    /// a reference cycle resolve to `copy`.
+    pub fn set_copy(&mut self, copy: &Value) {
+        if let Some(&index) = self.in_progress.last() {
+            self.foreign[index].1 = Some(copy.clone());
+        }
+    }
+
+    fn copy_foreign(&mut self, a: &Rc<dyn ForeignValue>) -> Result<Value, ExecError> {
+        let ptr = Rc::as_ptr(a) as *const ();
+
+        if let Some((_, copy)) = self.foreign.iter().find(|(p, _)| *p == ptr) {
+            // `None` indicates a copy in progress; i.e. a reference cycle
+            return copy.clone().ok_or(ExecError::ReferenceCycle);
+        }
+
+        let index = self.foreign.len();
+        self.foreign.push((ptr, None));
+        self.in_progress.push(index);
+
+        let res = a.deep_copy(self);
+        self.in_progress.pop();
+
+        let copy = res?.unwrap_or_else(|| Value::Foreign(a.clone()));
+
+        self.foreign[index].1 = Some(copy.clone());
+        Ok(copy)
+    }
+}
+
 /// Represents a foreign value that contains a callable function or closure
 pub struct ForeignFn<F> {
     name: Name,
@@ -611,6 +767,8 @@ impl NameDebug for Value {
             } else {
                 write!(f, "{}", r)
             },
+            // Debug formatting escapes quotes, backslashes, and control
+            // characters using escapes accepted by the lexer.
             Value::Char(ch) => write!(f, "#{:?}", ch),
             Value::String(ref s) => write!(f, "{:?}", s),
             Value::Bytes(ref s) => {
@@ -1088,6 +1246,29 @@ impl<T: FromValue> FromValue for Vec<T> {
     }
 }
 
+impl<K, V, S> FromValue for HashMap<K, V, S>
+        where K: FromValue + Eq + Hash, V: FromValue, S: BuildHasher + Default {
+    fn from_value(v: Value) -> Result<HashMap<K, V, S>, ExecError> {
+        match v {
+            Value::Unit => Ok(HashMap::default()),
+            Value::List(li) => li.into_vec().into_iter()
+                .map(<(K, V)>::from_value).collect(),
+            ref v => Err(ExecError::expected("list", v))
+        }
+    }
+}
+
+impl<K: FromValue + Ord, V: FromValue> FromValue for BTreeMap<K, V> {
+    fn from_value(v: Value) -> Result<BTreeMap<K, V>, ExecError> {
+        match v {
+            Value::Unit => Ok(BTreeMap::new()),
+            Value::List(li) => li.into_vec().into_iter()
+                .map(<(K, V)>::from_value).collect(),
+            ref v => Err(ExecError::expected("list", v))
+        }
+    }
+}
+
 impl<T: FromValue> FromValue for Option<T> {
     fn from_value(v: Value) -> Result<Option<T>, ExecError> {
         match v {
@@ -1163,6 +1344,18 @@ impl<T: Into<Value>> From<Vec<T>> for Value {
     }
 }
 
+impl<K: Into<Value>, V: Into<Value>, S> From<HashMap<K, V, S>> for Value {
+    fn from(m: HashMap<K, V, S>) -> Value {
+        m.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
+impl<K: Into<Value>, V: Into<Value>> From<BTreeMap<K, V>> for Value {
+    fn from(m: BTreeMap<K, V>) -> Value {
+        m.into_iter().collect::<Vec<_>>().into()
+    }
+}
+
 impl<'a, T: Clone + Into<Value>> From<&'a [T]> for Value {
     fn from(v: &[T]) -> Value {
         if v.is_empty() {
@@ -3,6 +3,7 @@
 //! See [`value_encode`](../value_encode/index.html) module documentation
 //! for details.
 
+use std::collections::BTreeSet;
 use std::fmt;
 use std::slice::Iter;
 
@@ -10,10 +11,12 @@ use serde::de::{
     self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, IntoDeserializer, MapAccess,
     SeqAccess, VariantAccess, Visitor,
 };
+use serde::de::value::SeqDeserializer;
 
 use crate::error::Error;
 use crate::exec::{panic, ExecError};
-use crate::name::Name;
+use crate::integer::Integer;
+use crate::name::{display_names, Name};
 use crate::scope::Scope;
 use crate::value::{FromValueRef, Value};
 
@@ -25,12 +28,64 @@ pub fn decode_value<'de, T: Deserialize<'de>>(
     scope: &'de Scope,
     value: &'de Value,
 ) -> Result<T, Error> {
-    let mut de = VDeserializer::new(scope, value);
+    decode_value_with(scope, value, &DecodeOptions::default())
+}
+
+/// Decodes a Rust type from a `Value`, using the given options.
+pub fn decode_value_with<'de, T: Deserialize<'de>>(
+    scope: &'de Scope,
+    value: &'de Value,
+    options: &DecodeOptions,
+) -> Result<T, Error> {
+    let mut de = VDeserializer::new(scope, value, options);
     let v = T::deserialize(&mut de)?;
     de.finish();
     Ok(v)
 }
 
+/// Decodes a Rust value from a `Value`, using the given `DeserializeSeed`.
+///
+/// This allows state, such as an arena or a registry, to be passed
+/// into decoding.
+pub fn decode_value_seed<'de, S: DeserializeSeed<'de>>(
+    scope: &'de Scope,
+    value: &'de Value,
+    seed: S,
+) -> Result<S::Value, Error> {
+    let mut de = VDeserializer::new(scope, value, &DecodeOptions::default());
+    let v = seed.deserialize(&mut de)?;
+    de.finish();
+    Ok(v)
+}
+
+/// Options controlling how values are decoded.
+///
+/// The default options decode strictly.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DecodeOptions {
+    /// Whether a string may be decoded from an integer, float, or bool value,
+    /// using its display representation.
+    pub lenient: bool,
+    /// How a key appearing more than once in a map or struct is handled
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// Whether a tuple or tuple struct may be decoded from a struct value,
+    /// taking its field values as a positional sequence.
+    /// Fields are taken in the order in which `struct->map` lists them.
+    pub positional_structs: bool,
+}
+
+/// Handling of a key which appears more than once in a decoded map or struct
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum DuplicateKeyPolicy {
+    /// The first entry for a key is used; later entries are ignored.
+    FirstWins,
+    /// The last entry for a key is used; earlier entries are ignored.
+    LastWins,
+    /// A duplicate key is an error. This is the default.
+    #[default]
+    Error,
+}
+
 impl de::Error for ExecError {
     fn custom<T: fmt::Display>(msg: T) -> ExecError {
         panic(msg.to_string())
@@ -39,6 +94,7 @@ impl de::Error for ExecError {
 
 struct VDeserializer<'de> {
     scope: &'de Scope,
+    options: DecodeOptions,
     state: Vec<DeserializeState<'de>>,
 }
 
@@ -49,9 +105,10 @@ enum DeserializeState<'de> {
 }
 
 impl<'de> VDeserializer<'de> {
-    fn new(scope: &'de Scope, value: &'de Value) -> VDeserializer<'de> {
+    fn new(scope: &'de Scope, value: &'de Value, options: &DecodeOptions) -> VDeserializer<'de> {
         VDeserializer {
             scope,
+            options: *options,
             state: vec![DeserializeState::Value(value)],
         }
     }
@@ -91,6 +148,14 @@ impl<'de> VDeserializer<'de> {
         }
     }
 
+    /// Returns the remaining elements of the innermost sequence.
+    fn seq_slice(&self) -> Result<&'de [Value], ExecError> {
+        match self.state.last() {
+            Some(DeserializeState::Seq(iter)) => Ok(iter.as_slice()),
+            _ => Err(panic("not a sequence")),
+        }
+    }
+
     fn read_name(&mut self) -> Result<Name, ExecError> {
         match *self.next_value()? {
             Value::Name(name) => Ok(name),
@@ -140,6 +205,41 @@ impl<'de> VDeserializer<'de> {
         self.enter_seq()
     }
 
+    /// Returns whether positional structs are enabled
+    /// and the next value is a struct value.
+    fn at_positional_struct(&self) -> Result<bool, ExecError> {
+        Ok(self.options.positional_structs &&
+            matches!(*self.peek_value()?, Value::Struct(_)))
+    }
+
+    /// Visits the field values of the next value, a struct, as a sequence.
+    fn visit_positional_struct<V: Visitor<'de>>(&mut self, visitor: V)
+            -> Result<V::Value, ExecError> {
+        let n = match *self.next_value()? {
+            Value::Struct(ref s) => {
+                self.state.push(DeserializeState::Seq(s.fields().iter()));
+                s.fields().len()
+            }
+            ref v => return Err(ExecError::expected("struct", v)),
+        };
+
+        let v = visitor.visit_seq(SeqVisitor { de: self, n })?;
+        self.leave_seq()?;
+        Ok(v)
+    }
+
+    /// Returns whether a list is an enum value in the form produced by
+    /// `encode_value`, `(Enum Variant (values ...))`, rather than
+    /// the short form `(Variant values ...)`.
+    fn is_encoded_variant(&self, li: &[Value], name: &str, variants: &[&str]) -> bool {
+        match *li {
+            [Value::Name(enum_name), Value::Name(variant), _] =>
+                self.scope.with_name(enum_name, |n| n == name) &&
+                    self.scope.with_name(variant, |v| variants.contains(&v)),
+            _ => false,
+        }
+    }
+
     fn enter_fields(&mut self) -> Result<usize, ExecError> {
         let n = self.enter_seq()?;
 
@@ -235,6 +335,9 @@ impl<'a, 'de: 'a> Deserializer<'de> for &'a mut VDeserializer<'de> {
         let v: Result<String, _> = match *self.next_value()? {
             Value::String(ref string) => Ok(string.to_string()),
             Value::Keyword(name) => Ok(self.scope.with_name(name, |s| s.to_string())),
+            ref v @ Value::Integer(_) | ref v @ Value::Float(_) | ref v @ Value::Bool(_)
+                    if self.options.lenient =>
+                Ok(display_names(&self.scope.borrow_names(), v).to_string()),
             ref v => Err(ExecError::expected("keyword or string", v)),
         };
         visitor.visit_string(v?)
@@ -264,19 +367,45 @@ impl<'a, 'de: 'a> Deserializer<'de> for &'a mut VDeserializer<'de> {
 
     fn deserialize_tuple<V: Visitor<'de>>(
         self,
-        _len: usize,
+        len: usize,
         visitor: V,
     ) -> Result<V::Value, ExecError> {
+        // A ratio decodes as a pair of its numerator and denominator
+        if let Value::Ratio(ref r) = *self.peek_value()? {
+            if len == 2 {
+                self.next_value()?;
+                let numer = r.numer().to_i64().ok_or(ExecError::Overflow)?;
+                let denom = r.denom().to_i64().ok_or(ExecError::Overflow)?;
+
+                let mut seq = SeqDeserializer::new(vec![numer, denom].into_iter());
+                let v = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                return Ok(v);
+            }
+        }
+
+        if self.at_positional_struct()? {
+            return self.visit_positional_struct(visitor);
+        }
+
         self.deserialize_seq(visitor)
     }
 
     fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ExecError> {
-        let n = self.enter_seq()?;
-        let v = visitor.visit_map(MapVisitor {
-            de: self,
-            n,
-            is_struct: false,
-        })?;
+        // A list beginning with a keyword, e.g. `(:a 1 :b 2)`,
+        // alternates keys and values, as do the fields of a struct.
+        let alternating = match *self.peek_value()? {
+            Value::List(ref li) => matches!(li.first(), Some(Value::Keyword(_))),
+            _ => false,
+        };
+
+        let n = if alternating {
+            self.enter_fields()?
+        } else {
+            self.enter_seq()?
+        };
+
+        let v = visitor.visit_map(MapVisitor::new(self, n, alternating)?)?;
         self.leave_seq()?;
         Ok(v)
     }
@@ -295,6 +424,10 @@ impl<'a, 'de: 'a> Deserializer<'de> for &'a mut VDeserializer<'de> {
         _len: usize,
         visitor: V,
     ) -> Result<V::Value, ExecError> {
+        if self.at_positional_struct()? {
+            return self.visit_positional_struct(visitor);
+        }
+
         let n = self.enter_tuple_struct(name)?;
         let v = visitor.visit_seq(SeqVisitor { de: self, n })?;
         self.leave_seq()?;
@@ -321,11 +454,7 @@ impl<'a, 'de: 'a> Deserializer<'de> for &'a mut VDeserializer<'de> {
         visitor: V,
     ) -> Result<V::Value, ExecError> {
         let n = self.enter_struct(name)?;
-        let v = visitor.visit_map(MapVisitor {
-            de: self,
-            n,
-            is_struct: true,
-        })?;
+        let v = visitor.visit_map(MapVisitor::new(self, n, true)?)?;
         self.leave_seq()?;
         self.leave_seq()?;
         Ok(v)
@@ -342,18 +471,28 @@ impl<'a, 'de: 'a> Deserializer<'de> for &'a mut VDeserializer<'de> {
 
     fn deserialize_enum<V: Visitor<'de>>(
         self,
-        _name: &'static str,
-        _variants: &'static [&'static str],
+        name: &'static str,
+        variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, ExecError> {
-        let v = match self.peek_value()? {
-            Value::List(_) => visitor.visit_enum(Variant(&mut *self)),
+        let v = match *self.peek_value()? {
+            Value::List(ref li) => {
+                let form = if self.is_encoded_variant(li, name, variants) {
+                    VariantForm::Encoded
+                } else {
+                    VariantForm::Short
+                };
+                visitor.visit_enum(Variant(&mut *self, form))
+            }
             _ => visitor.visit_enum(UnitVariant(&mut *self)),
         }?;
         Ok(v)
     }
 
     fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ExecError> {
+        // A compound value, such as a list or struct, occupies a single slot
+        // in the enclosing sequence; it is skipped in whole without
+        // entering it, leaving the state stack unchanged.
         self.next_value()?;
         visitor.visit_unit()
     }
@@ -372,17 +511,32 @@ impl<'a, 'de: 'a> EnumAccess<'de> for UnitVariant<'a, 'de> {
             .0
             .scope
             .with_name(name, |n| seed.deserialize(n.into_deserializer()))?;
-        Ok((val, Variant(self.0)))
+        Ok((val, Variant(self.0, VariantForm::Bare)))
     }
 }
 
-struct Variant<'a, 'de>(&'a mut VDeserializer<'de>);
+/// Form in which an enum value is written
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum VariantForm {
+    /// `Variant`, for a unit variant
+    Bare,
+    /// `(Variant values ...)`
+    Short,
+    /// `(Enum Variant (values ...))`, as produced by `encode_value`
+    Encoded,
+}
+
+struct Variant<'a, 'de>(&'a mut VDeserializer<'de>, VariantForm);
 impl<'a, 'de: 'a> EnumAccess<'de> for Variant<'a, 'de> {
     type Error = ExecError;
     type Variant = Self;
 
     fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), ExecError> {
         self.0.enter_seq()?;
+        if self.1 == VariantForm::Encoded {
+            // Enum name, checked by `is_encoded_variant`
+            self.0.read_name()?;
+        }
         let name = self.0.read_name()?;
         let val = self
             .0
@@ -396,20 +550,39 @@ impl<'a, 'de: 'a> VariantAccess<'de> for Variant<'a, 'de> {
     type Error = ExecError;
 
     fn unit_variant(self) -> Result<(), Self::Error> {
-        Ok(())
+        match self.1 {
+            VariantForm::Bare => Ok(()),
+            VariantForm::Short => self.0.leave_seq(),
+            VariantForm::Encoded => {
+                self.0.next_value().and_then(<()>::from_value_ref)?;
+                self.0.leave_seq()
+            }
+        }
     }
 
     fn newtype_variant_seed<T: DeserializeSeed<'de>>(
         self,
         seed: T,
     ) -> Result<T::Value, Self::Error> {
+        if self.1 == VariantForm::Encoded {
+            self.0.enter_seq()?;
+        }
         let v = seed.deserialize(&mut *self.0)?;
+        if self.1 == VariantForm::Encoded {
+            self.0.leave_seq()?;
+        }
         self.0.leave_seq()?;
         Ok(v)
     }
 
     fn tuple_variant<V: Visitor<'de>>(self, n: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.1 == VariantForm::Encoded {
+            self.0.enter_seq()?;
+        }
         let v = visitor.visit_seq(SeqVisitor { de: self.0, n })?;
+        if self.1 == VariantForm::Encoded {
+            self.0.leave_seq()?;
+        }
         self.0.leave_seq()?;
         Ok(v)
     }
@@ -420,11 +593,9 @@ impl<'a, 'de: 'a> VariantAccess<'de> for Variant<'a, 'de> {
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
         let n = self.0.enter_fields()?;
-        let v = visitor.visit_map(MapVisitor {
-            de: self.0,
-            n,
-            is_struct: true,
-        })?;
+        let v = visitor.visit_map(MapVisitor::new(self.0, n, true)?)?;
+        // Leave the field list, then the enclosing `(Variant ...)` list
+        self.0.leave_seq()?;
         self.0.leave_seq()?;
         Ok(v)
     }
@@ -459,7 +630,125 @@ impl<'a, 'de: 'a> SeqAccess<'de> for SeqVisitor<'a, 'de> {
 struct MapVisitor<'a, 'de: 'a> {
     de: &'a mut VDeserializer<'de>,
     n: usize,
+    /// Whether keys and values alternate, as in struct fields,
+    /// rather than being paired in lists
     is_struct: bool,
+    /// Whether each entry, in order, is skipped as a duplicate
+    skip: Vec<bool>,
+    index: usize,
+}
+
+impl<'a, 'de: 'a> MapVisitor<'a, 'de> {
+    /// Creates a visitor over the `n` entries of the innermost sequence,
+    /// applying the duplicate key policy of the deserializer.
+    fn new(de: &'a mut VDeserializer<'de>, n: usize, is_struct: bool)
+            -> Result<MapVisitor<'a, 'de>, ExecError> {
+        let slice = de.seq_slice()?;
+
+        let keys = (0..n).map(|i| {
+            let key = if is_struct {
+                slice.get(i * 2)
+            } else {
+                match slice.get(i) {
+                    Some(Value::List(li)) => li.first(),
+                    Some(_) => None,
+                    None => return Err(panic("unexpected end of sequence")),
+                }
+            };
+
+            if is_struct && key.is_none() {
+                Err(panic("unexpected end of sequence"))
+            } else {
+                Ok(key)
+            }
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        let skip = match de.options.duplicate_keys {
+            DuplicateKeyPolicy::Error => {
+                let repeated = mark_repeated(keys.iter().cloned().enumerate(), n);
+
+                if let Some(i) = repeated.iter().position(|&r| r) {
+                    return Err(duplicate_key(keys[i], is_struct));
+                }
+                repeated
+            }
+            DuplicateKeyPolicy::FirstWins =>
+                mark_repeated(keys.iter().cloned().enumerate(), n),
+            DuplicateKeyPolicy::LastWins =>
+                mark_repeated(keys.iter().cloned().enumerate().rev(), n),
+        };
+
+        Ok(MapVisitor {
+            de,
+            n,
+            is_struct,
+            skip,
+            index: 0,
+        })
+    }
+}
+
+/// Ordered form of a map key, used to find duplicate keys without
+/// comparing each key to every other
+#[derive(Eq, Ord, PartialEq, PartialOrd)]
+enum MapKey<'a> {
+    Bool(bool),
+    Bytes(&'a [u8]),
+    Char(char),
+    Integer(&'a Integer),
+    Keyword(Name),
+    Name(Name),
+    String(&'a str),
+}
+
+impl<'a> MapKey<'a> {
+    fn new(v: &'a Value) -> Option<MapKey<'a>> {
+        match *v {
+            Value::Bool(b) => Some(MapKey::Bool(b)),
+            Value::Bytes(ref b) => Some(MapKey::Bytes(b)),
+            Value::Char(c) => Some(MapKey::Char(c)),
+            Value::Integer(ref i) => Some(MapKey::Integer(i)),
+            Value::Keyword(name) => Some(MapKey::Keyword(name)),
+            Value::Name(name) => Some(MapKey::Name(name)),
+            Value::String(ref s) => Some(MapKey::String(s)),
+            _ => None,
+        }
+    }
+}
+
+/// Marks each of `n` entries whose key is identical to that of an entry
+/// visited before it. Entries are visited in the order of `keys`.
+///
+/// Keys without a `MapKey` form, such as lists and floats, are rare;
+/// these are compared with `is_identical` against one another.
+fn mark_repeated<'a, I>(keys: I, n: usize) -> Vec<bool>
+        where I: Iterator<Item=(usize, Option<&'a Value>)> {
+    let mut seen = BTreeSet::new();
+    let mut others = Vec::<&Value>::new();
+    let mut repeated = vec![false; n];
+
+    for (i, key) in keys {
+        if let Some(key) = key {
+            repeated[i] = match MapKey::new(key) {
+                Some(k) => !seen.insert(k),
+                None if others.iter().any(|o| o.is_identical(key)) => true,
+                None => {
+                    others.push(key);
+                    false
+                }
+            };
+        }
+    }
+
+    repeated
+}
+
+fn duplicate_key(key: Option<&Value>, is_struct: bool) -> ExecError {
+    match key {
+        Some(&Value::Keyword(name)) | Some(&Value::Name(name)) if is_struct =>
+            ExecError::DuplicateField(name),
+        _ => panic("duplicate key in map"),
+    }
 }
 
 impl<'a, 'de: 'a> MapAccess<'de> for MapVisitor<'a, 'de> {
@@ -469,14 +758,37 @@ impl<'a, 'de: 'a> MapAccess<'de> for MapVisitor<'a, 'de> {
         &mut self,
         seed: K,
     ) -> Result<Option<K::Value>, ExecError> {
-        if self.n == 0 {
-            return Ok(None);
-        }
-        self.n -= 1;
-        if !self.is_struct {
-            self.de.enter_seq()?;
+        loop {
+            if self.n == 0 {
+                return Ok(None);
+            }
+            self.n -= 1;
+            self.index += 1;
+
+            if self.skip[self.index - 1] {
+                // A key/value pair occupies a single slot in a map;
+                // in a struct, the key and value are separate slots.
+                self.de.next_value()?;
+                if self.is_struct {
+                    self.de.next_value()?;
+                }
+                continue;
+            }
+
+            if !self.is_struct {
+                self.de.enter_seq()?;
+            }
+
+            // A keyword or name key is decoded from its name
+            return match *self.de.peek_value()? {
+                Value::Keyword(name) | Value::Name(name) => {
+                    self.de.next_value()?;
+                    self.de.scope.with_name(name,
+                        |n| seed.deserialize(n.into_deserializer())).map(Some)
+                }
+                _ => seed.deserialize(&mut *self.de).map(Some),
+            };
         }
-        seed.deserialize(&mut *self.de).map(Some)
     }
 
     fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, ExecError> {
@@ -2,6 +2,7 @@
 
 extern crate ketos;
 
+use std::collections::{BTreeMap, HashMap};
 use std::ffi::{OsStr, OsString};
 use std::path::{Path, PathBuf};
 
@@ -28,12 +29,29 @@ fn test_from_value() {
     assert_eq!(from::<i32>(into(123)).unwrap(), 123);
     assert_eq!(from::<String>(into("foo")).unwrap(), "foo");
 
-    assert_eq!(from::<Vec<i32>>(Value::Unit).unwrap(), vec![]);
+    assert_eq!(from::<Vec<i32>>(Value::Unit).unwrap(), Vec::<i32>::new());
     assert_eq!(from::<Vec<i32>>(into(vec![1, 2, 3])).unwrap(), vec![1, 2, 3]);
 
     assert_eq!(from::<(String, i32)>(into(("foo", 1))).unwrap(),
         ("foo".to_owned(), 1));
 
+    let v: Vec<i64> = vec![1, -2, 3];
+    assert_eq!(from::<Vec<i64>>(into(v.clone())).unwrap(), v);
+
+    let mut m = HashMap::new();
+    m.insert("a".to_owned(), 1_i64);
+    m.insert("b".to_owned(), 2_i64);
+    assert_eq!(from::<HashMap<String, i64>>(into(m.clone())).unwrap(), m);
+    assert_eq!(from::<HashMap<String, i64>>(Value::Unit).unwrap(), HashMap::new());
+
+    let mut m = BTreeMap::new();
+    m.insert(1, "a".to_owned());
+    m.insert(2, "b".to_owned());
+    assert_eq!(from::<BTreeMap<i32, String>>(into(m.clone())).unwrap(), m);
+
+    assert_matches!(from::<HashMap<String, i64>>(into(vec![1, 2])),
+        Err(ExecError::TypeError{..}));
+
     assert_eq!(from::<PathBuf>(into("foo")).unwrap(), PathBuf::from("foo"));
     assert_eq!(from::<PathBuf>(into(Path::new("foo"))).unwrap(), PathBuf::from("foo"));
     assert_eq!(from::<OsString>(into("foo")).unwrap(), OsString::from("foo"));
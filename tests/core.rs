@@ -2,7 +2,8 @@
 
 extern crate ketos;
 
-use ketos::{CompileError, Error, ExecError, Interpreter, FromValue, Value};
+use ketos::{Builder, CompileError, Error, ExecError, ExecState, Interpreter,
+    FromValue, FromValueRef, RestrictConfig, RestrictError, Value};
 
 fn eval(s: &str) -> Result<String, Error> {
     let interp = Interpreter::new();
@@ -171,6 +172,39 @@ fn test_quasiquote() {
     assert_eq!(eval("```(foo ,,,1)").unwrap(), "``(foo 1)");
 }
 
+#[test]
+fn test_quote_keyword() {
+    fn is_keyword(interp: &Interpreter, v: &Value, s: &str) -> bool {
+        match *v {
+            Value::Keyword(name) => interp.scope().with_name(name, |n| n == s),
+            _ => false
+        }
+    }
+
+    fn is_name(interp: &Interpreter, v: &Value, s: &str) -> bool {
+        match *v {
+            Value::Name(name) => interp.scope().with_name(name, |n| n == s),
+            _ => false
+        }
+    }
+
+    let interp = Interpreter::new();
+
+    for code in &["'(:a b :c d)", "`(:a b ,:c ,'d)", "(list ':a 'b ':c 'd)"] {
+        let v = interp.run_single_expr(code, None).unwrap();
+        let li = <&[Value]>::from_value_ref(&v).unwrap();
+
+        assert_eq!(li.len(), 4);
+        assert!(is_keyword(&interp, &li[0], "a"), "{}", code);
+        assert!(is_name(&interp, &li[1], "b"), "{}", code);
+        assert!(is_keyword(&interp, &li[2], "c"), "{}", code);
+        assert!(is_name(&interp, &li[3], "d"), "{}", code);
+    }
+
+    assert_eq!(eval("'(:a b)").unwrap(), "(:a b)");
+    assert_eq!(eval("``(:a ,b)").unwrap(), "`(:a ,b)");
+}
+
 #[test]
 fn test_struct() {
     assert_eq!(run("
@@ -260,6 +294,104 @@ fn test_struct() {
         ["foo", "true"]);
 }
 
+#[test]
+fn test_update_in() {
+    assert_eq!(run("
+        (use struct (update-in))
+        (struct inner ((n integer)))
+        (struct outer ((name string) (inner struct)))
+        (define o (new outer :name \"o\" :inner (new inner :n 1)))
+        (update-in o '(:inner :n) (lambda (n) (+ n 1)))
+        (update-in o '(:name) (lambda (s) (concat s \"!\")))
+        o
+        ").unwrap(),
+        ["()", "inner", "outer", "o",
+            r#"outer { name: "o", inner: inner { n: 2 } }"#,
+            r#"outer { name: "o!", inner: inner { n: 1 } }"#,
+            r#"outer { name: "o", inner: inner { n: 1 } }"#]);
+
+    assert_matches!(run("
+        (use struct (update-in))
+        (struct inner ((n integer)))
+        (struct outer ((inner struct)))
+        (update-in (new outer :inner (new inner :n 1)) '(:inner :m) (lambda (n) n))
+        ").unwrap_err(),
+        Error::ExecError(ExecError::FieldError{..}));
+}
+
+#[test]
+fn test_struct_map() {
+    assert_eq!(run("
+        (use struct (map->struct struct->map))
+        (struct point ((x integer) (y integer)))
+        (define p (new point :x 1 :y 2))
+        (struct->map p)
+        (= (map->struct point (struct->map p)) p)
+        (map->struct point '((:y 4) (:x 3) (:z 5)) true)
+        ").unwrap(),
+        ["()", "point", "p", "((:x 1) (:y 2))", "true", "point { x: 3, y: 4 }"]);
+
+    assert_matches!(run("
+        (use struct (map->struct))
+        (struct point ((x integer) (y integer)))
+        (map->struct point '((:x 1)))
+        ").unwrap_err(),
+        Error::ExecError(ExecError::MissingField{..}));
+
+    assert_matches!(run("
+        (use struct (map->struct))
+        (struct point ((x integer) (y integer)))
+        (map->struct point '((:x 1) (:y 2) (:z 3)))
+        ").unwrap_err(),
+        Error::ExecError(ExecError::FieldError{..}));
+
+    assert_matches!(run("
+        (use struct (map->struct))
+        (struct point ((x integer) (y integer)))
+        (map->struct point '((:x 1) (:x 2)))
+        ").unwrap_err(),
+        Error::ExecError(ExecError::DuplicateField(_)));
+}
+
+#[test]
+fn test_struct_map_print_round_trip() {
+    use ketos::pretty::pretty_print;
+
+    let interp = Interpreter::new();
+
+    let map = interp.run_code(r#"
+        (use struct (struct->map))
+        (struct entry ((label string) (tags list) (size integer) (share ratio)))
+        (struct->map (new entry :label "a \"b\"" :tags '(x :y "z") :size 3 :share 1/2))
+        "#, None).unwrap();
+
+    let printed = interp.format_value(&map);
+    assert_eq!(printed, r#"((:label "a \"b\"") (:tags (x :y "z")) (:size 3) (:share 1/2))"#);
+
+    let mut pretty = String::new();
+    pretty_print(&mut pretty, &interp.scope().borrow_names(), &map, 0).unwrap();
+
+    for text in &[printed, pretty] {
+        let read = interp.run_code(&format!("'{}", text), None).unwrap();
+        assert!(read.is_identical(&map), "{} was not read back", text);
+    }
+}
+
+#[test]
+fn test_struct_field_order_independent() {
+    assert_eq!(run(r#"
+        (use struct (map->struct struct->map))
+        (struct entry ((label string) (size integer)))
+        (define a (new entry :label "x" :size 1))
+        (define b (new entry :size 1 :label "x"))
+        (define c (map->struct entry '((:size 1) (:label "x"))))
+        (list (= a b) (= a c) (= b c))
+        (= (struct->map a) (struct->map c))
+        (= a (new entry :size 2 :label "x"))
+        "#).unwrap(),
+        ["()", "entry", "a", "b", "c", "(true true true)", "true", "false"]);
+}
+
 #[test]
 fn test_format() {
     assert_eq!(eval_str(r#"(format "foo")"#).unwrap(), "foo");
@@ -608,11 +740,89 @@ fn test_rem() {
 
     assert_matches!(eval("(rem 1 0)").unwrap_err(),
         Error::ExecError(ExecError::DivideByZero));
-    assert_eq!(eval("(rem 1.0 0.0)").unwrap(), "NaN");
+    assert_matches!(eval("(rem 1.0 0.0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(eval("(rem 1.0 -0.0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
     assert_matches!(eval("(rem 1/1 0/1)").unwrap_err(),
         Error::ExecError(ExecError::DivideByZero));
 }
 
+#[test]
+fn test_mod() {
+    assert_eq!(run("
+        (use math (mod))
+        (mod 7 3)
+        (rem 7 3)
+        (mod -7 3)
+        (rem -7 3)
+        (mod 7 -3)
+        (rem 7 -3)
+        (mod -7 -3)
+        (mod 6 3)
+        (mod -6 3)
+        (mod -1/2 1/3)
+        (mod -7.5 2)
+        (mod -7 30000000000000000000)
+        ").unwrap(),
+        ["()", "1", "1", "2", "-1", "-2", "1", "-1", "0", "0", "1/6", "0.5",
+            "29999999999999999993"]);
+
+    assert_matches!(run("(use math (mod)) (mod 1 0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(eval("(rem -7 0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(run("(use math (mod)) (mod 1/2 0/1)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(run("(use math (mod)) (mod 7 0.0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(run("(use math (mod)) (mod 7.5 -0.0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(eval("(rem 7 0.0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+}
+
+#[test]
+fn test_clamp() {
+    assert_eq!(run("
+        (use math (clamp))
+        (clamp -5 0 10)
+        (clamp 5 0 10)
+        (clamp 15 0 10)
+        (clamp 0 0 10)
+        (clamp 10 0 10)
+        (clamp 1.5 0 1)
+        (clamp 1/2 0.0 1.0)
+        (clamp -2.5 -1.0 1.0)
+        (clamp 3 7 7)
+        (clamp 9 7 7)
+        ").unwrap(),
+        ["()", "0", "5", "10", "0", "10", "1", "1/2", "-1.0", "7", "7"]);
+
+    assert_matches!(run("(use math (clamp)) (clamp 5 10 0)").unwrap_err(),
+        Error::ExecError(ExecError::InvalidRange(_)));
+    assert_matches!(run("(use math (clamp)) (clamp \"a\" 0 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_lerp() {
+    assert_eq!(run("
+        (use math (lerp))
+        (lerp 10 20 0)
+        (lerp 10 20 1)
+        (lerp 10 20 0.5)
+        (lerp 10 20 1/2)
+        (lerp 0.0 1.0 0.25)
+        (lerp 20 10 0.5)
+        (lerp 0 10 2)
+        ").unwrap(),
+        ["()", "10", "20", "15.0", "15/1", "0.25", "15.0", "20"]);
+
+    assert_matches!(run("(use math (lerp)) (lerp 0 \"a\" 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
 #[test]
 fn test_shift() {
     assert_eq!(eval("(<< 1 10)").unwrap(), "1024");
@@ -691,6 +901,65 @@ fn test_eq() {
     assert_eq!(eval("(= 1/1 1.0)").unwrap(), "true");
 }
 
+#[test]
+fn test_eq_shared_list() {
+    let items: Vec<Value> = (0..1000).map(Value::from).collect();
+    let a = Value::List(items.clone().into());
+
+    // Shares the underlying buffer; compared by pointer
+    assert!(a.is_equal(&a.clone()).unwrap());
+    assert!(a.is_identical(&a.clone()));
+
+    // Distinct buffer; compared element by element
+    let b = Value::List(items.into());
+    assert!(a.is_equal(&b).unwrap());
+
+    let c = Value::List(vec![Value::Float(::std::f64::NAN)].into());
+    let d = Value::List(vec![Value::Float(::std::f64::NAN)].into());
+
+    assert!(c.is_equal(&c.clone()).unwrap());
+    assert!(!c.is_equal(&d).unwrap());
+}
+
+#[test]
+fn test_is_same() {
+    assert_eq!(run("
+        (use box (box eq?))
+        (define a (list 1 2))
+        (define b (list 1 2))
+        (= a b)
+        (eq? a b)
+        (eq? a a)
+        (eq? a (id a))
+        (eq? a (slice a 0 1))
+        (eq? 1 1)
+        (eq? 1 2)
+        (eq? 1 1.0)
+        (eq? #'a' #'a')
+        (eq? 'foo 'foo)
+        (eq? () ())
+        (eq? (nan) (nan))
+        (let ((s \"a\")) (eq? s s))
+        (eq? (box 1) (box 1))
+        (let ((x (box 1))) (eq? x x x))
+        (eq? id id)
+        ").unwrap(), ["()", "a", "b", "true", "false", "true", "true", "false",
+            "true", "false", "false", "true", "true", "true", "true",
+            "true", "false", "true", "true"]);
+
+    let a = Value::List(vec![Value::from(1), Value::from(2)].into());
+    let b = Value::List(vec![Value::from(1), Value::from(2)].into());
+
+    assert!(a.is_same(&a.clone()));
+    assert!(!a.is_same(&b));
+    assert!(a.is_equal(&b).unwrap());
+
+    let s = Value::from("abc");
+
+    assert!(s.is_same(&s.clone()));
+    assert!(!s.is_same(&Value::from("abc")));
+}
+
 #[test]
 fn test_ne() {
     assert_eq!(eval("(/= 1 2 3 4)").unwrap(), "true");
@@ -737,6 +1006,16 @@ fn test_cmp() {
     assert_matches!(eval("(< < <)").unwrap_err(),
         Error::ExecError(ExecError::CannotCompare("function")));
 
+    assert_eq!(eval("(< 1 1.5 2 5/2)").unwrap(), "true");
+    assert_eq!(eval(r#"(< "abc" "abd" "b")"#).unwrap(), "true");
+
+    assert_matches!(eval(r#"(< "a" 1)"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeMismatch{lhs: "string", rhs: "integer"}));
+    assert_matches!(eval(r#"(>= 1.0 "a")"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeMismatch{lhs: "float", rhs: "string"}));
+    assert_matches!(eval("(< 1 2 'a)").unwrap_err(),
+        Error::ExecError(ExecError::TypeMismatch{lhs: "integer", rhs: "name"}));
+
     assert_matches!(eval("(< 1.0 (nan))").unwrap_err(),
         Error::ExecError(ExecError::CompareNaN));
     assert_matches!(eval("(< 1 (nan))").unwrap_err(),
@@ -801,6 +1080,28 @@ fn test_min_max() {
     assert_eq!(eval("(min 3 2 1 2 3)").unwrap(), "1");
 }
 
+#[test]
+fn test_bool_conversion() {
+    assert_eq!(run("
+        (use math (->bool bool->int))
+        (->bool true)
+        (->bool false)
+        (->bool ())
+        (->bool 0)
+        (->bool \"\")
+        (->bool '(1))
+        (->bool 'a)
+        (bool->int true)
+        (bool->int false)
+        (bool->int (->bool ()))
+        ").unwrap(),
+        ["()", "true", "false", "false", "true", "true", "true", "true",
+            "1", "0", "0"]);
+
+    assert_matches!(run("(use math (bool->int)) (bool->int 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "bool", found: "integer", ..}));
+}
+
 #[test]
 fn test_and() {
     assert_eq!(eval("(and true true)").unwrap(), "true");
@@ -922,6 +1223,40 @@ fn test_cond() {
         Error::CompileError(_));
 }
 
+#[test]
+fn test_match() {
+    assert_eq!(run("
+        (struct Point ((x integer) (y integer)))
+        (struct Circle ((r integer)))
+        (define (area shape)
+          (match shape
+            ((Point x y) (+ x y))
+            ((Circle r)  (* r r))
+            (_           0)))
+        (area (new Point :x 1 :y 2))
+        (area (new Circle :r 3))
+        (area 'other)
+        (match (new Point :x 1 :y 2) ((Circle r) r))
+        (match (new Point :x 5 :y 6) ((Point y) y))
+        (let ((x 10))
+          (match (new Circle :r 1) ((Point x) x) ((Circle) x)))
+        ").unwrap(),
+        ["Point", "Circle", "area", "3", "9", "0", "()", "6", "10"]);
+
+    assert_matches!(run("
+        (struct Point ((x integer) (y integer)))
+        (match (new Point :x 1 :y 2) ((Point z) z))
+        ").unwrap_err(),
+        Error::ExecError(ExecError::FieldError{..}));
+
+    assert_matches!(eval("(match 1 (0 'a))").unwrap_err(),
+        Error::CompileError(CompileError::SyntaxError("expected struct pattern or `_`")));
+    assert_matches!(eval("(match 1 ((1) 'a))").unwrap_err(),
+        Error::CompileError(CompileError::SyntaxError("expected name")));
+    assert_matches!(eval("(match 1 (_ 'a) (_ 'b))").unwrap_err(),
+        Error::CompileError(_));
+}
+
 #[test]
 fn test_let() {
     assert_eq!(eval("
@@ -934,6 +1269,29 @@ fn test_let() {
     assert_eq!(eval("(let ((id 0)) id)").unwrap(), "0");
 }
 
+#[test]
+fn test_let_pattern() {
+    assert_eq!(eval("(let (((a b c) '(1 2 3))) (list c b a))").unwrap(), "(3 2 1)");
+    assert_eq!(eval("
+        (let ((x 1)
+              ((a (b (c d)) e) (list 2 '(3 (4 5)) 6))
+              (y (+ x a)))
+          (list x a b c d e y))
+        ").unwrap(), "(1 2 3 4 5 6 3)");
+    assert_eq!(eval("
+        (let (((a b) '(1 2))
+              ((c d) (list b a)))
+          (list a b c d))
+        ").unwrap(), "(1 2 2 1)");
+
+    assert_matches!(eval("(let (((a b c) '(1 2))) a)").unwrap_err(),
+        Error::ExecError(ExecError::ArityError{..}));
+    assert_matches!(eval("(let (((a (b c)) '(1 (2)))) a)").unwrap_err(),
+        Error::ExecError(ExecError::ArityError{..}));
+    assert_matches!(eval("(let (((a 1) '(1 2))) a)").unwrap_err(),
+        Error::CompileError(CompileError::SyntaxError(_)));
+}
+
 #[test]
 fn test_chars() {
     assert_eq!(eval(r#"(chars "")"#).unwrap(), "()");
@@ -942,6 +1300,29 @@ fn test_chars() {
         "(#'h' #'a' #'l' #'o' #' ' #'t' #'h' #'a' #'r')");
 }
 
+#[test]
+fn test_char_literal() {
+    let chars = [
+        ('\n', r"#'\n'"),
+        ('\\', r"#'\\'"),
+        ('\'', r"#'\''"),
+        ('\0', r"#'\0'"),
+        ('\u{7f}', r"#'\u{7f}'"),
+        ('é', "#'é'"),
+        ('"', "#'\"'"),
+    ];
+
+    for &(ch, lit) in &chars {
+        let interp = Interpreter::new();
+        let printed = interp.format_value(&Value::Char(ch));
+        assert_eq!(printed, lit);
+
+        // Printed form reads back as the same char
+        assert_matches!(interp.run_single_expr(&printed, None).unwrap(),
+            Value::Char(c) if c == ch);
+    }
+}
+
 #[test]
 fn test_string() {
     assert_eq!(eval(r#"(string #'a')"#).unwrap(), r#""a""#);
@@ -979,6 +1360,25 @@ fn test_slice() {
         Error::ExecError(ExecError::NotCharBoundary(2)));
     assert_matches!(eval(r#"(slice "a\u{2022}" 2)"#).unwrap_err(),
         Error::ExecError(ExecError::NotCharBoundary(2)));
+
+    assert_eq!(eval("(slice '(1 2 3 4) -2)").unwrap(), "(3 4)");
+    assert_eq!(eval("(slice '(1 2 3 4) -3 -1)").unwrap(), "(2 3)");
+    assert_eq!(eval("(slice '(1 2 3 4) 1 -1)").unwrap(), "(2 3)");
+    assert_eq!(eval("(slice '(1 2 3 4) -1 3)").unwrap(), "()");
+    assert_matches!(eval("(slice '(1 2 3 4) 3 1)").unwrap_err(),
+        Error::ExecError(ExecError::InvalidRange(_)));
+    assert_matches!(eval("(slice '(1 2 3 4) -1 0)").unwrap_err(),
+        Error::ExecError(ExecError::InvalidRange(_)));
+    assert_matches!(eval("(slice '(1 2 3 4) -5)").unwrap_err(),
+        Error::ExecError(ExecError::NegativeOutOfBounds(-5)));
+    assert_matches!(eval("(slice '(1 2 3 4) 0 5)").unwrap_err(),
+        Error::ExecError(ExecError::OutOfBounds(5)));
+
+    assert_eq!(eval(r#"(slice "foobar" -3)"#).unwrap(), r#""bar""#);
+    assert_eq!(eval(r#"(slice "foobar" 0 -3)"#).unwrap(), r#""foo""#);
+    assert_matches!(eval(r#"(slice "foobar" 4 2)"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidRange(_)));
+    assert_eq!(eval(r#"(slice #b"foobar" -3 -1)"#).unwrap(), r#"#b"ba""#);
 }
 
 #[test]
@@ -1003,6 +1403,18 @@ fn test_elt() {
 #[test]
 fn test_concat() {
     assert_eq!(eval("(concat '(1 2) () '(3 4))").unwrap(), "(1 2 3 4)");
+    assert_eq!(eval("(concat '(1) '(2 3) '(4 5 6))").unwrap(), "(1 2 3 4 5 6)");
+    assert_eq!(eval("(concat '(1 2))").unwrap(), "(1 2)");
+    assert_eq!(eval("(concat ())").unwrap(), "()");
+    assert_eq!(eval("(concat)").unwrap(), "()");
+    assert_eq!(eval("(apply concat ())").unwrap(), "()");
+
+    assert_matches!(eval("(concat '(1 2) 3)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+    assert_matches!(eval("(concat '(1 2) \"a\")").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+    assert_matches!(eval("(concat 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
 
     assert_eq!(eval(r#"(concat "foo" "" "bar")"#).unwrap(), r#""foobar""#);
     assert_eq!(eval(r#"(concat #'a' #'b')"#).unwrap(), r#""ab""#);
@@ -1013,6 +1425,21 @@ fn test_concat() {
     assert_eq!(eval(r#"(concat #b"foo" #b"bar")"#).unwrap(), r#"#b"foobar""#);
 }
 
+#[test]
+fn test_concat_shares_storage() {
+    let interp = Interpreter::new();
+
+    interp.run_code("
+        (define li (list 1 2 3))
+        (define c (concat li))
+        ", None).unwrap();
+
+    match (&interp.get_value("li").unwrap(), &interp.get_value("c").unwrap()) {
+        (Value::List(li), Value::List(c)) => assert_eq!(li.as_ptr(), c.as_ptr()),
+        _ => panic!("expected lists")
+    }
+}
+
 #[test]
 fn test_join() {
     assert_eq!(eval("(join '(0) '(1 2) () '(3 4))").unwrap(), "(1 2 0 0 3 4)");
@@ -1058,6 +1485,44 @@ fn test_list_fns() {
     assert_eq!(eval("(tail '(1 2 3))").unwrap(), "(2 3)");
     assert_eq!(eval("(init '(1))").unwrap(), "()");
     assert_eq!(eval("(tail '(1))").unwrap(), "()");
+
+    assert_matches!(eval("(first ())").unwrap_err(),
+        Error::ExecError(ExecError::OutOfBounds(0)));
+    assert_matches!(eval("(last ())").unwrap_err(),
+        Error::ExecError(ExecError::OutOfBounds(0)));
+    assert_matches!(eval("(init ())").unwrap_err(),
+        Error::ExecError(ExecError::OutOfBounds(0)));
+    assert_matches!(eval("(tail ())").unwrap_err(),
+        Error::ExecError(ExecError::OutOfBounds(0)));
+}
+
+#[test]
+fn test_tail_shares_storage() {
+    let interp = Interpreter::new();
+
+    interp.run_code("
+        (define li (list 1 2 3 4))
+        (define t (tail li))
+        (define tt (tail t))
+        (define t2 (append t 5))
+        ", None).unwrap();
+
+    let li = interp.get_value("li").unwrap();
+    let t = interp.get_value("t").unwrap();
+    let tt = interp.get_value("tt").unwrap();
+
+    match (&li, &t, &tt) {
+        (Value::List(li), Value::List(t), Value::List(tt)) => {
+            assert_eq!(t.as_ptr(), li[1..].as_ptr());
+            assert_eq!(tt.as_ptr(), li[2..].as_ptr());
+        }
+        _ => panic!("expected lists")
+    }
+
+    // Appending to a view copies it, leaving other views unchanged
+    assert_eq!(interp.format_value(&li), "(1 2 3 4)");
+    assert_eq!(interp.format_value(&t), "(2 3 4)");
+    assert_eq!(interp.format_value(&interp.get_value("t2").unwrap()), "(2 3 4 5)");
 }
 
 #[test]
@@ -1098,6 +1563,71 @@ fn test_bytes_fns() {
         Error::ExecError(ExecError::OutOfBounds(0)));
 }
 
+#[test]
+fn test_copy() {
+    assert_eq!(run(r#"
+        (use box (copy))
+        (copy 1)
+        (copy '(1 (2 "foo") #b"bar" 'baz))
+        (= (copy '(1 (2 "foo"))) '(1 (2 "foo")))
+        "#).unwrap(), ["()", "1", r#"(1 (2 "foo") #b"bar" 'baz)"#, "true"]);
+    assert_eq!(run("
+        (use box (copy))
+        (struct Foo ((a list)))
+        (copy (new Foo :a '(1 2)))
+        ").unwrap(), ["()", "Foo", "Foo { a: (1 2) }"]);
+}
+
+#[test]
+fn test_box() {
+    assert_eq!(run("
+        (use box (box unbox set-box!))
+        (define b (box 1))
+        (unbox b)
+        (set-box! b '(2 3))
+        (unbox b)
+        b
+        (type-of b)
+        (is 'box b)
+        (eq b b)
+        (= b b)
+        (eq (box 1) (box 1))
+        (= (box 1) (box 1))
+        ").unwrap(), ["()", "b", "1", "1", "(2 3)", "<box (2 3)>", "box", "true",
+            "true", "true", "false", "false"]);
+
+    // A copy of a box is a distinct box
+    assert_eq!(run("
+        (use box :all)
+        (define a (box (list 1)))
+        (define c (copy a))
+        (set-box! a 2)
+        (list (unbox a) (unbox c) (eq a c))
+        ").unwrap(), ["()", "a", "c", "(1)", "(2 (1) false)"]);
+
+    // A box containing itself can be printed
+    assert_eq!(run("
+        (use box (box set-box!))
+        (define s (box ()))
+        (set-box! s (list 1 s))
+        s
+        ").unwrap(), ["()", "s", "()", "<box (1 <box ...>)>"]);
+
+    // A copy of a box containing itself contains the copy
+    assert_eq!(run("
+        (use box :all)
+        (define s (box ()))
+        (set-box! s (list s))
+        (define c (copy s))
+        (list (eq? (first (unbox c)) c) (eq? (first (unbox c)) s))
+        ").unwrap(), ["()", "s", "()", "c", "(true false)"]);
+
+    assert_matches!(run("(use box (unbox)) (unbox 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+    assert_matches!(run("(use box (set-box!)) (set-box! '(1) 2)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
 #[test]
 fn test_list() {
     assert_eq!(eval("(list 1 2 (+ 1 2))").unwrap(), "(1 2 3)");
@@ -1115,6 +1645,8 @@ fn test_abs() {
     assert_eq!(eval("(abs -1/2)").unwrap(), "1/2");
     assert_eq!(eval("(abs -1.5)").unwrap(), "1.5");
     assert_eq!(eval("(abs (- (inf)))").unwrap(), "inf");
+    assert_eq!(eval("(abs -123456789012345678901234567890)").unwrap(),
+        "123456789012345678901234567890");
 }
 
 #[test]
@@ -1205,6 +1737,23 @@ fn test_ratio() {
     assert_eq!(eval("(rat 1/3)").unwrap(), "1/3");
     assert_eq!(eval("(rat 3)").unwrap(), "3/1");
     assert_eq!(eval("(rat 1 2)").unwrap(), "1/2");
+    assert_eq!(eval("(rat 6 4)").unwrap(), "3/2");
+    assert_eq!(eval("(rat 6 -4)").unwrap(), "-3/2");
+    assert_eq!(eval("(numer (rat 6 -4))").unwrap(), "-3");
+    assert_eq!(eval("(denom (rat 6 -4))").unwrap(), "2");
+    assert_eq!(eval("(rat -2 -4)").unwrap(), "1/2");
+    assert_matches!(eval("(rat 1 0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_eq!(eval("(make-ratio 6 4)").unwrap(), "3/2");
+    assert_eq!(eval("(make-ratio 6 -4)").unwrap(), "-3/2");
+    assert_eq!(eval("(make-ratio -2 -4)").unwrap(), "1/2");
+    assert_eq!(eval("(numerator (make-ratio 6 -4))").unwrap(), "-3");
+    assert_eq!(eval("(denominator (make-ratio 6 -4))").unwrap(), "2");
+    assert_eq!(eval("(list (numerator 5) (denominator 5))").unwrap(), "(5 1)");
+    assert_matches!(eval("(make-ratio 1 0)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(eval("(make-ratio 1/2 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
     assert_matches!(eval("(rat (inf))").unwrap_err(),
         Error::ExecError(ExecError::Overflow));
     assert_matches!(eval("(rat (nan))").unwrap_err(),
@@ -1421,6 +1970,72 @@ fn test_macro() {
         Error::CompileError(CompileError::MacroRecursionExceeded));
 }
 
+#[test]
+fn test_let_syntax() {
+    assert_eq!(eval("
+        (let-syntax (((twice e) `(* 2 ,e))
+                     ((thrice e) `(* 3 ,e)))
+          (+ (twice 3) (thrice 5)))
+        ").unwrap(),
+        "21");
+
+    assert_matches!(run("
+        (let-syntax (((twice e) `(* 2 ,e))) (twice 3))
+        (twice 3)
+        ").unwrap_err(),
+        Error::ExecError(ExecError::NameError(_)));
+
+    assert_eq!(run("
+        (macro (foo) 1)
+        (let-syntax (((foo) 2)) (foo))
+        (foo)
+        ").unwrap(),
+        ["foo", "2", "1"]);
+}
+
+#[test]
+fn test_dynamic_let() {
+    assert_eq!(run(r#"
+        (define (show v) (format "~a" v))
+        (list (show "a")
+              (dynamic-let ((*print-readably* true))
+                (list (show "b")
+                      (dynamic-let ((*print-readably* false)) (show "c"))
+                      (show "d")))
+              (show "e"))
+        "#).unwrap(),
+        ["show", r#"("a" ("\"b\"" "c" "\"d\"") "e")"#]);
+
+    let interp = Interpreter::new();
+
+    let err = interp.run_code(r#"
+        (dynamic-let ((*print-readably* true))
+          (panic (format "~a" "oops")))
+        "#, None).unwrap_err();
+    assert_eq!(interp.format_error(&err), r#"execution error: panic: "oops""#);
+
+    // The binding is removed when an error unwinds through the body
+    let v = interp.run_code(r#"(format "~a" "foo")"#, None).unwrap();
+    assert_eq!(interp.format_value(&v), r#""foo""#);
+
+    // Bindings of a suspended body are not visible to other code
+    let state = interp.run_code_with_budget(r#"
+        (dynamic-let ((*print-readably* true))
+          (format "~a" (concat "a" "b" "c" "d")))
+        "#, 8).unwrap();
+    let susp = match state {
+        ExecState::Suspended(s) => s,
+        ExecState::Done(_) => panic!("expected suspended execution")
+    };
+    let v = interp.run_code(r#"(format "~a" "foo")"#, None).unwrap();
+    assert_eq!(interp.format_value(&v), r#""foo""#);
+    assert_matches!(susp.resume(1000).unwrap(),
+        ExecState::Done(Value::String(ref s)) if s == r#""abcd""#);
+
+    assert_matches!(eval("(dynamic-let ((foo true)) ())").unwrap_err(),
+        Error::CompileError(CompileError::SyntaxError(_)));
+}
+
 #[test]
 fn test_apply() {
     assert_eq!(eval("(apply + '(1 2 3))").unwrap(), "6");
@@ -1492,3 +2107,1182 @@ fn test_use() {
         ").unwrap_err(),
         Error::CompileError(CompileError::ImportError{..}));
 }
+
+#[test]
+fn test_add_module() {
+    let interp = Interpreter::new();
+
+    interp.add_module("greet", r#"
+        (export (greet))
+        (define (greet name) (concat "hello, " name))
+        "#).unwrap();
+
+    let v = interp.run_code(r#"
+        (use greet (greet))
+        (greet "world")
+        "#, None).unwrap();
+    assert_eq!(interp.format_value(&v), r#""hello, world""#);
+
+    let v = interp.call("greet", vec!["ketos".into()]).unwrap();
+    assert_eq!(interp.format_value(&v), r#""hello, ketos""#);
+
+    assert_matches!(interp.add_module("greet", "(export ())").unwrap_err(),
+        Error::CompileError(CompileError::DuplicateModule(_)));
+    assert_matches!(interp.add_module("math", "(export ())").unwrap_err(),
+        Error::CompileError(CompileError::DuplicateModule(_)));
+}
+
+#[test]
+fn test_builtin_and_module_names() {
+    let interp = Interpreter::new();
+
+    let names = interp.builtin_names();
+    for name in &["+", "first", "let", "lambda", "define"] {
+        assert!(names.iter().any(|n| n == name), "missing {}", name);
+    }
+    assert!(!names.iter().any(|n| n == "foo"));
+
+    assert!(!interp.module_names().iter().any(|n| n == "mine"));
+    assert!(interp.module_names().iter().any(|n| n == "math"));
+
+    interp.add_module("mine", "(export (foo)) (define (foo) 1)").unwrap();
+    interp.run_code("(use math (sqrt))", None).unwrap();
+
+    let modules = interp.module_names();
+    assert!(modules.iter().any(|n| n == "mine"));
+    assert_eq!(modules.iter().filter(|n| *n == "math").count(), 1);
+}
+
+#[test]
+fn test_signum() {
+    assert_eq!(run("
+        (use math (signum))
+        (signum -5)
+        (signum 0)
+        (signum 3/4)
+        (signum -2.5)
+        ").unwrap(),
+        ["()", "-1", "0", "1/1", "-1.0"]);
+}
+
+#[test]
+fn test_expt() {
+    assert_eq!(run("
+        (use math (expt))
+        (expt 2 10)
+        (expt 2 -1)
+        (expt -2 -3)
+        (expt 2/3 -2)
+        (expt 2 0)
+        (expt 4 0.5)
+        (expt 2.0 -1)
+        (expt 1/4 1/2)
+        ").unwrap(),
+        ["()", "1024", "1/2", "-1/8", "9/4", "1", "2.0", "0.5", "0.5"]);
+
+    assert_matches!(run("(use math (expt)) (expt 0 -1)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert_matches!(run("(use math (expt)) (expt 'a 1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_integer_bits() {
+    assert_eq!(run("
+        (use math (bit-length count-ones trailing-zeros))
+        (bit-length 1024)
+        (count-ones 1024)
+        (trailing-zeros 1024)
+        (bit-length 0)
+        (count-ones 0)
+        (trailing-zeros 0)
+        (bit-length -12)
+        (count-ones -12)
+        (trailing-zeros -12)
+        (bit-length (<< 1 100))
+        ").unwrap(),
+        ["()", "11", "1", "10", "0", "0", "0", "4", "2", "2", "101"]);
+
+    assert_matches!(run("(use math (count-ones)) (count-ones 1.0)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "integer", ..}));
+}
+
+#[test]
+fn test_isqrt_factorial() {
+    assert_eq!(run("
+        (use math (factorial isqrt))
+        (isqrt 144)
+        (isqrt 143)
+        (isqrt 0)
+        (isqrt (* 12345678901234567890 12345678901234567890))
+        (isqrt (- (* 12345678901234567890 12345678901234567890) 1))
+        (factorial 5)
+        (factorial 0)
+        (factorial 25)
+        ").unwrap(),
+        ["()", "12", "11", "0", "12345678901234567890", "12345678901234567889",
+            "120", "1", "15511210043330985984000000"]);
+
+    assert_matches!(run("(use math (isqrt)) (isqrt -4)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "non-negative integer", ..}));
+    assert_matches!(run("(use math (factorial)) (factorial -1)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "non-negative integer", ..}));
+    assert_matches!(run("(use math (factorial)) (factorial 5.0)").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_even_odd() {
+    assert_eq!(run("
+        (use math (even? odd?))
+        (even? -4)
+        (odd? -3)
+        (even? 7)
+        ").unwrap(),
+        ["()", "true", "true", "false"]);
+
+    assert_matches!(run("
+        (use math (even?))
+        (even? 2.0)
+        ").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "integer", ..}));
+}
+
+#[test]
+fn test_intern_strings() {
+    fn string_ptrs(interp: &Interpreter) -> Vec<*const u8> {
+        let v = interp.run_single_expr(
+            r#"'("tag" "tag" "other" "tag" "other")"#, None).unwrap();
+
+        match v {
+            Value::List(li) => li.iter().map(|v| match *v {
+                Value::String(ref s) => s.as_ptr(),
+                ref v => panic!("expected string; got {}", v.type_name())
+            }).collect(),
+            v => panic!("expected list; got {}", v.type_name())
+        }
+    }
+
+    let interp = Builder::new().intern_strings(true).finish();
+    let p = string_ptrs(&interp);
+
+    assert_eq!(p[0], p[1]);
+    assert_eq!(p[0], p[3]);
+    assert_eq!(p[2], p[4]);
+    assert!(p[0] != p[2]);
+
+    let interp = Interpreter::new();
+    let p = string_ptrs(&interp);
+
+    assert!(p[0] != p[1]);
+}
+
+#[test]
+fn test_hex() {
+    assert_eq!(run(r#"
+        (use bytes (hex-encode hex-decode))
+        (hex-encode #b"\x00\x7f\xab\xff")
+        (hex-encode #b"\x00\x7f\xab\xff" true)
+        (hex-decode "007fABff")
+        (hex-decode (hex-encode #b"ketos\x01"))
+        (hex-encode #b"")
+        "#).unwrap(),
+        ["()", r#""007fabff""#, r#""007FABFF""#, r#"#b"\x00\x7f\xab\xff""#,
+            r#"#b"ketos\x01""#, r#""""#]);
+
+    assert_matches!(run(r#"
+        (use bytes (hex-decode))
+        (hex-decode "abc")
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{encoding: "hex", ..}));
+
+    assert_matches!(run(r#"
+        (use bytes (hex-decode))
+        (hex-decode "0g")
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{encoding: "hex", ..}));
+
+    assert_matches!(run(r#"
+        (use bytes (hex-decode))
+        (hex-decode "00 11")
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{encoding: "hex", ..}));
+}
+
+#[test]
+fn test_bytes_read_write() {
+    assert_eq!(run(r#"
+        (use bytes :all)
+        (define b #b"\x01\x02\x03\x04\x05\x06\x07\x08\x09")
+        (read-u16 b 0)
+        (read-u16 b 1 :little)
+        (read-u32 b 0 :big)
+        (read-u32 b 5 :little)
+        (read-u64 b 1)
+        (read-u64 b 0 :little)
+        (write-u16 #b"\x00\x00\x00" 1 0xabcd)
+        (write-u32 #b"\x00\x00\x00\x00" 0 0x01020304 :little)
+        (write-u64 #b"\x00\x00\x00\x00\x00\x00\x00\x00" 0 0xffffffffffffffff)
+        "#).unwrap(),
+        ["()", "b", "258", "770", "16909060", "151521030",
+            "144964032628459529", "578437695752307201",
+            r#"#b"\x00\xab\xcd""#, r#"#b"\x04\x03\x02\x01""#,
+            r#"#b"\xff\xff\xff\xff\xff\xff\xff\xff""#]);
+
+    assert_matches!(run(r#"
+        (use bytes (read-u32))
+        (read-u32 #b"abcdef" 3)
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::BytesOutOfBounds{offset: 3, width: 4, len: 6}));
+
+    assert_matches!(run(r#"
+        (use bytes (write-u16))
+        (write-u16 #b"ab" 1 0)
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::BytesOutOfBounds{offset: 1, width: 2, len: 2}));
+
+    assert_matches!(run(r#"
+        (use bytes (write-u16))
+        (write-u16 #b"ab" 0 0x10000)
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+
+    assert_matches!(run(r#"
+        (use bytes (read-u16))
+        (read-u16 #b"ab" 0 :middle)
+        "#).unwrap_err(),
+        Error::ExecError(ExecError::UnrecognizedKeyword(_)));
+}
+
+#[test]
+fn test_bytes_base64() {
+    assert_eq!(run(r#"
+        (use bytes (base64-decode base64-encode))
+        (base64-encode #b"")
+        (base64-encode #b"f")
+        (base64-encode #b"fo")
+        (base64-encode #b"foo")
+        (base64-encode #b"foob")
+        (base64-encode #b"\xfb\xff\xbf")
+        (base64-encode #b"\xfb\xff\xbf" :url-safe)
+        (define b #b"\x00\x01\x7f\x80\xfe\xff\x10")
+        (= b (base64-decode (base64-encode b)))
+        (= b (base64-decode (base64-encode b :url-safe) :url-safe))
+        (base64-decode "Zm9vYg==")
+        (base64-decode "Zm9vYg" :standard true)
+        (base64-decode "Zm9vYg==" :standard true)
+        (base64-decode "-_-_" :url-safe)
+        "#).unwrap(),
+        ["()", r#""""#, r#""Zg==""#, r#""Zm8=""#, r#""Zm9v""#, r#""Zm9vYg==""#,
+            r#""+/+/""#, r#""-_-_""#, "b", "true", "true",
+            r#"#b"foob""#, r#"#b"foob""#, r#"#b"foob""#, r#"#b"\xfb\xff\xbf""#]);
+
+    for input in &["Zm9vYg", "Zm9vYg=", "Zm9v=Yg=", "Zm9vY", "Zm9 v", "-_-_", "Zh=="] {
+        assert_matches!(run(&format!(r#"(use bytes (base64-decode)) (base64-decode "{}")"#, input))
+                .unwrap_err(),
+            Error::ExecError(ExecError::InvalidEncoding{encoding: "base64", ..}),
+            "{:?} should not decode", input);
+    }
+
+    assert_matches!(run(r#"(use bytes (base64-encode)) (base64-encode #b"" :hex)"#).unwrap_err(),
+        Error::ExecError(ExecError::UnrecognizedKeyword(_)));
+}
+
+#[test]
+fn test_compile_cache() {
+    let interp = Builder::new().compile_cache_size(2).finish();
+
+    let v = interp.run_code("(+ 1 2)", None).unwrap();
+    assert_eq!(interp.format_value(&v), "3");
+    let v = interp.run_code("(+ 1 2)", None).unwrap();
+    assert_eq!(interp.format_value(&v), "3");
+
+    let stats = interp.compile_cache_stats();
+    assert_eq!(stats.misses, 1);
+    assert_eq!(stats.hits, 1);
+
+    interp.run_code("(+ 2 3)", None).unwrap();
+    interp.run_code("(+ 3 4)", None).unwrap();
+    // `(+ 1 2)` was least recently used and should have been evicted
+    interp.run_code("(+ 1 2)", None).unwrap();
+
+    let stats = interp.compile_cache_stats();
+    assert_eq!(stats.misses, 4);
+    assert_eq!(stats.hits, 1);
+
+    // Identical input from another path is compiled separately
+    interp.run_code("(+ 1 2)", Some("a.ket".to_owned())).unwrap();
+    interp.run_code("(+ 1 2)", Some("a.ket".to_owned())).unwrap();
+
+    let stats = interp.compile_cache_stats();
+    assert_eq!(stats.misses, 5);
+    assert_eq!(stats.hits, 2);
+
+    let interp = Interpreter::new();
+
+    interp.run_code("(+ 1 2)", None).unwrap();
+    interp.run_code("(+ 1 2)", None).unwrap();
+    assert_eq!(interp.compile_cache_stats(), Default::default());
+}
+
+#[test]
+fn test_string_case() {
+    assert_eq!(run(r#"
+        (use string (capitalize title-case))
+        (capitalize "hELLO, wORLD!")
+        (capitalize "")
+        (capitalize "(éclair)")
+        (title-case "the  quick\tbrown 'fox'")
+        (title-case "")
+        "#).unwrap(),
+        ["()", r#""Hello, world!""#, r#""""#, r#""(Éclair)""#,
+            r#""The  Quick\tBrown 'Fox'""#, r#""""#]);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex() {
+    assert_eq!(run(r#"
+        (use regex (find match? replace))
+        (match? "^a+b" "aaab")
+        (match? "^a+b" "ba")
+        (find "(\\w+)@(\\w+)(\\.org)?" "mail: joe@example.com")
+        (find "x" "abc")
+        (replace "(\\d+)" "1 and 23" "<$1>")
+        "#).unwrap(),
+        ["()", "true", "false", r#"("joe@example" "joe" "example" ())"#, "()",
+            r#""<1> and <23>""#]);
+
+    assert_matches!(run(r#"(use regex (match?)) (match? "(" "")"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidRegex(_)));
+}
+
+#[cfg(not(feature = "regex"))]
+#[test]
+fn test_regex_unavailable() {
+    assert_matches!(run(r#"(use regex (match?)) (match? "a" "a")"#).unwrap_err(),
+        Error::ExecError(ExecError::FeatureUnavailable("regex")));
+}
+
+#[test]
+fn test_string_newlines() {
+    assert_eq!(run(r#"
+        (use string (normalize-newlines split-lines-keepends))
+        (normalize-newlines "a\r\nb\rc\nd\r\r\ne")
+        (normalize-newlines "\r")
+        (split-lines-keepends "a\r\nb\rc\nd\r\r\ne")
+        (split-lines-keepends "a\n\nb\n")
+        (split-lines-keepends "")
+        "#).unwrap(),
+        ["()", r#""a\nb\nc\nd\n\ne""#, r#""\n""#,
+            r#"("a\r\n" "b\r" "c\n" "d\r" "\r\n" "e")"#,
+            r#"("a\n" "\n" "b\n")"#, "()"]);
+}
+
+#[test]
+fn test_string_length() {
+    assert_eq!(run(r#"
+        (use string (byte-length char-length))
+        (list (byte-length "hello") (char-length "hello") (len "hello"))
+        (list (byte-length "héllo") (char-length "héllo") (len "héllo"))
+        (list (byte-length "日本") (char-length "日本"))
+        (list (byte-length "") (char-length ""))
+        "#).unwrap(),
+        ["()", "(5 5 5)", "(6 5 6)", "(6 2)", "(0 0)"]);
+
+    assert_matches!(run(r#"(use string (char-length)) (char-length 'a)"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_string_split_once() {
+    assert_eq!(run(r#"
+        (use string (split-once rsplit-once))
+        (split-once "a=b=c" "=")
+        (rsplit-once "a=b=c" "=")
+        (split-once "key: value" ": ")
+        (split-once "=" "=")
+        (split-once "abc" "=")
+        (rsplit-once "" "=")
+        "#).unwrap(),
+        ["()", r#"("a" "b=c")"#, r#"("a=b" "c")"#, r#"("key" "value")"#,
+            r#"("" "")"#, "()", "()"]);
+
+    assert_matches!(run(r#"(use string (split-once)) (split-once "abc" "")"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+    assert_matches!(run(r#"(use string (rsplit-once)) (rsplit-once "abc" "")"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_string_split_whitespace() {
+    assert_eq!(run(r#"
+        (use string (split-whitespace))
+        (split-whitespace "  a  b c ")
+        (split-whitespace "")
+        (split-whitespace " \t\n ")
+        (split-whitespace "one\ttwo\n\nthree\r\nfour")
+        (split-whitespace "a\u{3000}b")
+        "#).unwrap(),
+        ["()", r#"("a" "b" "c")"#, "()", "()",
+            r#"("one" "two" "three" "four")"#, r#"("a" "b")"#]);
+}
+
+#[test]
+fn test_string_csv() {
+    assert_eq!(run(r#"
+        (use string (parse-csv-line format-csv-line))
+        (parse-csv-line "a,b,c")
+        (parse-csv-line "a,\"b,c\",d")
+        (parse-csv-line "\"say \"\"hi\"\"\",x")
+        (parse-csv-line "a,,b,")
+        (parse-csv-line "")
+        (parse-csv-line "\"\"")
+        (format-csv-line '("a" "b" "c"))
+        (format-csv-line '("a" "b,c" "say \"hi\"" "" "x\ny"))
+        (format-csv-line ())
+        (parse-csv-line (format-csv-line '("a" "b,c" "say \"hi\"" "")))
+        "#).unwrap(),
+        ["()", r#"("a" "b" "c")"#, r#"("a" "b,c" "d")"#, r#"("say \"hi\"" "x")"#,
+            r#"("a" "" "b" "")"#, r#"("")"#, r#"("")"#,
+            r#""a,b,c""#, r#""a,\"b,c\",\"say \"\"hi\"\"\",,\"x\ny\"""#, r#""""#,
+            r#"("a" "b,c" "say \"hi\"" "")"#]);
+
+    for (input, reason) in &[
+            (r#""a,b"#, "unterminated quoted field"),
+            (r#""a"b,c"#, "unexpected text after quoted field"),
+            (r#"a"b,c"#, "quote within unquoted field")] {
+        let code = format!("(use string (parse-csv-line)) (parse-csv-line {:?})", input);
+
+        assert_matches!(run(&code).unwrap_err(),
+            Error::ExecError(ExecError::InvalidEncoding{encoding: "csv", reason: r})
+                if r == *reason);
+    }
+
+    assert_matches!(run("(use string (format-csv-line)) (format-csv-line '(1))").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_string_center_truncate() {
+    assert_eq!(run(r#"
+        (use string (center truncate-ellipsis))
+        (center "ab" 6)
+        (center "abc" 6 #'*')
+        (center "abc" 3)
+        (center "abcd" 2)
+        (center "" 3 #'-')
+        (truncate-ellipsis "hello, world" 8)
+        (truncate-ellipsis "héllo" 5)
+        (truncate-ellipsis "héllo" 4)
+        (truncate-ellipsis "hi" 1)
+        (truncate-ellipsis "hi" 0)
+        (truncate-ellipsis "" 0)
+        "#).unwrap(),
+        ["()", r#""  ab  ""#, r#""*abc**""#, r#""abc""#, r#""abcd""#, r#""---""#,
+            r#""hello, …""#, r#""héllo""#, r#""hél…""#, r#""…""#, r#""""#, r#""""#]);
+
+    assert_matches!(run(r#"(use string (center)) (center "a" -1)"#).unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+}
+
+#[test]
+fn test_string_word_wrap() {
+    assert_eq!(run(r#"
+        (use string (word-wrap))
+        (word-wrap "the quick brown fox jumps over the lazy dog" 20)
+        (word-wrap "a extraordinarily long word" 10)
+        (word-wrap "one two\n\nthree  four five" 9)
+        (word-wrap "" 5)
+        "#).unwrap(),
+        ["()", r#""the quick brown fox\njumps over the lazy\ndog""#,
+            r#""a\nextraordinarily\nlong word""#,
+            r#""one two\n\nthree\nfour five""#, r#""""#]);
+
+    assert_matches!(run(r#"(use string (word-wrap)) (word-wrap "a" -1)"#).unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+}
+
+#[test]
+fn test_string_parse_number() {
+    assert_eq!(run(r#"
+        (use string (parse-float parse-int))
+        (parse-int "123")
+        (parse-int "-42")
+        (parse-int "ff" 16)
+        (parse-int "100000000000000000000")
+        (parse-float "1.5")
+        (parse-float "3")
+        (parse-float "-2e3")
+        "#).unwrap(),
+        ["()", "123", "-42", "255", "100000000000000000000", "1.5", "3.0", "-2000.0"]);
+
+    for s in &[r#""""#, r#""1.5""#, r#""+1""#, r#""1_000""#, r#"" 1""#, r#""1\n""#, r##""#x1f""##] {
+        assert_matches!(run(&format!("(use string (parse-int)) (parse-int {})", s))
+            .unwrap_err(), Error::ExecError(ExecError::InvalidEncoding{
+                encoding: "integer", reason: "not a valid number"}));
+    }
+
+    for s in &[r#""""#, r#""1.5x""#, r#""inf""#, r#""NaN""#, r#"" 1.5""#, r#""1.5 ""#] {
+        assert_matches!(run(&format!("(use string (parse-float)) (parse-float {})", s))
+            .unwrap_err(), Error::ExecError(ExecError::InvalidEncoding{
+                encoding: "float", reason: "not a valid number"}));
+    }
+
+    assert_matches!(run(r#"(use string (parse-int)) (parse-int "12" 1)"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "radix from 2 to 36", ..}));
+    assert_matches!(run(r#"(use string (parse-int)) (parse-int "19" 8)"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{encoding: "integer", ..}));
+}
+
+#[test]
+fn test_symbol_string() {
+    assert_eq!(run(r#"
+        (use string (keyword->string string->keyword string->symbol symbol->string))
+        (symbol->string 'foo-bar)
+        (string->symbol "foo-bar")
+        (eq (string->symbol (symbol->string 'a.b)) 'a.b)
+        (keyword->string :key)
+        (string->keyword "key")
+        (eq (string->keyword (keyword->string :x)) :x)
+        "#).unwrap(),
+        ["()", r#""foo-bar""#, "foo-bar", "true", r#""key""#, ":key", "true"]);
+
+    for s in &[r#""""#, r#""a b""#, r#""123""#, r#""(a)""#, r#""a)""#] {
+        assert_matches!(run(&format!("(use string (string->symbol)) (string->symbol {})", s))
+            .unwrap_err(), Error::ExecError(ExecError::InvalidEncoding{
+                encoding: "name", reason: "not a valid identifier"}));
+    }
+
+    assert_matches!(run(r#"(use string (string->keyword)) (string->keyword ":a")"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{encoding: "keyword", ..}));
+    assert_matches!(run(r#"(use string (symbol->string)) (symbol->string :a)"#).unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "name", ..}));
+}
+
+#[test]
+fn test_string_escape() {
+    let interp = Interpreter::new();
+
+    let v = interp.run_code(r#"
+        (use string (escape))
+        (escape "say \"hi\"\n")
+        "#, None).unwrap();
+    assert_eq!(<&str>::from_value_ref(&v).unwrap(), r#"say \"hi\"\n"#);
+
+    assert_eq!(run(r#"
+        (use string (escape unescape))
+        (define s "say \"hi\"\n\ttab \\ \u{7f}")
+        (= (unescape (escape s)) s)
+        (unescape "a\\nb\\x41\\u{42}")
+        "#).unwrap(),
+        ["()", "s", "true", r#""a\nbAB""#]);
+
+    assert_matches!(run(r#"(use string (unescape)) (unescape "bad \\q")"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{
+            encoding: "escaped string", reason: "unknown escape sequence"}));
+    assert_matches!(run(r#"(use string (unescape)) (unescape "end \\")"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidEncoding{
+            reason: "incomplete escape sequence", ..}));
+}
+
+#[test]
+fn test_run_code_named() {
+    let interp = Interpreter::new();
+
+    let e = interp.run_code_named("(define (foo) (+ 1 'a)) (foo)", "rule:42")
+        .unwrap_err();
+    let trace = interp.take_traceback().unwrap();
+    assert!(interp.format_trace(&trace).starts_with("  In source rule:42\n"));
+    assert_matches!(e, Error::ExecError(ExecError::TypeError{..}));
+
+    let e = interp.run_code_named("(foo", "rule:43").unwrap_err();
+    assert!(interp.format_error(&e).starts_with("rule:43:1:4:parse error"));
+}
+
+#[test]
+fn test_traced_error() {
+    use ketos::trace::TraceItem;
+
+    let interp = Interpreter::new();
+
+    interp.run_code("
+        (define (inner x) (first x))
+        (define (outer x) (inner x))
+        ", None).unwrap();
+
+    let calls = |e: &ketos::TracedError| {
+        let names = interp.scope().borrow_names();
+        e.traceback().unwrap().iter().filter_map(|item| match *item {
+            TraceItem::CallCode(_, n) => Some(names.get(n).to_owned()),
+            _ => None
+        }).collect::<Vec<_>>()
+    };
+
+    let e = interp.traced(|i| i.run_code("(outer 1)", None)).unwrap_err();
+    assert_matches!(*e.error(), Error::ExecError(ExecError::TypeError{..}));
+    assert_eq!(calls(&e), ["outer", "inner"]);
+
+    let e = interp.traced(|i| i.call("outer", vec![1.into()])).unwrap_err();
+    assert_eq!(calls(&e), ["outer", "inner"]);
+
+    let e = interp.traced(|i| i.run_code_named("(outer 1)", "input")).unwrap_err();
+    assert_matches!(e.traceback().unwrap()[0], TraceItem::Source(_));
+    assert_eq!(calls(&e), ["outer", "inner"]);
+
+    let e = interp.traced(|i| i.run_code("(outer", None)).unwrap_err();
+    assert_matches!(e.into_error(), Error::ParseError(_));
+
+    assert!(interp.traced(|i| i.run_code("(+ 1 1)", None)).is_ok());
+}
+
+#[test]
+fn test_name_observer() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use ketos::NameOrigin;
+
+    let interp = Interpreter::new();
+    let seen = Rc::new(RefCell::new(Vec::new()));
+
+    interp.run_code(r#"
+        (use string (capitalize))
+        (define x 1)
+        "#, None).unwrap();
+
+    let s = seen.clone();
+    interp.set_name_observer(move |name, origin| s.borrow_mut().push((name, origin)));
+
+    interp.run_code(r#"
+        (define (shadow x) (+ x 1))
+        (define (outer) (capitalize (string x)))
+        "#, None).unwrap();
+
+    interp.clear_name_observer();
+    interp.run_code("(shadow x)", None).unwrap();
+
+    let seen = seen.borrow().iter()
+        .map(|&(name, origin)| (interp.scope().with_name(name, |s| s.to_owned()), origin))
+        .collect::<Vec<_>>();
+
+    assert_eq!(seen, [
+        ("+".to_owned(), NameOrigin::Builtin),
+        ("x".to_owned(), NameOrigin::Local),
+        ("capitalize".to_owned(), NameOrigin::Module),
+        ("string".to_owned(), NameOrigin::Builtin),
+        ("x".to_owned(), NameOrigin::Global),
+    ]);
+}
+
+#[test]
+fn test_run_code_with_budget() {
+
+    let code = r#"
+        (define (sum-to n) (if (= n 0) 0 (+ n (sum-to (- n 1)))))
+        (define (loop n acc) (if (= n 0) acc (loop (- n 1) (+ acc (sum-to 20)))))
+        (list (loop 500 0) (sum-to 300))
+        "#;
+
+    let interp = Interpreter::new();
+    let expected = interp.run_code(code, None).unwrap();
+
+    let interp = Interpreter::new();
+    let mut state = interp.run_code_with_budget(code, 1000).unwrap();
+    let mut n_resumed = 0;
+
+    let v = loop {
+        match state {
+            ExecState::Done(v) => break v,
+            ExecState::Suspended(s) => {
+                n_resumed += 1;
+                state = s.resume(1000).unwrap();
+            }
+        }
+    };
+
+    assert!(n_resumed > 10, "resumed only {} times", n_resumed);
+    assert_eq!(interp.format_value(&v), interp.format_value(&expected));
+    assert_eq!(interp.format_value(&v), "(105000 45150)");
+
+    let interp = Interpreter::new();
+
+    assert_matches!(interp.run_code_with_budget("(+ 1 2)", 100).unwrap(),
+        ExecState::Done(Value::Integer(ref i)) if i.to_u32() == Some(3));
+    assert_matches!(interp.run_code_with_budget("(+ 1 2)", 0).unwrap(),
+        ExecState::Suspended(_));
+
+    let state = interp.run_code_with_budget("(define (f n) (if (= n 0) (/ 1 n) (f (- n 1)))) (f 100)", 50)
+        .unwrap();
+
+    match state {
+        ExecState::Suspended(s) => assert_matches!(s.resume(1_000_000).unwrap_err(),
+            Error::ExecError(ExecError::DivideByZero)),
+        ExecState::Done(_) => panic!("expected suspended execution")
+    }
+}
+
+#[test]
+fn test_run_code_returning_all() {
+    let interp = Interpreter::new();
+
+    let values = interp.run_code_returning_all(r#"
+        (define x 2)
+        (* x 3)
+        (list x "y")
+        "#).unwrap();
+
+    let printed = values.iter().map(|v| interp.format_value(v)).collect::<Vec<_>>();
+    assert_eq!(printed, ["x", "6", r#"(2 "y")"#]);
+
+    assert!(interp.run_code_returning_all("").unwrap().is_empty());
+
+    assert_matches!(interp.run_code_returning_all("(define y 1) (/ y 0) (define z 2)").unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+    assert!(interp.get_value("y").is_some());
+    assert!(interp.get_value("z").is_none());
+}
+
+#[test]
+fn test_fork() {
+    let base = Interpreter::new();
+
+    base.run_code("
+        (use box (box unbox))
+        (use string (center))
+        (define shared 10)
+        (define (add-shared n) (+ n shared))
+        (define (make-adder n) (lambda (x) (+ x n shared)))
+        (define fns (list add-shared (make-adder 1)))
+        (define cell (box add-shared))
+        ", None).unwrap();
+
+    let a = base.fork();
+    let b = base.fork();
+
+    a.run_code("(define x 1) (define shared 11)", None).unwrap();
+    b.run_code("(define x 2) (define y 3)", None).unwrap();
+
+    let eval = |interp: &Interpreter, s: &str| interp.run_code(s, None)
+        .map(|v| interp.format_value(&v));
+
+    assert_eq!(eval(&a, "(list x shared (add-shared 0))").unwrap(), "(1 11 11)");
+    assert_eq!(eval(&b, "(list x y shared (add-shared 0))").unwrap(), "(2 3 10 10)");
+
+    // Functions held in values and closures built after forking are rebound
+    assert_eq!(eval(&a, "(list ((first fns) 0) ((last fns) 0) ((make-adder 2) 0))").unwrap(),
+        "(11 12 13)");
+    // Functions held in mutable values are shared with the original
+    assert_eq!(eval(&a, "((unbox cell) 0)").unwrap(), "10");
+
+    assert!(base.get_value("x").is_none());
+    assert!(a.get_value("y").is_none());
+    assert_eq!(eval(&base, "shared").unwrap(), "10");
+
+    // Builtins and modules imported before forking are available to each fork
+    for interp in &[&a, &b] {
+        assert_eq!(eval(interp, "(len (center \"a\" 3))").unwrap(), "3");
+    }
+
+    // Modules loaded by one fork are not visible to the others
+    let math = base.scope().add_name("math");
+    a.run_code("(use math (sqrt))", None).unwrap();
+    assert!(a.scope().modules().get_module(math).is_some());
+    assert!(b.scope().modules().get_module(math).is_none());
+    assert!(base.scope().modules().get_module(math).is_none());
+    assert_matches!(b.run_code("(sqrt 4.0)", None).unwrap_err(),
+        Error::ExecError(ExecError::NameError(_)));
+    b.run_code("(use math (sqrt))", None).unwrap();
+    assert_eq!(eval(&b, "(sqrt 4.0)").unwrap(), "2.0");
+
+    // Settings are independent
+    a.scope().set_fixed_integers(true);
+    assert!(!b.scope().fixed_integers());
+    assert!(!base.scope().fixed_integers());
+
+    // Functions defined before forking do not depend on the original
+    drop(base);
+    assert_eq!(eval(&a, "(list (add-shared 1) ((first fns) 1) ((make-adder 2) 1))").unwrap(),
+        "(12 12 14)");
+    assert_matches!(a.run_code("((unbox cell) 0)", None).unwrap_err(),
+        Error::ExecError(ExecError::ScopeDropped));
+}
+
+#[test]
+fn test_define_alias() {
+    let interp = Interpreter::new();
+
+    interp.define_alias("size", "len").unwrap();
+    interp.run_code("(define (length li) (len li))", None).unwrap();
+    interp.define_alias("count", "length").unwrap();
+
+    for f in &["len", "size", "length", "count"] {
+        let v = interp.run_code(&format!("(list ({0} '(1 2 3)) ({0} \"ab\"))", f), None)
+            .unwrap();
+        assert_eq!(interp.format_value(&v), "(3 2)");
+    }
+
+    // The alias keeps the value bound at the time of aliasing
+    interp.run_code("(define (length li) 0)", None).unwrap();
+    assert_eq!(interp.format_value(&interp.run_code("(count '(1))", None).unwrap()), "1");
+
+    assert_matches!(interp.define_alias("x", "no-such-fn").unwrap_err(),
+        Error::ExecError(ExecError::NameError(_)));
+    assert_matches!(interp.define_alias("if", "len").unwrap_err(),
+        Error::CompileError(CompileError::CannotDefine(_)));
+    assert!(interp.get_value("x").is_none());
+}
+
+#[test]
+fn test_disassemble() {
+    let interp = Interpreter::new();
+
+    interp.run_code("
+        (define (double n) (* n 2))
+        (define (quad n) (double (double n)))
+        (define (add-n n) (lambda (x) (+ x n)))
+        ", None).unwrap();
+
+    let s = interp.disassemble("quad").unwrap();
+    assert!(s.contains("1 positional argument total"));
+    assert!(s.contains("Call"));
+    assert!(s.contains("; double"));
+    assert!(s.contains("Return"));
+
+    let s = interp.disassemble("double").unwrap();
+    assert!(s.contains("; *"));
+    assert!(s.contains("Return"));
+
+    let l = interp.call("add-n", vec![1.into()]).unwrap();
+    interp.scope().add_value_with_name("add-1", |_| l);
+    let s = interp.disassemble("add-1").unwrap();
+    assert!(s.contains("1 enclosed value:"));
+
+    let code = interp.compile_single_expr("(quad 1)", None).unwrap();
+    let s = interp.disassemble_code(&code).unwrap();
+    assert!(s.contains("; quad"));
+
+    assert_matches!(interp.disassemble("no-such-fn").unwrap_err(),
+        Error::ExecError(ExecError::NameError(_)));
+    assert_matches!(interp.disassemble("len").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[test]
+fn test_load_prelude() {
+    let interp = Interpreter::new();
+
+    interp.load_prelude("
+        (define (inc n) (+ n 1))
+        (const step 2)
+        ").unwrap();
+
+    let v = interp.run_code("(inc (inc step))", None).unwrap();
+    assert_eq!(interp.format_value(&v), "4");
+
+    let interp = Interpreter::new();
+
+    let e = interp.load_prelude("(define (inc n) (+ n 1)) (inc 'a)").unwrap_err();
+    let trace = interp.take_traceback().unwrap();
+    assert!(interp.format_trace(&trace).starts_with("  In source <prelude>\n"));
+    assert_matches!(e, Error::ExecError(ExecError::TypeError{..}));
+
+    let e = interp.load_prelude("(define (inc n)").unwrap_err();
+    assert!(interp.format_error(&e).starts_with("<prelude>:1:15:parse error"));
+}
+
+#[test]
+fn test_io_provider() {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
+    use ketos::{Capabilities, IoError, IoProvider, RestrictConfig, RestrictError};
+    use ketos::io::IoMode;
+
+    #[derive(Default)]
+    struct MemoryIo {
+        files: RefCell<HashMap<PathBuf, Vec<u8>>>,
+    }
+
+    impl IoProvider for MemoryIo {
+        fn read_file(&self, path: &Path) -> Result<Vec<u8>, IoError> {
+            self.files.borrow().get(path).cloned()
+                .ok_or_else(|| IoError::new(IoMode::Open, path,
+                    io::Error::new(io::ErrorKind::NotFound, "no such file")))
+        }
+
+        fn write_file(&self, path: &Path, data: &[u8]) -> Result<(), IoError> {
+            self.files.borrow_mut().insert(path.to_owned(), data.to_vec());
+            Ok(())
+        }
+    }
+
+    let mem = Rc::new(MemoryIo::default());
+    mem.files.borrow_mut().insert(PathBuf::from("/virtual/config"), b"hello".to_vec());
+
+    let interp = Interpreter::new();
+    interp.set_io_provider(mem.clone());
+
+    let v = interp.run_code(r#"
+        (use file (read write))
+        (write #p"/virtual/out" (concat (read "/virtual/config") ", world"))
+        (read "/virtual/out")
+        "#, None).unwrap();
+    assert_eq!(interp.format_value(&v), r#""hello, world""#);
+    assert_eq!(mem.files.borrow()[Path::new("/virtual/out")], b"hello, world");
+
+    assert_matches!(interp.run_code(r#"(read "/missing")"#, None)
+        .unwrap_err(), Error::IoError(IoError{mode: IoMode::Open, ..}));
+
+    let interp = Builder::new()
+        .restrict(RestrictConfig{
+            capabilities: Capabilities::all() - Capabilities::FILES,
+            .. RestrictConfig::permissive()
+        })
+        .finish();
+    interp.set_io_provider(mem);
+
+    assert_matches!(interp.run_code(r#"(use file (read)) (read "/virtual/config")"#, None)
+        .unwrap_err(), Error::RestrictError(RestrictError::PermissionDenied));
+}
+
+#[test]
+fn test_define_constant_value() {
+    let interp = Interpreter::new();
+
+    interp.define_constant_value("version", 3.into()).unwrap();
+
+    let v = interp.run_code("
+        (const next-version (+ version 1))
+        (define (get) (* version 2))
+        (list next-version (get))
+        ", None).unwrap();
+    assert_eq!(interp.format_value(&v), "(4 6)");
+
+    assert_matches!(interp.run_code("(define version 4)", None).unwrap_err(),
+        Error::CompileError(CompileError::ConstantExists(_)));
+    assert_matches!(interp.run_code("(const version 4)", None).unwrap_err(),
+        Error::CompileError(CompileError::ConstantExists(_)));
+    assert_matches!(interp.define_constant_value("version", 4.into()).unwrap_err(),
+        Error::CompileError(CompileError::ConstantExists(_)));
+    assert_matches!(interp.define_constant_value("list", 4.into()).unwrap_err(),
+        Error::CompileError(CompileError::CannotDefine(_)));
+}
+
+#[test]
+fn test_exact_decimals() {
+    let interp = Builder::new().exact_decimals(true).finish();
+    let eval = |s| interp.run_single_expr(s, None).map(|v| interp.format_value(&v));
+
+    assert_eq!(eval("0.1").unwrap(), "1/10");
+    assert_eq!(eval("-1.25").unwrap(), "-5/4");
+    assert_eq!(eval("1_000.5e-2").unwrap(), "2001/200");
+    assert_eq!(eval("2.5e2").unwrap(), "250/1");
+    assert_eq!(eval("(= 0.1 (+ 0.05 0.05))").unwrap(), "true");
+    assert_eq!(eval("(= 0.3 (+ 0.1 0.2))").unwrap(), "true");
+    assert_eq!(eval("(type-of 0.5)").unwrap(), "ratio");
+    assert_eq!(eval("(type-of (+ 0.5 1.0))").unwrap(), "ratio");
+    assert_eq!(eval("(+ 0.5 (float 1))").unwrap(), "1.5");
+
+    // Exponents too large to represent exactly are rejected before scaling
+    assert!(eval("1.0e20000000").is_err());
+    assert!(eval("1.0e-20000000").is_err());
+
+    let interp = Builder::new()
+        .exact_decimals(true)
+        .restrict(RestrictConfig{
+            max_integer_size: 64,
+            .. RestrictConfig::permissive()
+        })
+        .finish();
+
+    assert_matches!(interp.run_single_expr("1.5e30", None).unwrap_err(),
+        Error::RestrictError(RestrictError::IntegerLimitExceeded));
+    assert!(interp.run_single_expr("1.5e10", None).is_ok());
+
+    let interp = Interpreter::new();
+    let eval = |s| interp.run_single_expr(s, None).map(|v| interp.format_value(&v));
+
+    assert_eq!(eval("(= 0.1 (+ 0.05 0.05))").unwrap(), "true");
+    assert_eq!(eval("(= 0.3 (+ 0.1 0.2))").unwrap(), "false");
+    assert_eq!(eval("(type-of 0.5)").unwrap(), "float");
+}
+
+#[test]
+fn test_fixed_integers() {
+    let interp = Builder::new().fixed_integers(true).finish();
+    let eval = |s| interp.run_single_expr(s, None).map(|v| interp.format_value(&v));
+
+    assert_eq!(eval("(+ 1 2)").unwrap(), "3");
+    assert_eq!(eval("(* -3 7)").unwrap(), "-21");
+    assert_eq!(eval("(- 10 (// 7 2))").unwrap(), "7");
+    assert_eq!(eval("(/ 3 6)").unwrap(), "1/2");
+    assert_eq!(eval("(+ 9223372036854775806 1)").unwrap(), "9223372036854775807");
+    assert_eq!(eval("(- -9223372036854775807 1)").unwrap(), "-9223372036854775808");
+    assert_eq!(eval("(^ 2 62)").unwrap(), "4611686018427387904");
+
+    assert_matches!(eval("(* 4294967296 4294967296)").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+    assert_matches!(eval("(+ 9223372036854775807 1)").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+    assert_matches!(eval("(^ 2 64)").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+    assert_matches!(eval("(<< 1 63)").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+
+    // Every operation producing an integer is checked
+    for expr in &[
+        "(- (- 0 9223372036854775807 1))",
+        "(abs (- 0 9223372036854775807 1))",
+        "(let ((n 9223372036854775807)) (+ n 1))",
+        "(let ((n (- 0 9223372036854775807 1))) (- n 1))",
+        "(int 1e30)",
+        "(int 36893488147419103232/3)",
+        "(numer 36893488147419103232/3)",
+    ] {
+        assert_matches!(eval(expr).unwrap_err(),
+            Error::ExecError(ExecError::Overflow), "{}", expr);
+    }
+
+    interp.run_code("(use math (factorial))", None).unwrap();
+    assert_matches!(eval("(factorial 30)").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+    assert_eq!(eval("(factorial 20)").unwrap(), "2432902008176640000");
+    assert_eq!(eval("(abs (- 0 9223372036854775807))").unwrap(), "9223372036854775807");
+
+    // Literals and values from Rust are checked as well
+    assert_matches!(eval("9223372036854775808").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+    assert_matches!(eval("0x10000000000000000").unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+
+    let big = ketos::Integer::from_u64(u64::MAX);
+    interp.run_code("(define (add-one n) (+ n 1))", None).unwrap();
+    assert_matches!(interp.call("add-one", vec![big.into()]).unwrap_err(),
+        Error::ExecError(ExecError::Overflow));
+
+    let interp = Interpreter::new();
+    let eval = |s| interp.run_single_expr(s, None).map(|v| interp.format_value(&v));
+
+    assert_eq!(eval("(* 4294967296 4294967296)").unwrap(), "18446744073709551616");
+}
+
+#[test]
+fn test_env() {
+    use ketos::Capabilities;
+
+    std::env::set_var("KETOS_TEST_ENV", "yes");
+    std::env::remove_var("KETOS_TEST_ENV_UNSET");
+
+    assert_eq!(run(r#"
+        (use env (get))
+        (get "KETOS_TEST_ENV")
+        (get "KETOS_TEST_ENV_UNSET")
+        "#).unwrap(), ["()", r#""yes""#, "()"]);
+
+    let interp = Builder::new()
+        .restrict(RestrictConfig{
+            env_vars: Some(vec!["KETOS_TEST_ENV".to_owned()]),
+            .. RestrictConfig::permissive()
+        })
+        .finish();
+
+    assert_eq!(interp.format_value(&interp.run_code(
+        r#"(use env (get)) (get "KETOS_TEST_ENV")"#, None).unwrap()), r#""yes""#);
+    assert_matches!(interp.run_code(r#"(get "KETOS_TEST_ENV_UNSET")"#, None)
+        .unwrap_err(), Error::RestrictError(RestrictError::PermissionDenied));
+
+    let interp = Builder::new()
+        .restrict(RestrictConfig{
+            capabilities: Capabilities::all() - Capabilities::ENV,
+            .. RestrictConfig::permissive()
+        })
+        .finish();
+
+    assert_matches!(interp.run_code(r#"(use env (get)) (get "KETOS_TEST_ENV")"#, None)
+        .unwrap_err(), Error::RestrictError(RestrictError::PermissionDenied));
+}
+
+#[test]
+fn test_form_callback() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let interp = Interpreter::new();
+    let values = Rc::new(RefCell::new(Vec::new()));
+
+    let v = values.clone();
+    interp.set_form_callback(move |value| v.borrow_mut().push(value.clone()));
+
+    let res = interp.run_code("(define x 2) (* x 3) (list x 'a)", None).unwrap();
+    assert_eq!(interp.format_value(&res), "(2 a)");
+
+    let formatted = values.borrow().iter()
+        .map(|v| interp.format_value(v)).collect::<Vec<_>>();
+    assert_eq!(formatted, ["x", "6", "(2 a)"]);
+
+    interp.clear_form_callback();
+    interp.run_code("(+ 1 2)", None).unwrap();
+    assert_eq!(values.borrow().len(), 3);
+}
+
+#[test]
+fn test_eval_catching() {
+    use ketos::ErrorValue;
+
+    let interp = Interpreter::new();
+
+    let v = interp.eval_catching("(define x 2) (+ x 1)");
+    assert_eq!(interp.format_value(&v), "3");
+
+    let v = interp.eval_catching("(/ x 0)");
+    assert_eq!(v.type_name(), "error");
+
+    match v {
+        Value::Foreign(ref fv) => {
+            let e = fv.downcast_ref::<ErrorValue>().unwrap();
+            assert_eq!(e.description(), "execution error");
+            assert_eq!(e.message(), "attempt to divide by zero");
+        }
+        ref v => panic!("expected error value; found {}", v.type_name())
+    }
+
+    assert_eq!(interp.format_value(&v),
+        r#"<error "execution error": "attempt to divide by zero">"#);
+
+    let v = interp.eval_catching("(+ 1");
+    assert_eq!(v.type_name(), "error");
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json() {
+    assert_eq!(run(r#"
+        (use json (parse stringify))
+        (define doc (parse "{\"name\": \"ketos\", \"tags\": [1, 2.5, null], \"meta\": {\"ok\": true}}"))
+        doc
+        (stringify doc)
+        (parse "null")
+        (stringify '(1 "two" #'3' four :five 6/4 ()))
+        "#).unwrap(), [
+            "()",
+            "doc",
+            r#"((:name "ketos") (:tags (1 2.5 ())) (:meta ((:ok true))))"#,
+            r#""{\"name\":\"ketos\",\"tags\":[1,2.5,null],\"meta\":{\"ok\":true}}""#,
+            "()",
+            r#""[1,\"two\",\"3\",\"four\",\"five\",1.5,null]""#,
+        ]);
+
+    assert_matches!(run(r#"(use json (parse)) (parse "[1,")"#).unwrap_err(),
+        Error::ExecError(ExecError::InvalidJson(_)));
+    assert_matches!(run("(use json (stringify)) (stringify (nan))").unwrap_err(),
+        Error::ExecError(ExecError::TypeError{..}));
+}
+
+#[cfg(not(feature = "json"))]
+#[test]
+fn test_json_unavailable() {
+    assert_matches!(run(r#"(use json (parse)) (parse "null")"#).unwrap_err(),
+        Error::ExecError(ExecError::FeatureUnavailable("json")));
+}
@@ -6,7 +6,7 @@ use std::rc::Rc;
 
 use ketos::{
     BuiltinModuleLoader, FileModuleLoader, ModuleLoader,
-    Context, Error, Interpreter, Value, run_code,
+    Context, DecodeError, Error, Interpreter, Value, run_code,
 };
 use ketos::encode::{read_bytecode, write_bytecode};
 use ketos::module::ModuleCode;
@@ -63,6 +63,7 @@ fn test_encode() {
 
         (const b #b"y halo")
         (const p #p"thar")
+        (define q '(:kw name))
         "#, |ctx| {
             assert_matches!(ctx.scope().get_named_constant("foo"),
                 Some(Value::Integer(ref i)) if i.to_u32() == Some(1));
@@ -72,6 +73,9 @@ fn test_encode() {
                 Some(Value::Bytes(ref b)) if b == b"y halo");
             assert_matches!(ctx.scope().get_named_constant("p"),
                 Some(Value::Path(ref p)) if p == Path::new("thar"));
+            assert_matches!(ctx.scope().get_named_value("q"),
+                Some(Value::List(ref li)) if matches!(&li[..],
+                    &[Value::Keyword(_), Value::Name(_)]));
         }).unwrap();
 }
 
@@ -107,3 +111,46 @@ fn test_docs() {
                 "#).unwrap();
         }).unwrap();
 }
+
+#[test]
+fn test_run_bytes() {
+    let interp = new_interpreter();
+
+    let bytes = interp.compile_to_bytes(r#"
+        (const offset 10)
+        (macro (twice e) `(* 2 ,e))
+        (define (foo n) (+ offset (twice n)))
+        (foo 3)
+        "#).unwrap();
+
+    // Compiling does not define anything in the interpreter scope
+    assert!(interp.get_value("foo").is_none());
+
+    let sec_interp = new_interpreter();
+
+    assert_matches!(sec_interp.run_bytes(&bytes).unwrap(),
+        Value::Integer(ref i) if i.to_u32() == Some(16));
+    assert_matches!(sec_interp.call("foo", vec![1.into()]).unwrap(),
+        Value::Integer(ref i) if i.to_u32() == Some(12));
+}
+
+#[test]
+fn test_run_bytes_bad_header() {
+    let interp = new_interpreter();
+    let bytes = interp.compile_to_bytes("(+ 1 2)").unwrap();
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[1] = b'X';
+
+    assert_matches!(interp.run_bytes(&bad_magic).unwrap_err(),
+        Error::DecodeError(DecodeError::IncorrectMagicNumber(_)));
+
+    let mut bad_version = bytes.clone();
+    bad_version[4] ^= 0xff;
+
+    assert_matches!(interp.run_bytes(&bad_version).unwrap_err(),
+        Error::DecodeError(DecodeError::IncorrectVersion(_)));
+
+    assert_matches!(interp.run_bytes(&bytes[..3]).unwrap_err(),
+        Error::IoError(_));
+}
@@ -3,9 +3,11 @@
 #[macro_use] extern crate ketos;
 #[macro_use] extern crate ketos_derive;
 
+use std::cell::{Cell, RefCell};
 use std::cmp::Ordering;
+use std::rc::Rc;
 
-use ketos::{Context, ExecError, Error, ForeignValue, Interpreter, Value};
+use ketos::{Context, DeepCopier, ExecError, Error, ForeignValue, Interpreter, Value};
 
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, FromValueRef, IntoValue)]
 pub struct MyType {
@@ -139,3 +141,156 @@ fn test_compare_foreign_value() {
     assert_eq!(eval(&interp, "(< (new-my-type 2) (new-my-type 1))").unwrap(), "false");
     assert_eq!(eval(&interp, "(> (new-my-type 2) (new-my-type 1))").unwrap(), "true");
 }
+
+#[derive(Debug)]
+pub struct MyBox {
+    value: RefCell<Value>,
+    copies: Rc<Cell<u32>>,
+    /// Whether `deep_copy` supports reference cycles
+    cycles: bool,
+}
+
+impl MyBox {
+    fn new_value(value: Value, copies: &Rc<Cell<u32>>, cycles: bool) -> Value {
+        Value::new_foreign(MyBox{
+            value: RefCell::new(value),
+            copies: copies.clone(),
+            cycles,
+        })
+    }
+
+    fn get(v: &Value) -> &MyBox {
+        match *v {
+            Value::Foreign(ref a) => a.downcast_ref::<MyBox>().unwrap(),
+            ref v => panic!("expected MyBox; found {:?}", v)
+        }
+    }
+}
+
+impl ForeignValue for MyBox {
+    fn type_name(&self) -> &'static str { "MyBox" }
+
+    fn deep_copy(&self, copier: &mut DeepCopier) -> Result<Option<Value>, ExecError> {
+        self.copies.set(self.copies.get() + 1);
+
+        if self.cycles {
+            let copy = MyBox::new_value(Value::Unit, &self.copies, true);
+            copier.set_copy(&copy);
+            *MyBox::get(&copy).value.borrow_mut() = copier.copy(&self.value.borrow())?;
+            Ok(Some(copy))
+        } else {
+            let value = copier.copy(&self.value.borrow())?;
+            Ok(Some(MyBox::new_value(value, &self.copies, false)))
+        }
+    }
+}
+
+#[test]
+fn test_copy_foreign_value() {
+    let interp = Interpreter::new();
+    let copies = Rc::new(Cell::new(0));
+
+    let b = MyBox::new_value(Value::from(vec![Value::from(1), Value::from("a")]), &copies, true);
+
+    interp.scope().add_named_value("my-box", b.clone());
+
+    // A box referenced twice is copied once
+    let v = interp.run_code("(use box (copy)) (copy (list my-box '(1 2) my-box))", None).unwrap();
+    assert_eq!(copies.get(), 1);
+
+    let li = match v {
+        Value::List(ref li) => li,
+        ref v => panic!("expected list; found {:?}", v)
+    };
+
+    *MyBox::get(&b).value.borrow_mut() = Value::Unit;
+
+    assert_eq!(interp.format_value(&MyBox::get(&li[0]).value.borrow()), r#"(1 "a")"#);
+    assert!(std::ptr::eq(MyBox::get(&li[0]), MyBox::get(&li[2])));
+
+    // A box which contains itself is copied into a box which contains the copy
+    *MyBox::get(&b).value.borrow_mut() = Value::from(vec![b.clone()]);
+
+    let c = b.deep_copy().unwrap();
+    assert_eq!(copies.get(), 2);
+
+    match *MyBox::get(&c).value.borrow() {
+        Value::List(ref li) => assert!(std::ptr::eq(MyBox::get(&li[0]), MyBox::get(&c))),
+        ref v => panic!("expected list; found {:?}", v)
+    };
+
+    // Break the cycles so the boxes are freed
+    *MyBox::get(&b).value.borrow_mut() = Value::Unit;
+    *MyBox::get(&c).value.borrow_mut() = Value::Unit;
+
+    // A cycle through a value without support for cycles is an error
+    let d = MyBox::new_value(Value::Unit, &copies, false);
+    *MyBox::get(&d).value.borrow_mut() = Value::from(vec![d.clone()]);
+
+    assert_matches!(d.deep_copy().unwrap_err(), ExecError::ReferenceCycle);
+    *MyBox::get(&d).value.borrow_mut() = Value::Unit;
+}
+
+/// Fixed-point number with three decimal places
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    fn operand(v: &Value) -> Option<Fixed> {
+        match *v {
+            Value::Foreign(ref a) => a.downcast_ref::<Fixed>().cloned(),
+            Value::Integer(ref i) => i.to_i64().map(|i| Fixed(i * 1000)),
+            _ => None
+        }
+    }
+}
+
+impl ForeignValue for Fixed {
+    fn type_name(&self) -> &'static str { "Fixed" }
+
+    fn add_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> {
+        Fixed::operand(rhs).map(|rhs| Ok(Value::new_foreign(Fixed(self.0 + rhs.0))))
+    }
+
+    fn mul_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> {
+        Fixed::operand(rhs).map(|rhs| Ok(Value::new_foreign(Fixed(self.0 * rhs.0 / 1000))))
+    }
+
+    fn div_value(&self, rhs: &Value) -> Option<Result<Value, ExecError>> {
+        Fixed::operand(rhs).map(|rhs| if rhs.0 == 0 {
+            Err(ExecError::DivideByZero)
+        } else {
+            Ok(Value::new_foreign(Fixed(self.0 * 1000 / rhs.0)))
+        })
+    }
+}
+
+#[test]
+fn test_foreign_arithmetic() {
+    let interp = Interpreter::new();
+
+    interp.scope().add_named_value("fp1", Value::new_foreign(Fixed(1500)));
+    interp.scope().add_named_value("fp2", Value::new_foreign(Fixed(250)));
+
+    let fixed = |input: &str| match interp.run_single_expr(input, None) {
+        Ok(Value::Foreign(ref a)) => *a.downcast_ref::<Fixed>().unwrap(),
+        r => panic!("expected Fixed; found {:?}", r)
+    };
+
+    assert_eq!(fixed("(+ fp1 fp2)"), Fixed(1750));
+    assert_eq!(fixed("(+ fp1 fp2 2)"), Fixed(3750));
+    assert_eq!(fixed("(* fp1 fp2)"), Fixed(375));
+    assert_eq!(fixed("(/ fp1 fp2)"), Fixed(6000));
+    assert_eq!(fixed("(+ fp1)"), Fixed(1500));
+
+    assert_matches!(interp.run_single_expr("(/ fp1 0)", None).unwrap_err(),
+        Error::ExecError(ExecError::DivideByZero));
+
+    // Unsupported operations and operands raise the usual errors
+    assert_matches!(interp.run_single_expr("(- fp1 fp2)", None).unwrap_err(),
+        Error::ExecError(ExecError::TypeMismatch{lhs: "Fixed", rhs: "Fixed"}));
+    assert_matches!(interp.run_single_expr("(+ fp1 1.0)", None).unwrap_err(),
+        Error::ExecError(ExecError::TypeMismatch{lhs: "Fixed", rhs: "float"}));
+    assert_matches!(interp.run_single_expr("(+ 1 fp1)", None).unwrap_err(),
+        Error::ExecError(ExecError::TypeError{expected: "number", ..}));
+}
@@ -1,10 +1,11 @@
 #[macro_use] extern crate assert_matches;
 extern crate ketos;
 
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use ketos::{
     Builder,
+    Capabilities,
     Error,
     RestrictConfig,
     RestrictError,
@@ -39,6 +40,110 @@ fn test_restrict_time() {
         RestrictError::ExecutionTimeExceeded);
 }
 
+#[test]
+fn test_restrict_time_deadline() {
+    let cfg = RestrictConfig{
+        execution_time: Some(Duration::from_millis(50)),
+        .. RestrictConfig::permissive()
+    };
+
+    let start = Instant::now();
+
+    assert_matches_re!(run(cfg.clone(), "
+        (define (spin n) (spin (+ n 1)))
+        (spin 0)
+        ").unwrap_err(),
+        RestrictError::ExecutionTimeExceeded);
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    run(cfg, "
+        (define (count n) (if (= n 0) 0 (count (- n 1))))
+        (count 1000)
+        ").unwrap();
+}
+
+#[test]
+fn test_restrict_steps() {
+    assert_matches_re!(run(
+        RestrictConfig{
+            max_steps: Some(1000),
+            .. RestrictConfig::permissive()
+        },
+        "
+        (define (foo) (foo))
+        (foo)
+        ").unwrap_err(),
+        RestrictError::StepLimitExceeded);
+
+    // The count is reset for each top-level expression
+    run(RestrictConfig{
+            max_steps: Some(1000),
+            .. RestrictConfig::permissive()
+        },
+        "
+        (define (count n) (if (= n 0) 0 (count (- n 1))))
+        (count 50)
+        (count 50)
+        ").unwrap();
+
+    // Code called back from a system function counts toward the same limit
+    assert_matches_re!(run(
+        RestrictConfig{
+            max_steps: Some(1000),
+            .. RestrictConfig::permissive()
+        },
+        "
+        (use struct (update-in))
+        (struct counter ((n integer)))
+        (define (burn n) (if (= n 0) 0 (burn (- n 1))))
+        (define (loop c i)
+          (if (= i 0) c
+            (loop (update-in c '(:n) (lambda (n) (+ n (burn 100)))) (- i 1))))
+        (loop (new counter :n 0) 20)
+        ").unwrap_err(),
+        RestrictError::StepLimitExceeded);
+}
+
+#[test]
+fn test_eval_with_timeout_and_steps() {
+    let interp = Builder::new().finish();
+
+    interp.run_code("
+        (define (spin n) (spin (+ n 1)))
+        (define (count n) (if (= n 0) 0 (count (- n 1))))
+        ", None).unwrap();
+
+    // Step limit is reached first
+    let start = Instant::now();
+    assert_matches_re!(interp.eval_with_timeout_and_steps(
+            "(spin 0)", Duration::from_secs(60), 10_000).unwrap_err(),
+        RestrictError::StepLimitExceeded);
+    assert!(start.elapsed() < Duration::from_secs(5));
+
+    // Deadline is reached first
+    assert_matches_re!(interp.eval_with_timeout_and_steps(
+            "(spin 0)", Duration::from_millis(50), u64::max_value()).unwrap_err(),
+        RestrictError::ExecutionTimeExceeded);
+
+    // Neither limit is reached
+    let v = interp.eval_with_timeout_and_steps(
+        "(count 10) (+ 1 2)", Duration::from_secs(60), 10_000).unwrap();
+    assert_eq!(interp.format_value(&v), "3");
+
+    // Steps are counted across all expressions of the input
+    assert_matches_re!(interp.eval_with_timeout_and_steps(
+            "(count 100) (count 100) (count 100)", Duration::from_secs(60), 1000).unwrap_err(),
+        RestrictError::StepLimitExceeded);
+    interp.eval_with_timeout_and_steps(
+        "(count 100)", Duration::from_secs(60), 1000).unwrap();
+
+    // Prior settings are unchanged afterward
+    assert!(interp.context().restrict().max_steps.is_none());
+    assert!(interp.context().restrict().execution_time.is_none());
+    interp.run_code("(count 10000)", None).unwrap();
+}
+
 #[test]
 fn test_restrict_stack() {
     assert_matches_re!(run(
@@ -53,6 +158,15 @@ fn test_restrict_stack() {
         ").unwrap_err(),
         RestrictError::CallStackExceeded);
 
+    // Recursion through the body of `dynamic-let` is limited in the same way
+    assert_matches_re!(run(RestrictConfig::permissive(), "
+        (define (f n)
+          (dynamic-let ((*print-readably* true))
+            (if (= n 0) 0 (f (- n 1)))))
+        (f 100000)
+        ").unwrap_err(),
+        RestrictError::CallStackExceeded);
+
     assert_matches_re!(run(
         RestrictConfig{
             value_stack_size: 100,
@@ -137,11 +251,29 @@ fn test_restrict_integer() {
         ").unwrap_err(),
         RestrictError::IntegerLimitExceeded);
 
+    assert_matches_re!(run(cfg.clone(), "
+        (use math (expt))
+        (expt 2 1_000_000)
+        ").unwrap_err(),
+        RestrictError::IntegerLimitExceeded);
+
+    assert_matches_re!(run(cfg.clone(), "
+        (use math (expt))
+        (expt 3 -1_000_000)
+        ").unwrap_err(),
+        RestrictError::IntegerLimitExceeded);
+
     assert_matches_re!(run(cfg.clone(), "
         (<< 1 1_000_000)
         ").unwrap_err(),
         RestrictError::IntegerLimitExceeded);
 
+    assert_matches_re!(run(cfg.clone(), "
+        (use math (factorial))
+        (factorial 1_000)
+        ").unwrap_err(),
+        RestrictError::IntegerLimitExceeded);
+
     assert_matches_re!(run(cfg.clone(), "
         (define (foo n) (foo (* n 2)))
         (foo 1)
@@ -170,3 +302,36 @@ fn test_restrict_syntax() {
         ").unwrap_err(),
         RestrictError::MaxSyntaxNestingExceeded);
 }
+
+#[test]
+fn test_restrict_capabilities() {
+    let cfg = RestrictConfig{
+        capabilities: Capabilities::all() - Capabilities::IO,
+        .. RestrictConfig::permissive()
+    };
+
+    assert_matches_re!(run(cfg.clone(), r#"
+        (println "hello")
+        "#).unwrap_err(),
+        RestrictError::PermissionDenied);
+
+    run(cfg.clone(), r#"
+        (use code (compile))
+        ((compile '(+ 1 2)))
+        "#).unwrap();
+
+    let cfg = RestrictConfig{
+        capabilities: Capabilities::IO,
+        .. RestrictConfig::permissive()
+    };
+
+    assert_matches_re!(run(cfg.clone(), "
+        (use code (compile))
+        (compile '(+ 1 2))
+        ").unwrap_err(),
+        RestrictError::PermissionDenied);
+
+    run(cfg, r#"
+        (format "~a" 1)
+        "#).unwrap();
+}
@@ -2,22 +2,24 @@ extern crate ketos;
 
 use ketos::{
     BuiltinModuleLoader, FileModuleLoader, ModuleLoader,
-    Error, Interpreter,
+    CompileWarning, Error, Interpreter,
 };
+use ketos::name::display_names;
 
 use std::fs::read_dir;
 use std::path::{Path, PathBuf};
 
-fn run_file(path: &Path) -> Result<(), Error> {
+fn new_interpreter() -> Interpreter {
     let mut loader = FileModuleLoader::with_search_paths(vec![PathBuf::from("lib")]);
 
     loader.set_read_bytecode(false);
     loader.set_write_bytecode(false);
 
-    let interp = Interpreter::with_loader(
-        Box::new(BuiltinModuleLoader.chain(loader)));
+    Interpreter::with_loader(Box::new(BuiltinModuleLoader.chain(loader)))
+}
 
-    interp.run_file(path)
+fn run_file(path: &Path) -> Result<(), Error> {
+    new_interpreter().run_file(path)
 }
 
 // Runs all the tests matching `lib/test-*.ket`
@@ -35,3 +37,132 @@ fn test_run() {
         }
     }
 }
+
+#[test]
+fn test_list_sum_error() {
+    let interp = new_interpreter();
+
+    let err = interp.run_code(r#"(use list (sum)) (sum '(1 "two" 3))"#, None).unwrap_err();
+
+    assert_eq!(interp.format_error(&err),
+        r#"execution error: type error: expected number; found string: "two""#);
+}
+
+#[test]
+fn test_list_transpose_error() {
+    let interp = new_interpreter();
+
+    let err = interp.run_code("(use list (transpose)) (transpose '((1 2) (3)))", None).unwrap_err();
+
+    assert_eq!(interp.format_error(&err),
+        "execution error: panic: `transpose` got rows of differing lengths");
+}
+
+#[test]
+fn test_list_index_error() {
+    let interp = new_interpreter();
+
+    interp.run_code("(use list (insert remove-at splice))", None).unwrap();
+
+    for (code, msg) in &[
+            ("(insert 4 'x '(a b c))", "`insert` index out of bounds: 4"),
+            ("(insert -1 'x '(a b c))", "`insert` index out of bounds: -1"),
+            ("(remove-at 3 '(a b c))", "`remove-at` index out of bounds: 3"),
+            ("(remove-at 0 ())", "`remove-at` index out of bounds: 0"),
+            ("(splice 2 4 () '(a b c))", "`splice` index out of bounds: 4"),
+            ("(splice 2 1 () '(a b c))", "`splice` index out of bounds: 2")] {
+        let err = interp.run_code(code, None).unwrap_err();
+
+        assert_eq!(interp.format_error(&err), format!("execution error: panic: {}", msg));
+    }
+}
+
+#[test]
+fn test_list_extreme_by_error() {
+    let interp = new_interpreter();
+
+    interp.run_code("(use list (max-by min-by))", None).unwrap();
+
+    let err = interp.run_code("(max-by len ())", None).unwrap_err();
+    assert_eq!(interp.format_error(&err), "execution error: panic: `max-by` got empty list");
+
+    let err = interp.run_code("(min-by len ())", None).unwrap_err();
+    assert_eq!(interp.format_error(&err), "execution error: panic: `min-by` got empty list");
+
+    // Keys must be comparable
+    let err = interp.run_code("(max-by id '(1 \"a\"))", None).unwrap_err();
+    assert_eq!(interp.format_error(&err),
+        "execution error: type mismatch; string and integer");
+}
+
+#[test]
+fn test_list_frequencies_error() {
+    let interp = new_interpreter();
+
+    interp.run_code("(use list (frequencies))", None).unwrap();
+
+    let err = interp.run_code("(frequencies '(1 2.5 1))", None).unwrap_err();
+    assert_eq!(interp.format_error(&err),
+        "execution error: panic: `frequencies` got float element 2.5");
+}
+
+#[test]
+fn test_list_frange_error() {
+    let interp = new_interpreter();
+
+    interp.run_code("(use list (frange))", None).unwrap();
+
+    let err = interp.run_code("(frange 0.0 1.0 0.0)", None).unwrap_err();
+    assert_eq!(interp.format_error(&err), "execution error: panic: `frange` got 0 step");
+}
+
+#[test]
+fn test_list_sort_error() {
+    let interp = new_interpreter();
+
+    interp.run_code("(use list (sort))", None).unwrap();
+
+    let err = interp.run_code("(sort '(1 2) :up)", None).unwrap_err();
+    assert_eq!(interp.format_error(&err),
+        "execution error: panic: `sort` got unrecognized order :up");
+}
+
+#[test]
+fn test_shadow_builtin_warning() {
+    let interp = new_interpreter();
+
+    interp.run_code("
+        (use list (filter))
+        (define (map fn li) li)
+        (define sum 0)
+        ", None).unwrap();
+
+    let warnings = interp.scope().take_diagnostics();
+    let names = interp.scope().borrow_names();
+
+    let msgs = warnings.iter()
+        .map(|w| display_names(&names, w).to_string())
+        .collect::<Vec<_>>();
+
+    assert_eq!(msgs, [
+        "definition of `map` shadows builtin from module `list`",
+        "definition of `sum` shadows builtin from module `list`",
+    ]);
+    assert!(matches!(warnings[0], CompileWarning::ShadowBuiltin{..}));
+
+    drop(names);
+    assert!(interp.scope().take_diagnostics().is_empty());
+}
+
+#[test]
+fn test_shadow_builtin_local() {
+    let interp = new_interpreter();
+
+    interp.run_code("
+        (use list (filter))
+        (let ((map 1)) map)
+        (define (foo map) map)
+        ", None).unwrap();
+
+    assert!(interp.scope().take_diagnostics().is_empty());
+}
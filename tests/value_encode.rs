@@ -4,12 +4,13 @@ extern crate ketos;
 extern crate serde;
 #[macro_use] extern crate serde_derive;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
 
 use ketos::{
     BuiltinModuleLoader, FileModuleLoader, ModuleLoader,
-    Error, Interpreter, decode_value, encode_value,
+    DecodeOptions, DuplicateKeyPolicy, Error, ExecError, Interpreter,
+    decode_value, decode_value_seed, decode_value_with, encode_value,
 };
 
 macro_rules! map {
@@ -206,6 +207,17 @@ macro_rules! de {
     } }
 }
 
+macro_rules! de_with {
+    ( $opts:expr ; $ty:ty => $e:expr ) => { {
+        let interp = interp(&format!("
+            (define (make) '{})
+            ", $e)).unwrap();
+
+        decode_value_with::<$ty>(interp.scope(),
+            &interp.call("make", vec![]).unwrap(), &$opts)
+    } }
+}
+
 #[test]
 fn test_primitive() {
     assert_eq!(de!((u32, String) => r#"(1 "foo")"#).unwrap(),
@@ -255,3 +267,211 @@ fn test_error() {
     assert!(de!(BTreeMap<u32, u32> => "((0 1) (1 2 3))").is_err());
     assert!(de!(Vec<u32> => "(1 2 ())").is_err());
 }
+
+#[derive(Debug, Eq, PartialEq, Deserialize)]
+enum Shape {
+    Rect{w: u32, h: u32},
+    Circle{r: u32, #[serde(default)] filled: bool},
+}
+
+#[test]
+fn test_struct_variant() {
+    assert_eq!(de!(Shape => "(Rect (:w 1 :h 2))").unwrap(),
+        Shape::Rect{w: 1, h: 2});
+    assert_eq!(de!(Shape => "(Rect (h 2 w 1))").unwrap(),
+        Shape::Rect{w: 1, h: 2});
+    assert_eq!(de!(Shape => "(Circle (:r 3))").unwrap(),
+        Shape::Circle{r: 3, filled: false});
+    assert_eq!(de!(Vec<Shape> => "((Circle (:filled true :r 3)) (Rect (:w 1 :h 2)))").unwrap(),
+        [Shape::Circle{r: 3, filled: true}, Shape::Rect{w: 1, h: 2}]);
+
+    assert!(de!(Shape => "(Rect (:w 1))").is_err());
+    assert!(de!(Shape => "(Rect (:w 1 :h 2) ())").is_err());
+
+    // The form produced by `encode_value` names the enum type
+    assert_eq!(de!(Shape => "(Shape Rect (:h 2 :w 1))").unwrap(),
+        Shape::Rect{w: 1, h: 2});
+    assert_eq!(de!(Shape => "(Shape Circle (:r 3))").unwrap(),
+        Shape::Circle{r: 3, filled: false});
+    assert_eq!(de!(Vec<Enum> => "((Enum Beta (1)) (Enum Alpha ()) (Alpha) Alpha)").unwrap(),
+        [Enum::Beta(1), Enum::Alpha, Enum::Alpha, Enum::Alpha]);
+    assert!(de!(Enum => "(Enum Beta (1 2))").is_err());
+    assert!(de!(Enum => "(Enum Alpha (1))").is_err());
+}
+
+#[derive(Debug, Eq, PartialEq, Deserialize)]
+struct Partial {
+    a: u32,
+    #[serde(skip)]
+    b: u32,
+    c: Vec<u32>,
+}
+
+#[test]
+fn test_ignored_fields() {
+    assert_eq!(de!(Partial => "(Partial (:a 1 :x ((1 2) (3 (4))) :c (5 6)))").unwrap(),
+        Partial{a: 1, b: 0, c: vec![5, 6]});
+    assert_eq!(de!(Partial => "(Partial (:x (StructA (:a 1)) :a 1 :b 2 :c ()))").unwrap(),
+        Partial{a: 1, b: 0, c: vec![]});
+    assert_eq!(de!(Vec<Partial> => "((Partial (:y (()) :a 1 :c ())) (Partial (:a 2 :c (3))))").unwrap(),
+        [Partial{a: 1, b: 0, c: vec![]}, Partial{a: 2, b: 0, c: vec![3]}]);
+}
+
+#[test]
+fn test_tuple_edge_cases() {
+    assert_eq!(de!(() => "()").unwrap(), ());
+    assert_eq!(de!(Vec<(i32, i32)> => "()").unwrap(), []);
+    assert_eq!(de!((i64, i64) => "6/4").unwrap(), (3, 2));
+    assert_eq!(de!((i64, u8) => "-1/3").unwrap(), (-1, 3));
+    assert_eq!(de!((i64, i64) => "(1 2)").unwrap(), (1, 2));
+
+    assert!(de!((i64, i64, i64) => "1/2").is_err());
+    assert!(de!((i64, i64) => "100000000000000000000/3").is_err());
+}
+
+#[test]
+fn test_lenient_string() {
+    let lenient = DecodeOptions{lenient: true, ..DecodeOptions::default()};
+
+    assert_eq!(de_with!(lenient; StructA => "(StructA (:a 1 :b #'x' :c 2))").unwrap(),
+        StructA{a: 1, b: 'x', c: "2".to_owned()});
+    assert_eq!(de_with!(lenient; StructA => "(StructA (:a 1 :b #'x' :c true))").unwrap(),
+        StructA{a: 1, b: 'x', c: "true".to_owned()});
+    assert_eq!(de_with!(lenient; String => "1.5").unwrap(), "1.5");
+    assert_eq!(de_with!(lenient; String => ":foo").unwrap(), "foo");
+    assert!(de_with!(lenient; String => "(1)").is_err());
+
+    assert!(de!(StructA => "(StructA (:a 1 :b #'x' :c 2))").is_err());
+    assert!(de!(StructA => "(StructA (:a 1 :b #'x' :c true))").is_err());
+    assert!(de_with!(DecodeOptions::default(); String => "1.5").is_err());
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let policy = |duplicate_keys| DecodeOptions{duplicate_keys, ..DecodeOptions::default()};
+
+    let first = policy(DuplicateKeyPolicy::FirstWins);
+    let last = policy(DuplicateKeyPolicy::LastWins);
+
+    assert_eq!(de_with!(first; BTreeMap<String, u32> => r#"(("a" 1) ("b" 2) ("a" 3))"#).unwrap(),
+        map!("a".to_owned() => 1, "b".to_owned() => 2));
+    assert_eq!(de_with!(last; BTreeMap<String, u32> => r#"(("a" 1) ("b" 2) ("a" 3))"#).unwrap(),
+        map!("a".to_owned() => 3, "b".to_owned() => 2));
+    assert!(de!(BTreeMap<String, u32> => r#"(("a" 1) ("b" 2) ("a" 3))"#).is_err());
+
+    assert_eq!(de_with!(first; StructA => r#"(StructA (:a 1 :b #'x' :a 2 :c "c"))"#).unwrap(),
+        StructA{a: 1, b: 'x', c: "c".to_owned()});
+    assert_eq!(de_with!(last; StructA => r#"(StructA (:a 1 :b #'x' :a 2 :c "c"))"#).unwrap(),
+        StructA{a: 2, b: 'x', c: "c".to_owned()});
+    assert!(matches!(de!(StructA => r#"(StructA (:a 1 :b #'x' :a 2 :c "c"))"#).unwrap_err(),
+        Error::ExecError(ExecError::DuplicateField(_))));
+
+    assert_eq!(de_with!(first; Shape => "(Rect (:w 1 :h 2 :w 3))").unwrap(),
+        Shape::Rect{w: 1, h: 2});
+    assert!(de!(Shape => "(Rect (:w 1 :h 2 :w 3))").is_err());
+
+    // Keys without an ordered form are compared structurally
+    assert_eq!(de_with!(last; BTreeMap<Vec<i32>, u32> => "(((1 2) 1) ((3) 2) ((1 2) 3))").unwrap(),
+        map!(vec![1, 2] => 3, vec![3] => 2));
+    assert!(de!(BTreeMap<Vec<i32>, u32> => "(((1 2) 1) ((3) 2) ((1 2) 3))").is_err());
+    assert!(de!(BTreeMap<i32, u32> => "((1 1) (2 2) (1 3))").is_err());
+}
+
+#[test]
+fn test_positional_struct() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Point(i32, String);
+
+    let positional = DecodeOptions{positional_structs: true, ..DecodeOptions::default()};
+
+    let interp = interp(r#"
+        (struct Point ((x integer) (label string)))
+        (define (make) (new Point :label "origin" :x 3))
+        "#).unwrap();
+    let v = interp.call("make", vec![]).unwrap();
+
+    assert_eq!(decode_value_with::<(i32, String)>(interp.scope(), &v, &positional).unwrap(),
+        (3, "origin".to_owned()));
+    assert_eq!(decode_value_with::<Point>(interp.scope(), &v, &positional).unwrap(),
+        Point(3, "origin".to_owned()));
+
+    assert!(decode_value_with::<(String, i32)>(interp.scope(), &v, &positional).is_err());
+    assert!(decode_value_with::<(i32, String, i32)>(interp.scope(), &v, &positional).is_err());
+    assert!(decode_value::<(i32, String)>(interp.scope(), &v).is_err());
+
+    // Lists still decode as usual
+    assert_eq!(de_with!(positional; (i32, String) => r#"(1 "a")"#).unwrap(),
+        (1, "a".to_owned()));
+}
+
+#[test]
+fn test_keyword_map_keys() {
+    let mut expected = HashMap::new();
+    expected.insert("a".to_owned(), 1);
+    expected.insert("b".to_owned(), 2);
+
+    assert_eq!(de!(HashMap<String, i32> => "(:a 1 :b 2)").unwrap(), expected);
+    assert_eq!(de!(HashMap<String, i32> => "((:a 1) (:b 2))").unwrap(), expected);
+    assert_eq!(de!(HashMap<String, i32> => "((a 1) (b 2))").unwrap(), expected);
+    assert_eq!(de!(HashMap<String, i32> => r#"(("a" 1) ("b" 2))"#).unwrap(), expected);
+    assert_eq!(de!(HashMap<String, i32> => "()").unwrap(), HashMap::new());
+
+    assert!(de!(HashMap<String, i32> => "(:a 1 :b)").is_err());
+    assert!(de!(HashMap<String, i32> => "(:a 1 :a 2)").is_err());
+    assert!(de!(HashMap<u32, i32> => "(:a 1)").is_err());
+}
+
+#[test]
+fn test_decode_seed() {
+    use serde::de::{self, Deserialize, DeserializeSeed, Deserializer};
+
+    struct AtMost(u32);
+
+    impl<'de> DeserializeSeed<'de> for AtMost {
+        type Value = u32;
+
+        fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<u32, D::Error> {
+            let n = u32::deserialize(d)?;
+
+            if n > self.0 {
+                Err(de::Error::custom(format!("{} exceeds maximum {}", n, self.0)))
+            } else {
+                Ok(n)
+            }
+        }
+    }
+
+    let interp = interp("").unwrap();
+    let v = interp.run_code("10", None).unwrap();
+
+    assert_eq!(decode_value_seed(interp.scope(), &v, AtMost(10)).unwrap(), 10);
+    assert_eq!(decode_value_seed(interp.scope(), &v, AtMost(100)).unwrap(), 10);
+
+    let e = decode_value_seed(interp.scope(), &v, AtMost(9)).unwrap_err();
+    assert_eq!(interp.format_error(&e), "execution error: panic: 10 exceeds maximum 9");
+
+    let s = interp.run_code(r#""ten""#, None).unwrap();
+    assert!(decode_value_seed(interp.scope(), &s, AtMost(100)).is_err());
+}
+
+#[test]
+fn test_scope_handle() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Tagged {
+        tag: String,
+        n: u32,
+    }
+
+    let interp = interp("").unwrap();
+    let v = interp.run_code("'(Tagged (:tag :some-tag :n 1))", None).unwrap();
+    let kw = interp.run_code(":other-tag", None).unwrap();
+    let scope = interp.scope_handle();
+
+    drop(interp);
+
+    let t: Tagged = decode_value(&scope, &v).unwrap();
+    assert_eq!(t, Tagged{tag: "some-tag".to_owned(), n: 1});
+
+    let s: String = decode_value(&scope, &kw).unwrap();
+    assert_eq!(s, "other-tag");
+}